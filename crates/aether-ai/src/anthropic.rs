@@ -2,7 +2,7 @@
 
 use aether_core::{
     AetherError, AiProvider, ProviderConfig, Result,
-    provider::{GenerationRequest, GenerationResponse},
+    provider::{DEFAULT_ANTHROPIC_MODEL, GenerationRequest, GenerationResponse, PromptPreview},
     SlotKind,
 };
 use async_trait::async_trait;
@@ -11,12 +11,17 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, instrument};
 
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+/// Default `anthropic-version`, overridable with `ProviderConfig::with_api_version`.
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
+/// Claude models reject temperatures above 1.0, so higher values are clamped.
+const MAX_TEMPERATURE: f32 = 1.0;
+
 /// Anthropic Claude provider for code generation.
 #[derive(Debug, Clone)]
 pub struct AnthropicProvider {
     client: Client,
+    stream_client: Client,
     config: ProviderConfig,
 }
 
@@ -31,6 +36,14 @@ struct MessageRequest {
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<RequestMetadata>,
+}
+
+/// Request metadata; `user_id` identifies the end user for abuse monitoring.
+#[derive(Debug, Serialize)]
+struct RequestMetadata {
+    user_id: String,
 }
 
 /// Anthropic streaming response event (minimal)
@@ -61,6 +74,10 @@ struct Message {
 struct MessageResponse {
     content: Vec<ContentBlock>,
     usage: Usage,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    stop_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,13 +94,18 @@ struct Usage {
 impl AnthropicProvider {
     /// Create a new Anthropic provider.
     pub fn new(config: ProviderConfig) -> Result<Self> {
-        let timeout = config.timeout_seconds.unwrap_or(60);
         let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(timeout))
+            .timeout(config.request_timeout())
+            .user_agent(config.user_agent())
+            .build()
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+        let stream_client = Client::builder()
+            .timeout(config.stream_timeout())
+            .user_agent(config.user_agent())
             .build()
             .map_err(|e| AetherError::NetworkError(e.to_string()))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, stream_client, config })
     }
 
     /// Create a provider from environment variables.
@@ -94,7 +116,7 @@ impl AnthropicProvider {
             .map_err(|_| AetherError::ConfigError("ANTHROPIC_API_KEY not set".to_string()))?;
 
         let model = std::env::var("ANTHROPIC_MODEL")
-            .unwrap_or_else(|_| "claude-opus-4-5".to_string());
+            .unwrap_or_else(|_| DEFAULT_ANTHROPIC_MODEL.to_string());
 
         let config = ProviderConfig::new(api_key, model);
         Self::new(config)
@@ -110,7 +132,7 @@ impl AnthropicProvider {
     }
 
     /// Build the system prompt for code generation.
-    fn build_system_prompt(&self, kind: &SlotKind, context: Option<&str>) -> String {
+    fn build_system_prompt(&self, kind: &SlotKind, kind_instruction: Option<&str>, context: Option<&str>) -> String {
         let base = "You are a code generation assistant. Generate only the requested code without explanations or markdown code blocks. Output raw code only.";
 
         let kind_specific = match kind {
@@ -120,9 +142,15 @@ impl AnthropicProvider {
             SlotKind::Function => "\nGenerate a complete function definition.",
             SlotKind::Class => "\nGenerate a complete class/struct definition.",
             SlotKind::Component => "\nGenerate a complete component with HTML, CSS, and JavaScript as needed.",
+            SlotKind::Json => "\nGenerate a single valid JSON value.",
             _ => "",
         };
 
+        // Custom kinds carry their instruction from the engine's kind registry
+        let kind_specific = kind_instruction
+            .map(|i| format!("\n{}", i))
+            .unwrap_or_else(|| kind_specific.to_string());
+
         let context_part = context
             .filter(|c| !c.is_empty())
             .map(|c| format!("\n\nContext:\n{}", c))
@@ -141,22 +169,24 @@ impl AiProvider for AnthropicProvider {
         "anthropic"
     }
 
-    #[instrument(skip(self, request), fields(slot = %request.slot.name))]
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
+
+    #[instrument(skip(self, request), fields(slot = %request.slot.name, request_id = ?request.request_id))]
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         debug!("Generating code with Anthropic for slot: {}", request.slot.name);
 
         let api_key = self.config.resolve_api_key().await?;
 
-        let system = Some(request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        }));
-
+        let preview = self.preview_prompt(&request);
+        let system = preview.system;
         let messages = vec![Message {
             role: "user".to_string(),
-            content: request.slot.prompt.clone(),
+            content: preview.user,
         }];
 
-        let temperature = request.slot.temperature.or(self.config.temperature);
+        let temperature = request.slot.temperature.or(self.config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = MessageRequest {
             model: request.model.clone().unwrap_or_else(|| self.config.model.clone()),
             max_tokens: request.max_tokens.or(self.config.max_tokens).unwrap_or(4096),
@@ -164,16 +194,13 @@ impl AiProvider for AnthropicProvider {
             messages,
             temperature,
             stream: None,
+            metadata: request.end_user_id.clone().map(|user_id| RequestMetadata { user_id }),
         };
 
         let url = self.config.base_url.as_deref().unwrap_or(ANTHROPIC_API_URL);
+        crate::util::check_request_size(&api_request, self.config.max_request_bytes)?;
 
-        let response = self
-            .client
-            .post(url)
-            .header("x-api-key", &api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("Content-Type", "application/json")
+        let response = with_headers(self.client.post(url), &self.config, &api_key, request.request_id.as_deref())
             .json(&api_request)
             .send()
             .await
@@ -181,6 +208,9 @@ impl AiProvider for AnthropicProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                self.config.report_rate_limited(&api_key);
+            }
             let body = response.text().await.unwrap_or_default();
             return Err(AetherError::ProviderError(format!(
                 "API error {}: {}",
@@ -200,28 +230,38 @@ impl AiProvider for AnthropicProvider {
             .unwrap_or_default();
 
         // Strip markdown code blocks if present
-        let code = strip_code_blocks(&code);
+        let code = request.output_code(&code);
 
         Ok(GenerationResponse {
             code,
             tokens_used: Some(msg_response.usage.input_tokens + msg_response.usage.output_tokens),
-            metadata: None,
+            metadata: Some(serde_json::json!({
+                "model": msg_response.model.unwrap_or(api_request.model),
+                "input_tokens": msg_response.usage.input_tokens,
+                "output_tokens": msg_response.usage.output_tokens,
+                // Normalized to the OpenAI vocabulary the engine checks
+                "finish_reason": match msg_response.stop_reason.as_deref() {
+                    Some("max_tokens") => "length",
+                    _ => "stop",
+                },
+            })),
         })
     }
 
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
     ) -> BoxStream<'static, Result<StreamResponse>> {
-        let client = self.client.clone();
+        let client = self.stream_client.clone();
         let config = self.config.clone();
-        let system = Some(request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        }));
-        let user_prompt = request.slot.prompt.clone();
+        let PromptPreview { system, user: user_prompt } = self.preview_prompt(&request);
         let url = config.base_url.as_deref().unwrap_or(ANTHROPIC_API_URL).to_string();
 
-        let temperature = request.slot.temperature.or(config.temperature);
+        let temperature = request.slot.temperature.or(config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = MessageRequest {
             model: request.model.clone().unwrap_or_else(|| config.model.clone()),
             max_tokens: request.max_tokens.or(config.max_tokens).unwrap_or(4096),
@@ -232,8 +272,10 @@ impl AiProvider for AnthropicProvider {
             }],
             temperature,
             stream: Some(true),
+            metadata: request.end_user_id.clone().map(|user_id| RequestMetadata { user_id }),
         };
 
+        let request_id = request.request_id.clone();
         let stream = async_stream::stream! {
             let api_key = match config.resolve_api_key().await {
                 Ok(k) => k,
@@ -243,11 +285,12 @@ impl AiProvider for AnthropicProvider {
                 }
             };
 
-            let response = client
-                .post(&url)
-                .header("x-api-key", &api_key)
-                .header("anthropic-version", ANTHROPIC_VERSION)
-                .header("Content-Type", "application/json")
+            if let Err(e) = crate::util::check_request_size(&api_request, config.max_request_bytes) {
+                yield Err(e);
+                return;
+            }
+
+            let response = with_headers(client.post(&url), &config, &api_key, request_id.as_deref())
                 .json(&api_request)
                 .send()
                 .await
@@ -263,6 +306,9 @@ impl AiProvider for AnthropicProvider {
 
             if !response.status().is_success() {
                 let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    config.report_rate_limited(&api_key);
+                }
                 let body = response.text().await.unwrap_or_default();
                 yield Err(aether_core::AetherError::ProviderError(format!(
                     "API error {}: {}",
@@ -271,30 +317,27 @@ impl AiProvider for AnthropicProvider {
                 return;
             }
 
-            let mut stream = response.bytes_stream();
-            
-            while let Some(chunk_result) = stream.next().await {
-                let chunk = match chunk_result {
-                    Ok(c) => c,
+            let mut lines = Box::pin(crate::util::lines(response.bytes_stream()));
+
+            while let Some(line_result) = lines.next().await {
+                let line = match line_result {
+                    Ok(l) => l,
                     Err(e) => {
                         yield Err(aether_core::AetherError::NetworkError(e.to_string()));
                         break;
                     }
                 };
 
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    let line = line.trim();
-                    if line.is_empty() { continue; }
-                    
-                    if let Some(event_data) = line.strip_prefix("data: ") {
-                        if let Ok(event) = serde_json::from_str::<StreamEvent>(event_data) {
-                            if let StreamEvent::ContentBlockDelta { delta } = event {
-                                yield Ok(StreamResponse {
-                                    delta: delta.text,
-                                    metadata: None,
-                                });
-                            }
+                let line = line.trim();
+                if line.is_empty() { continue; }
+                
+                if let Some(event_data) = line.strip_prefix("data: ") {
+                    if let Ok(event) = serde_json::from_str::<StreamEvent>(event_data) {
+                        if let StreamEvent::ContentBlockDelta { delta } = event {
+                            yield Ok(StreamResponse {
+                                delta: delta.text,
+                                metadata: None,
+                            });
                         }
                     }
                 }
@@ -303,20 +346,38 @@ impl AiProvider for AnthropicProvider {
 
         Box::pin(stream)
     }
-}
-
-/// Strip markdown code blocks from generated code.
-fn strip_code_blocks(code: &str) -> String {
-    let code = code.trim();
 
-    if code.starts_with("```") && code.ends_with("```") {
-        let lines: Vec<&str> = code.lines().collect();
-        if lines.len() >= 2 {
-            return lines[1..lines.len() - 1].join("\n");
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        let system = request.system_prompt.clone().unwrap_or_else(|| {
+            self.build_system_prompt(&request.slot.kind, request.kind_instruction.as_deref(), request.context.as_deref())
+        });
+        PromptPreview {
+            system: Some(system),
+            user: request.slot.prompt.clone(),
         }
     }
+}
 
-    code.to_string()
+/// Add authentication, version, beta and request-id headers to a Messages API request.
+fn with_headers(
+    builder: reqwest::RequestBuilder,
+    config: &ProviderConfig,
+    api_key: &str,
+    request_id: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let mut builder = builder
+        .header("x-api-key", api_key)
+        .header("anthropic-version", config.api_version.as_deref().unwrap_or(ANTHROPIC_VERSION))
+        .header("Content-Type", "application/json");
+    if let Some(id) = request_id {
+        builder = builder.header("X-Request-Id", id);
+    }
+
+    if config.beta_features.is_empty() {
+        builder
+    } else {
+        builder.header("anthropic-beta", config.beta_features.join(","))
+    }
 }
 
 #[cfg(test)]
@@ -328,7 +389,78 @@ mod tests {
         let config = ProviderConfig::new("test-key", "claude-3-sonnet-20240229");
         let provider = AnthropicProvider::new(config).unwrap();
 
-        let prompt = provider.build_system_prompt(&SlotKind::Html, None);
+        let prompt = provider.build_system_prompt(&SlotKind::Html, None, None);
         assert!(prompt.contains("HTML5"));
     }
+
+    #[tokio::test]
+    async fn test_temperature_clamped_to_one() {
+        use aether_core::Slot;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "ok"}],
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "claude-3-sonnet-20240229")
+            .with_base_url(format!("{}/v1/messages", server.uri()))
+            .with_temperature(1.5);
+        let provider = AnthropicProvider::new(config).unwrap();
+        let request = GenerationRequest::builder(Slot::new("slot", "Say ok")).build();
+        provider.generate(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["temperature"], 1.0);
+        assert_eq!(received[0].headers.get("anthropic-version").unwrap(), ANTHROPIC_VERSION);
+        assert!(received[0].headers.get("anthropic-beta").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_version_and_beta_headers() {
+        use aether_core::Slot;
+        use wiremock::matchers::{header, headers, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("anthropic-version", "2024-10-22"))
+            .and(headers("anthropic-beta", vec!["prompt-caching-2024-07-31", "token-efficient-tools-2025-02-19"]))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "ok"}],
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "claude-3-sonnet-20240229")
+            .with_base_url(format!("{}/v1/messages", server.uri()))
+            .with_api_version("2024-10-22")
+            .with_beta_feature("prompt-caching-2024-07-31")
+            .with_beta_feature("token-efficient-tools-2025-02-19");
+        let provider = AnthropicProvider::new(config).unwrap();
+        let request = GenerationRequest::builder(Slot::new("slot", "Say ok")).build();
+        let response = provider.generate(request).await.unwrap();
+        assert_eq!(response.code, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_mock_server() {
+        let server = crate::testing::anthropic_server("fn main() {}").await;
+        let config = ProviderConfig::new("test-key", "claude-3-sonnet-20240229")
+            .with_base_url(crate::testing::url(&server, "/v1/messages"));
+        let provider = AnthropicProvider::new(config).unwrap();
+
+        let response = provider.generate(crate::testing::request("main", "Write main")).await.unwrap();
+        assert_eq!(response.code, "fn main() {}");
+        assert_eq!(response.tokens_used, Some(5));
+        assert_eq!(response.finish_reason(), Some("stop"));
+    }
 }
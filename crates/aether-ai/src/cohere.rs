@@ -0,0 +1,517 @@
+//! Cohere Command provider implementation.
+
+use aether_core::{
+    AetherError, AiProvider, ProviderConfig, Result,
+    provider::{DEFAULT_COHERE_MODEL, GenerationRequest, GenerationResponse, PromptPreview, StreamResponse},
+    SlotKind,
+};
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
+
+const COHERE_API_URL: &str = "https://api.cohere.com/v1/chat";
+const COHERE_MODELS_URL: &str = "https://api.cohere.com/v1/models";
+
+/// Cohere accepts temperatures in 0.0–1.0; higher values are clamped.
+const MAX_TEMPERATURE: f32 = 1.0;
+
+/// Cohere Command provider for code generation.
+#[derive(Debug, Clone)]
+pub struct CohereProvider {
+    client: Client,
+    stream_client: Client,
+    config: ProviderConfig,
+}
+
+/// Cohere chat request.
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    message: String,
+    preamble: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream: Option<bool>,
+}
+
+/// Cohere chat response.
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    text: String,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    meta: Option<ResponseMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMeta {
+    #[serde(default)]
+    billed_units: Option<BilledUnits>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BilledUnits {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+/// Cohere streaming event (minimal). Events arrive one JSON object per line.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event_type")]
+enum StreamEvent {
+    #[serde(rename = "text-generation")]
+    TextGeneration { text: String },
+    #[serde(rename = "stream-end")]
+    StreamEnd {
+        #[serde(default)]
+        finish_reason: Option<String>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+impl CohereProvider {
+    /// Create a new Cohere provider.
+    pub fn new(config: ProviderConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(config.request_timeout())
+            .user_agent(config.user_agent())
+            .build()
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+        let stream_client = Client::builder()
+            .timeout(config.stream_timeout())
+            .user_agent(config.user_agent())
+            .build()
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+
+        Ok(Self { client, stream_client, config })
+    }
+
+    /// Create a provider from environment variables.
+    ///
+    /// Reads `COHERE_API_KEY` and optionally `COHERE_MODEL`.
+    pub fn from_env() -> Result<Self> {
+        let model = std::env::var("COHERE_MODEL")
+            .unwrap_or_else(|_| DEFAULT_COHERE_MODEL.to_string());
+        Self::from_env_with_model(&model)
+    }
+
+    /// Create a provider from environment with a specific model.
+    pub fn from_env_with_model(model: &str) -> Result<Self> {
+        let api_key = std::env::var("COHERE_API_KEY")
+            .map_err(|_| AetherError::ConfigError("COHERE_API_KEY not set".to_string()))?;
+
+        let config = ProviderConfig::new(api_key, model);
+        Self::new(config)
+    }
+
+    /// URL of the models endpoint, next to the configured chat endpoint.
+    fn models_url(&self) -> String {
+        match self.config.base_url.as_deref() {
+            Some(url) => {
+                let url = url.trim_end_matches('/');
+                format!("{}/models", url.strip_suffix("/chat").unwrap_or(url))
+            }
+            None => COHERE_MODELS_URL.to_string(),
+        }
+    }
+
+    /// Build the preamble (system prompt) for code generation.
+    fn build_preamble(&self, kind: &SlotKind, kind_instruction: Option<&str>, context: Option<&str>) -> String {
+        let base = "You are a code generation assistant. Generate only the requested code without explanations or markdown code blocks. Output raw code only.";
+
+        let kind_specific = match kind {
+            SlotKind::Html => "\nGenerate valid HTML5 markup.",
+            SlotKind::Css => "\nGenerate valid CSS styles.",
+            SlotKind::JavaScript => "\nGenerate valid JavaScript code.",
+            SlotKind::Function => "\nGenerate a complete function definition.",
+            SlotKind::Class => "\nGenerate a complete class/struct definition.",
+            SlotKind::Component => "\nGenerate a complete component with HTML, CSS, and JavaScript as needed.",
+            SlotKind::Json => "\nGenerate a single valid JSON value.",
+            _ => "",
+        };
+
+        // Custom kinds carry their instruction from the engine's kind registry
+        let kind_specific = kind_instruction
+            .map(|i| format!("\n{}", i))
+            .unwrap_or_else(|| kind_specific.to_string());
+
+        let context_part = context
+            .filter(|c| !c.is_empty())
+            .map(|c| format!("\n\nContext:\n{}", c))
+            .unwrap_or_default();
+
+        format!("{}{}{}", base, kind_specific, context_part)
+    }
+
+    /// Build the chat request body shared by `generate` and `generate_stream`.
+    fn chat_request(&self, request: &GenerationRequest, stream: bool) -> ChatRequest {
+        let preview = self.preview_prompt(request);
+
+        ChatRequest {
+            model: request.model.clone().unwrap_or_else(|| self.config.model.clone()),
+            message: preview.user,
+            preamble: preview.system.unwrap_or_default(),
+            max_tokens: request.max_tokens.or(self.config.max_tokens),
+            temperature: request
+                .slot
+                .temperature
+                .or(self.config.temperature)
+                .or(request.default_temperature)
+                .map(|t| t.clamp(0.0, MAX_TEMPERATURE)),
+            stream: stream.then_some(true),
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for CohereProvider {
+    fn name(&self) -> &str {
+        "cohere"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
+
+    #[instrument(skip(self, request), fields(slot = %request.slot.name, request_id = ?request.request_id))]
+    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+        debug!("Generating code with Cohere for slot: {}", request.slot.name);
+
+        let api_key = self.config.resolve_api_key().await?;
+        let api_request = self.chat_request(&request, false);
+
+        let url = self.config.base_url.as_deref().unwrap_or(COHERE_API_URL);
+        crate::util::check_request_size(&api_request, self.config.max_request_bytes)?;
+
+        let response = with_headers(self.client.post(url), &api_key, request.request_id.as_deref())
+            .json(&api_request)
+            .send()
+            .await
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                self.config.report_rate_limited(&api_key);
+            }
+            let body = response.text().await.unwrap_or_default();
+            return Err(AetherError::ProviderError(format!(
+                "API error {}: {}",
+                status, body
+            )));
+        }
+
+        let chat_response: ChatResponse = response
+            .json()
+            .await
+            .map_err(|e| AetherError::ProviderError(e.to_string()))?;
+        if let Some(e) = finish_error(chat_response.finish_reason.as_deref()) {
+            return Err(e);
+        }
+
+        let code = request.output_code(&chat_response.text);
+
+        let usage = chat_response.meta.and_then(|m| m.billed_units);
+        let mut metadata = serde_json::json!({
+            "model": api_request.model,
+            "finish_reason": normalize_finish_reason(chat_response.finish_reason.as_deref()),
+        });
+        if let Some(ref usage) = usage {
+            metadata["input_tokens"] = usage.input_tokens.into();
+            metadata["output_tokens"] = usage.output_tokens.into();
+        }
+
+        Ok(GenerationResponse {
+            code,
+            tokens_used: usage.map(|u| u.input_tokens + u.output_tokens),
+            metadata: Some(metadata),
+        })
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> BoxStream<'static, Result<StreamResponse>> {
+        let client = self.stream_client.clone();
+        let config = self.config.clone();
+        let api_request = self.chat_request(&request, true);
+        let url = config.base_url.as_deref().unwrap_or(COHERE_API_URL).to_string();
+        let request_id = request.request_id.clone();
+
+        let stream = async_stream::stream! {
+            let api_key = match config.resolve_api_key().await {
+                Ok(k) => k,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            if let Err(e) = crate::util::check_request_size(&api_request, config.max_request_bytes) {
+                yield Err(e);
+                return;
+            }
+
+            let response = with_headers(client.post(&url), &api_key, request_id.as_deref())
+                .json(&api_request)
+                .send()
+                .await
+                .map_err(|e| AetherError::NetworkError(e.to_string()));
+
+            let response = match response {
+                Ok(r) => r,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            if !response.status().is_success() {
+                let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    config.report_rate_limited(&api_key);
+                }
+                let body = response.text().await.unwrap_or_default();
+                yield Err(AetherError::ProviderError(format!(
+                    "API error {}: {}",
+                    status, body
+                )));
+                return;
+            }
+
+            let mut lines = Box::pin(crate::util::lines(response.bytes_stream()));
+
+            while let Some(line_result) = lines.next().await {
+                let line = match line_result {
+                    Ok(l) => l,
+                    Err(e) => {
+                        yield Err(AetherError::NetworkError(e.to_string()));
+                        break;
+                    }
+                };
+
+                match parse_stream_line(&line) {
+                    Some(StreamEvent::TextGeneration { text }) => {
+                        yield Ok(StreamResponse {
+                            delta: text,
+                            metadata: None,
+                        });
+                    }
+                    Some(StreamEvent::StreamEnd { finish_reason }) => {
+                        if let Some(e) = finish_error(finish_reason.as_deref()) {
+                            yield Err(e);
+                            break;
+                        }
+                        yield Ok(StreamResponse {
+                            delta: String::new(),
+                            metadata: Some(serde_json::json!({
+                                "finish_reason": normalize_finish_reason(finish_reason.as_deref()),
+                            })),
+                        });
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        Box::pin(stream)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        let api_key = self.config.resolve_api_key().await?;
+        let response = self
+            .client
+            .get(self.models_url())
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send()
+            .await
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+
+        Ok(response.status().is_success())
+    }
+
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        let preamble = request.system_prompt.clone().unwrap_or_else(|| {
+            self.build_preamble(&request.slot.kind, request.kind_instruction.as_deref(), request.context.as_deref())
+        });
+        PromptPreview {
+            system: Some(preamble),
+            user: request.slot.prompt.clone(),
+        }
+    }
+}
+
+/// Parse one line of a Cohere stream.
+///
+/// Cohere sends one JSON event per line with an `event_type` field; an SSE
+/// `data:` prefix is tolerated for proxies that re-frame the stream.
+fn parse_stream_line(line: &str) -> Option<StreamEvent> {
+    let line = line.trim();
+    let line = line.strip_prefix("data:").map(str::trim_start).unwrap_or(line);
+    if line.is_empty() {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// Map Cohere finish reasons to the OpenAI vocabulary the engine checks.
+fn normalize_finish_reason(reason: Option<&str>) -> &'static str {
+    match reason {
+        Some("MAX_TOKENS") => "length",
+        _ => "stop",
+    }
+}
+
+/// Map an `ERROR*` finish reason to an error. `ERROR_TOXIC` is a policy
+/// block; the others mean generation failed.
+fn finish_error(reason: Option<&str>) -> Option<AetherError> {
+    match reason? {
+        "ERROR_TOXIC" => Some(AetherError::ContentBlocked { reason: "ERROR_TOXIC".to_string(), retryable: false }),
+        reason if reason.starts_with("ERROR") => {
+            Some(AetherError::ProviderError(format!("Cohere generation failed ({})", reason)))
+        }
+        _ => None,
+    }
+}
+
+/// Add authentication and request-id headers to a chat request.
+fn with_headers(
+    builder: reqwest::RequestBuilder,
+    api_key: &str,
+    request_id: Option<&str>,
+) -> reqwest::RequestBuilder {
+    let builder = builder
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json");
+    match request_id {
+        Some(id) => builder.header("X-Request-Id", id),
+        None => builder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preamble() {
+        let provider = CohereProvider::new(ProviderConfig::new("test-key", "command-r-plus")).unwrap();
+
+        let preamble = provider.build_preamble(&SlotKind::Html, None, None);
+        assert!(preamble.contains("HTML5"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_mock_server() {
+        let server = crate::testing::cohere_server("fn main() {}").await;
+        let config = ProviderConfig::new("test-key", "command-r-plus")
+            .with_base_url(crate::testing::url(&server, "/v1/chat"))
+            .with_temperature(1.5);
+        let provider = CohereProvider::new(config).unwrap();
+
+        let response = provider.generate(crate::testing::request("main", "Write main")).await.unwrap();
+        assert_eq!(response.code, "fn main() {}");
+        assert_eq!(response.tokens_used, Some(5));
+        assert_eq!(response.finish_reason(), Some("stop"));
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["message"], "Write main");
+        assert_eq!(body["temperature"], 1.0);
+        assert_eq!(received[0].headers.get("authorization").unwrap(), "Bearer test-key");
+    }
+
+    #[tokio::test]
+    async fn test_stream_parses_text_generation_events() {
+        let server = crate::testing::cohere_stream_server(&["fn ", "main() ", "{}"]).await;
+        let config = ProviderConfig::new("test-key", "command-r-plus")
+            .with_base_url(crate::testing::url(&server, "/v1/chat"));
+        let provider = CohereProvider::new(config).unwrap();
+
+        let chunks: Vec<StreamResponse> = provider
+            .generate_stream(crate::testing::request("main", "Write main"))
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        let text: String = chunks.iter().map(|c| c.delta.as_str()).collect();
+        assert_eq!(text, "fn main() {}");
+        assert_eq!(chunks.last().unwrap().metadata.as_ref().unwrap()["finish_reason"], "stop");
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_error_finish_reason_fails() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "",
+                "finish_reason": "ERROR_TOXIC"
+            })))
+            .mount(&server)
+            .await;
+        let config = ProviderConfig::new("test-key", "command-r-plus")
+            .with_base_url(crate::testing::url(&server, "/v1/chat"));
+        let provider = CohereProvider::new(config).unwrap();
+
+        let result = provider.generate(crate::testing::request("main", "Write main")).await;
+        assert!(matches!(result, Err(AetherError::ContentBlocked { retryable: false, .. })));
+
+        assert!(matches!(finish_error(Some("ERROR")), Some(AetherError::ProviderError(_))));
+        assert!(matches!(finish_error(Some("ERROR_LIMIT")), Some(AetherError::ProviderError(_))));
+        assert!(finish_error(Some("COMPLETE")).is_none());
+        assert!(finish_error(Some("MAX_TOKENS")).is_none());
+        assert!(finish_error(None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_uses_base_url() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"models": []})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let config = ProviderConfig::new("test-key", "command-r-plus")
+            .with_base_url(crate::testing::url(&server, "/v1/chat"));
+        let provider = CohereProvider::new(config).unwrap();
+
+        assert!(provider.health_check().await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert!(matches!(
+            parse_stream_line(r#"{"is_finished":false,"event_type":"text-generation","text":"hi"}"#),
+            Some(StreamEvent::TextGeneration { text }) if text == "hi"
+        ));
+        assert!(matches!(
+            parse_stream_line(r#"data: {"event_type":"stream-start","generation_id":"1"}"#),
+            Some(StreamEvent::Unknown)
+        ));
+        assert!(parse_stream_line("").is_none());
+    }
+}
@@ -4,7 +4,7 @@
 
 use aether_core::{
     AetherError, AiProvider, ProviderConfig, Result,
-    provider::{GenerationRequest, GenerationResponse},
+    provider::{DEFAULT_GEMINI_MODEL, GenerationRequest, GenerationResponse, PromptPreview},
     SlotKind,
 };
 use async_trait::async_trait;
@@ -16,10 +16,14 @@ use futures::stream::{BoxStream, StreamExt};
 
 const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
 
+/// Gemini accepts temperatures in 0.0–2.0; values outside are clamped.
+const MAX_TEMPERATURE: f32 = 2.0;
+
 /// Google Gemini provider for code generation.
 #[derive(Debug, Clone)]
 pub struct GeminiProvider {
     client: Client,
+    stream_client: Client,
     config: ProviderConfig,
 }
 
@@ -59,12 +63,18 @@ struct GeminiResponse {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Candidate {
+    // Missing when the candidate was blocked
+    #[serde(default)]
     content: ContentResponse,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct ContentResponse {
+    #[serde(default)]
     parts: Vec<PartResponse>,
 }
 
@@ -79,16 +89,80 @@ struct UsageMetadata {
     total_token_count: u32,
 }
 
+/// Map a policy `finishReason` to an error. `RECITATION` (output too close
+/// to existing sources) may pass with a reworded prompt; safety blocks won't.
+fn blocked_error(finish_reason: &str) -> Option<AetherError> {
+    let retryable = match finish_reason {
+        "RECITATION" => true,
+        "SAFETY" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => false,
+        _ => return None,
+    };
+    Some(AetherError::ContentBlocked { reason: finish_reason.to_string(), retryable })
+}
+
+/// Clean up markdown around single-block output.
+fn strip_outer_fence(code: &str) -> &str {
+    let code = code.trim().trim_start_matches("```").trim_end_matches("```");
+    // Sometimes it includes the language name like ```rust ... ```
+    if let Some(newline_idx) = code.find('\n') {
+        if code[..newline_idx].chars().all(char::is_alphanumeric) {
+            return &code[newline_idx + 1..];
+        }
+    }
+    code
+}
+
+/// Extract the generated code from the first candidate.
+fn into_generation_response(response: GeminiResponse, request: &GenerationRequest) -> Result<GenerationResponse> {
+    let candidate = response
+        .candidates
+        .as_ref()
+        .and_then(|c| c.first())
+        .ok_or_else(|| AetherError::ProviderError("No content generated".to_string()))?;
+    let finish_reason = candidate.finish_reason.as_deref();
+    if let Some(err) = finish_reason.and_then(blocked_error) {
+        return Err(err);
+    }
+
+    let code = candidate
+        .content
+        .parts
+        .first()
+        .map(|p| p.text.clone())
+        .ok_or_else(|| AetherError::ProviderError("No content generated".to_string()))?;
+
+    let code = if request.raw_output || request.slot.kind == SlotKind::Component {
+        request.output_code(&code)
+    } else {
+        strip_outer_fence(&code).to_string()
+    };
+
+    Ok(GenerationResponse {
+        code,
+        tokens_used: response.usage_metadata.map(|u| u.total_token_count),
+        metadata: finish_reason.map(|reason| {
+            serde_json::json!({
+                "finish_reason": if reason == "MAX_TOKENS" { "length" } else { "stop" },
+            })
+        }),
+    })
+}
+
 impl GeminiProvider {
     /// Create a new Gemini provider with the given configuration.
     pub fn new(config: ProviderConfig) -> Result<Self> {
-        let timeout = config.timeout_seconds.unwrap_or(60);
         let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(timeout))
+            .timeout(config.request_timeout())
+            .user_agent(config.user_agent())
+            .build()
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+        let stream_client = Client::builder()
+            .timeout(config.stream_timeout())
+            .user_agent(config.user_agent())
             .build()
             .map_err(|e| AetherError::NetworkError(e.to_string()))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, stream_client, config })
     }
 
     /// Create a provider from environment variables.
@@ -98,7 +172,7 @@ impl GeminiProvider {
         let api_key = std::env::var("GOOGLE_API_KEY")
             .map_err(|_| AetherError::ConfigError("GOOGLE_API_KEY not set".to_string()))?;
 
-        let model = std::env::var("GEMINI_MODEL").unwrap_or_else(|_| "gemini-1.5-pro".to_string());
+        let model = std::env::var("GEMINI_MODEL").unwrap_or_else(|_| DEFAULT_GEMINI_MODEL.to_string());
         
         // Google API key is query param, not header like OpenAI
         // We store it in config.api_key but will use it in URL
@@ -106,8 +180,13 @@ impl GeminiProvider {
         Self::new(config)
     }
 
+    /// Models endpoint, honoring `ProviderConfig::base_url`.
+    fn api_base(&self) -> &str {
+        self.config.base_url.as_deref().unwrap_or(GEMINI_API_BASE)
+    }
+
     /// Build the specific prompt for Gemini
-    fn build_prompt(&self, kind: &SlotKind, context: Option<&str>, user_prompt: &str) -> String {
+    fn build_prompt(&self, kind: &SlotKind, kind_instruction: Option<&str>, context: Option<&str>, user_prompt: &str) -> String {
         let base_instructions = match kind {
             SlotKind::Html => "Generate valid HTML5 markup.",
             SlotKind::Css => "Generate valid CSS styles.",
@@ -115,8 +194,10 @@ impl GeminiProvider {
             SlotKind::Function => "Generate a complete function definition.",
             SlotKind::Class => "Generate a complete class/struct definition.",
             SlotKind::Component => "Generate a complete component with HTML, CSS, and JavaScript as needed.",
+            SlotKind::Json => "Generate a single valid JSON value.",
             _ => "Generate code based on the request.",
         };
+        let base_instructions = kind_instruction.unwrap_or(base_instructions);
 
         let context_str = context
             .map(|c| format!("\nContext:\n{}", c))
@@ -135,7 +216,11 @@ impl AiProvider for GeminiProvider {
         "gemini"
     }
 
-    #[instrument(skip(self, request), fields(slot = %request.slot.name))]
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
+
+    #[instrument(skip(self, request), fields(slot = %request.slot.name, request_id = ?request.request_id))]
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         debug!("Generating code with Gemini for slot: {}", request.slot.name);
 
@@ -143,14 +228,14 @@ impl AiProvider for GeminiProvider {
 
         // Gemini API is slightly different (no system role in v1beta easily)
         // so we verify robust prompt engineering in the user message
-        let full_prompt = self.build_prompt(&request.slot.kind, request.context.as_deref(), &request.slot.prompt);
+        let full_prompt = self.preview_prompt(&request).user;
 
         let contents = vec![Content {
             role: "user".to_string(),
             parts: vec![Part { text: full_prompt }],
         }];
 
-        let temperature = request.slot.temperature.or(self.config.temperature);
+        let temperature = request.slot.temperature.or(self.config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = GeminiRequest {
             contents,
             generation_config: Some(GenerationConfig {
@@ -162,13 +247,17 @@ impl AiProvider for GeminiProvider {
         let model = request.model.clone().unwrap_or_else(|| self.config.model.clone());
         let url = format!(
             "{}/{}:generateContent?key={}",
-            GEMINI_API_BASE, model, api_key
+            self.api_base(), model, api_key
         );
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
+        crate::util::check_request_size(&api_request, self.config.max_request_bytes)?;
+
+        let mut http_request = self.client.post(&url).header("Content-Type", "application/json");
+        if let Some(ref id) = request.request_id {
+            http_request = http_request.header("X-Request-Id", id);
+        }
+
+        let response = http_request
             .json(&api_request)
             .send()
             .await
@@ -176,6 +265,9 @@ impl AiProvider for GeminiProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                self.config.report_rate_limited(&api_key);
+            }
             let body = response.text().await.unwrap_or_default();
             return Err(AetherError::ProviderError(format!(
                 "API error {}: {}",
@@ -188,44 +280,22 @@ impl AiProvider for GeminiProvider {
             .await
             .map_err(|e| AetherError::ProviderError(e.to_string()))?;
 
-        // Extract text from the first candidate
-        let code = gemini_response
-            .candidates
-            .as_ref()
-            .and_then(|c| c.first())
-            .and_then(|c| c.content.parts.first())
-            .map(|p| p.text.clone())
-            .ok_or_else(|| AetherError::ProviderError("No content generated".to_string()))?;
-
-        // Clean up markdown
-        let code = code.trim().trim_start_matches("```").trim_end_matches("```");
-        // Sometimes it includes the language name like ```rust ... ```
-        let code = if let Some(newline_idx) = code.find('\n') {
-            if code[..newline_idx].chars().all(char::is_alphanumeric) {
-                &code[newline_idx + 1..]
-            } else {
-                code
-            }
-        } else {
-            code
-        };
+        into_generation_response(gemini_response, &request)
+    }
 
-        Ok(GenerationResponse {
-            code: code.to_string(),
-            tokens_used: gemini_response.usage_metadata.map(|u| u.total_token_count),
-            metadata: None,
-        })
+    fn supports_streaming(&self) -> bool {
+        true
     }
 
     fn generate_stream(
         &self,
         request: GenerationRequest,
     ) -> BoxStream<'static, Result<StreamResponse>> {
-        let client = self.client.clone();
+        let client = self.stream_client.clone();
         let config = self.config.clone();
-        let full_prompt = self.build_prompt(&request.slot.kind, request.context.as_deref(), &request.slot.prompt);
+        let full_prompt = self.preview_prompt(&request).user;
         
-        let temperature = request.slot.temperature.or(config.temperature);
+        let temperature = request.slot.temperature.or(config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = GeminiRequest {
             contents: vec![Content {
                 role: "user".to_string(),
@@ -237,6 +307,7 @@ impl AiProvider for GeminiProvider {
             }),
         };
 
+        let base = self.api_base().to_string();
         let stream = async_stream::stream! {
             let api_key = match config.resolve_api_key().await {
                 Ok(k) => k,
@@ -249,12 +320,20 @@ impl AiProvider for GeminiProvider {
             let model = request.model.clone().unwrap_or_else(|| config.model.clone());
             let url = format!(
                 "{}/{}:streamGenerateContent?alt=sse&key={}",
-                GEMINI_API_BASE, model, api_key
+                base, model, api_key
             );
 
-            let response = client
-                .post(&url)
-                .header("Content-Type", "application/json")
+            if let Err(e) = crate::util::check_request_size(&api_request, config.max_request_bytes) {
+                yield Err(e);
+                return;
+            }
+
+            let mut http_request = client.post(&url).header("Content-Type", "application/json");
+            if let Some(ref id) = request.request_id {
+                http_request = http_request.header("X-Request-Id", id);
+            }
+
+            let response = http_request
                 .json(&api_request)
                 .send()
                 .await
@@ -270,6 +349,9 @@ impl AiProvider for GeminiProvider {
 
             if !response.status().is_success() {
                 let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    config.report_rate_limited(&api_key);
+                }
                 let body = response.text().await.unwrap_or_default();
                 yield Err(aether_core::AetherError::ProviderError(format!(
                     "API error {}: {}",
@@ -278,31 +360,32 @@ impl AiProvider for GeminiProvider {
                 return;
             }
 
-            let mut stream = response.bytes_stream();
-            
-            while let Some(chunk_result) = stream.next().await {
-                let chunk = match chunk_result {
-                    Ok(c) => c,
+            let mut lines = Box::pin(crate::util::lines(response.bytes_stream()));
+
+            while let Some(line_result) = lines.next().await {
+                let line = match line_result {
+                    Ok(l) => l,
                     Err(e) => {
                         yield Err(aether_core::AetherError::NetworkError(e.to_string()));
                         break;
                     }
                 };
 
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    let line = line.trim();
-                    if line.is_empty() { continue; }
-                    
-                    if let Some(event_data) = line.strip_prefix("data: ") {
-                        if let Ok(gemini_resp) = serde_json::from_str::<GeminiResponse>(event_data) {
-                            if let Some(candidate) = gemini_resp.candidates.as_ref().and_then(|c| c.first()) {
-                                if let Some(part) = candidate.content.parts.first() {
-                                    yield Ok(StreamResponse {
-                                        delta: part.text.clone(),
-                                        metadata: None,
-                                    });
-                                }
+                let line = line.trim();
+                if line.is_empty() { continue; }
+                
+                if let Some(event_data) = line.strip_prefix("data: ") {
+                    if let Ok(gemini_resp) = serde_json::from_str::<GeminiResponse>(event_data) {
+                        if let Some(candidate) = gemini_resp.candidates.as_ref().and_then(|c| c.first()) {
+                            if let Some(err) = candidate.finish_reason.as_deref().and_then(blocked_error) {
+                                yield Err(err);
+                                break;
+                            }
+                            if let Some(part) = candidate.content.parts.first() {
+                                yield Ok(StreamResponse {
+                                    delta: part.text.clone(),
+                                    metadata: None,
+                                });
                             }
                         }
                     }
@@ -318,7 +401,7 @@ impl AiProvider for GeminiProvider {
         // Minimal check - try to get model info
          let url = format!(
             "{}/{}?key={}",
-            GEMINI_API_BASE, self.config.model, api_key
+            self.api_base(), self.config.model, api_key
         );
 
         let response = self
@@ -330,4 +413,50 @@ impl AiProvider for GeminiProvider {
 
         Ok(response.status().is_success())
     }
+
+    /// Gemini gets no system message: instructions and context are folded
+    /// into the single user message.
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        PromptPreview {
+            system: None,
+            user: self.build_prompt(&request.slot.kind, request.kind_instruction.as_deref(), request.context.as_deref(), &request.slot.prompt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recitation_finish_reason() {
+        let body = r#"{"candidates": [{"finishReason": "RECITATION", "index": 0}]}"#;
+        let response: GeminiResponse = serde_json::from_str(body).unwrap();
+        match into_generation_response(response, &crate::testing::request("slot", "Say ok")) {
+            Err(e @ AetherError::ContentBlocked { .. }) => {
+                assert!(e.is_retryable());
+                assert!(e.to_string().contains("RECITATION"));
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other.map(|r| r.code)),
+        }
+
+        let safety = blocked_error("SAFETY").unwrap();
+        assert!(!safety.is_retryable());
+        assert!(blocked_error("STOP").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_mock_server() {
+        let server = crate::testing::gemini_server("fn main() {}").await;
+        let config = ProviderConfig::new("test-key", "gemini-1.5-pro")
+            .with_base_url(crate::testing::url(&server, "/v1beta/models"));
+        let provider = GeminiProvider::new(config).unwrap();
+
+        let response = provider.generate(crate::testing::request("main", "Write main")).await.unwrap();
+        assert_eq!(response.code, "fn main() {}");
+        assert_eq!(response.finish_reason(), Some("stop"));
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received[0].url.path(), "/v1beta/models/gemini-1.5-pro:generateContent");
+    }
 }
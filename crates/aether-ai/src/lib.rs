@@ -6,6 +6,8 @@
 //!
 //! - **OpenAI**: GPT-4, GPT-3.5-turbo
 //! - **Anthropic**: Claude models
+//! - **Gemini**: Google Gemini models
+//! - **Cohere**: Command models
 //! - **Local**: Ollama and other local providers
 //!
 //! ## Example
@@ -26,12 +28,17 @@ pub mod openai;
 pub mod anthropic;
 pub mod ollama;
 pub mod gemini;
+pub mod cohere;
 pub mod error;
+mod util;
+#[cfg(any(test, feature = "dev"))]
+pub mod testing;
 
 pub use openai::OpenAiProvider;
 pub use anthropic::AnthropicProvider;
 pub use ollama::OllamaProvider;
 pub use gemini::GeminiProvider;
+pub use cohere::CohereProvider;
 pub use error::AiError;
 
 /// Re-export core types for convenience.
@@ -81,6 +88,19 @@ pub fn gemini(model: &str) -> Result<GeminiProvider> {
     }
 }
 
+/// Create a Cohere provider with a single line.
+///
+/// Requires `COHERE_API_KEY` environment variable.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let provider = aether_ai::cohere("command-r-plus");
+/// ```
+pub fn cohere(model: &str) -> Result<CohereProvider> {
+    CohereProvider::from_env_with_model(model)
+}
+
 /// Create a Grok (xAI) provider with a single line.
 ///
 /// Uses the OpenAI-compatible API from xAI.
@@ -114,3 +134,21 @@ pub fn grok(model: &str) -> Result<OpenAiProvider> {
 pub fn ollama(model: &str) -> OllamaProvider {
     OllamaProvider::new(model)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aether_core::provider::MockProvider;
+
+    #[test]
+    fn test_supports_streaming() {
+        let config = || ProviderConfig::new("test-key", "test-model");
+
+        assert!(MockProvider::new().supports_streaming());
+        assert!(OpenAiProvider::new(config()).unwrap().supports_streaming());
+        assert!(AnthropicProvider::new(config()).unwrap().supports_streaming());
+        assert!(GeminiProvider::new(config()).unwrap().supports_streaming());
+        assert!(CohereProvider::new(config()).unwrap().supports_streaming());
+        assert!(OllamaProvider::new("codellama").supports_streaming());
+    }
+}
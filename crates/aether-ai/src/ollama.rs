@@ -4,7 +4,7 @@
 
 use aether_core::{
     AetherError, AiProvider, Result,
-    provider::{GenerationRequest, GenerationResponse},
+    provider::{DEFAULT_OLLAMA_MODEL, GenerationRequest, GenerationResponse, PromptPreview},
     SlotKind,
 };
 use async_trait::async_trait;
@@ -22,6 +22,8 @@ pub struct OllamaProvider {
     client: Client,
     model: String,
     base_url: String,
+    auto_pull: bool,
+    max_request_bytes: Option<usize>,
 }
 
 /// Ollama generate request.
@@ -50,6 +52,9 @@ struct GenerateResponse {
     done: bool,
     #[serde(default)]
     eval_count: Option<u32>,
+    /// `"stop"` or `"length"`; only reported by Ollama 0.1.39 and later.
+    #[serde(default)]
+    done_reason: Option<String>,
 }
 
 impl OllamaProvider {
@@ -62,6 +67,7 @@ impl OllamaProvider {
     pub fn with_options(model: impl Into<String>, base_url: impl Into<String>) -> Self {
         let client = Client::builder()
             .timeout(std::time::Duration::from_secs(300)) // Local models can be slow
+            .user_agent(aether_core::provider::DEFAULT_USER_AGENT)
             .build()
             .expect("Failed to create HTTP client");
 
@@ -69,20 +75,85 @@ impl OllamaProvider {
             client,
             model: model.into(),
             base_url: base_url.into(),
+            auto_pull: false,
+            max_request_bytes: None,
+        }
+    }
+
+    /// Fail fast with `RequestTooLarge` when a request body exceeds `limit` bytes.
+    pub fn with_max_request_bytes(mut self, limit: usize) -> Self {
+        self.max_request_bytes = Some(limit);
+        self
+    }
+
+    /// Pull a missing model (`POST /api/pull`) and retry once when generation
+    /// fails with "model not found". Pulls can take minutes for large models.
+    pub fn with_auto_pull(mut self, enabled: bool) -> Self {
+        self.auto_pull = enabled;
+        self
+    }
+
+    /// URL of another Ollama API endpoint on the same server as `base_url`.
+    fn api_url(&self, endpoint: &str) -> String {
+        match self.base_url.rfind("/api/") {
+            Some(i) => format!("{}/api/{}", &self.base_url[..i], endpoint),
+            None => format!("{}/api/{}", self.base_url.trim_end_matches('/'), endpoint),
+        }
+    }
+
+    /// Download `model` into the local Ollama library.
+    async fn pull_model(&self, model: &str) -> Result<()> {
+        debug!("Pulling Ollama model: {}", model);
+        let response = self
+            .client
+            .post(self.api_url("pull"))
+            .json(&serde_json::json!({ "model": model, "stream": false }))
+            .send()
+            .await
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AetherError::ProviderError(format!(
+                "Ollama pull of '{}' failed {}: {}",
+                model, status, body
+            )));
+        }
+        Ok(())
+    }
+
+    /// POST a generate request, pulling the model and retrying once if enabled.
+    async fn send_generate(&self, api_request: &GenerateRequest) -> Result<reqwest::Response> {
+        crate::util::check_request_size(api_request, self.max_request_bytes)?;
+        let send = || async move {
+            self.client
+                .post(&self.base_url)
+                .json(api_request)
+                .send()
+                .await
+                .map_err(|e| AetherError::NetworkError(e.to_string()))
+        };
+
+        let response = send().await?;
+        if self.auto_pull && response.status() == reqwest::StatusCode::NOT_FOUND {
+            self.pull_model(&api_request.model).await?;
+            return send().await;
         }
+        Ok(response)
     }
 
     /// Create from environment variables.
     ///
     /// Reads `OLLAMA_MODEL` and optionally `OLLAMA_URL`.
     pub fn from_env() -> Self {
-        let model = std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| "codellama".to_string());
+        let model = std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| DEFAULT_OLLAMA_MODEL.to_string());
         let url = std::env::var("OLLAMA_URL").unwrap_or_else(|_| DEFAULT_OLLAMA_URL.to_string());
         Self::with_options(model, url)
     }
 
     /// Build the system prompt for code generation.
-    fn build_system_prompt(&self, kind: &SlotKind, context: Option<&str>) -> String {
+    fn build_system_prompt(&self, kind: &SlotKind, kind_instruction: Option<&str>, context: Option<&str>) -> String {
         let base = "You are a code generation assistant. Generate only the requested code without explanations or markdown code blocks. Output raw code only.";
 
         let kind_specific = match kind {
@@ -92,9 +163,15 @@ impl OllamaProvider {
             SlotKind::Function => "\nGenerate a complete function definition.",
             SlotKind::Class => "\nGenerate a complete class/struct definition.",
             SlotKind::Component => "\nGenerate a complete component with HTML, CSS, and JavaScript as needed.",
+            SlotKind::Json => "\nGenerate a single valid JSON value.",
             _ => "",
         };
 
+        // Custom kinds carry their instruction from the engine's kind registry
+        let kind_specific = kind_instruction
+            .map(|i| format!("\n{}", i))
+            .unwrap_or_else(|| kind_specific.to_string());
+
         let context_part = context
             .filter(|c| !c.is_empty())
             .map(|c| format!("\n\nContext:\n{}", c))
@@ -110,18 +187,20 @@ impl AiProvider for OllamaProvider {
         "ollama"
     }
 
-    #[instrument(skip(self, request), fields(slot = %request.slot.name))]
+    fn model(&self) -> Option<&str> {
+        Some(&self.model)
+    }
+
+    #[instrument(skip(self, request), fields(slot = %request.slot.name, request_id = ?request.request_id))]
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         debug!("Generating code with Ollama for slot: {}", request.slot.name);
 
-        let system = Some(request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        }));
+        let PromptPreview { system, user } = self.preview_prompt(&request);
 
-        let temperature = request.slot.temperature.unwrap_or(0.7);
+        let temperature = request.slot.temperature.or(request.default_temperature).unwrap_or(0.7);
         let api_request = GenerateRequest {
             model: request.model.clone().unwrap_or_else(|| self.model.clone()),
-            prompt: request.slot.prompt.clone(),
+            prompt: user,
             system,
             stream: false,
             options: Some(GenerateOptions {
@@ -130,13 +209,7 @@ impl AiProvider for OllamaProvider {
             }),
         };
 
-        let response = self
-            .client
-            .post(&self.base_url)
-            .json(&api_request)
-            .send()
-            .await
-            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+        let response = self.send_generate(&api_request).await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -152,15 +225,25 @@ impl AiProvider for OllamaProvider {
             .await
             .map_err(|e| AetherError::ProviderError(e.to_string()))?;
 
-        let code = strip_code_blocks(&gen_response.response);
+        let code = request.output_code(&gen_response.response);
 
+        // Older servers send no done_reason, so their truncated output is
+        // never continued by `continue_until_complete`
         Ok(GenerationResponse {
             code,
             tokens_used: gen_response.eval_count,
-            metadata: None,
+            metadata: gen_response.done_reason.map(|reason| {
+                serde_json::json!({
+                    "finish_reason": if reason == "length" { "length" } else { "stop" },
+                })
+            }),
         })
     }
 
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
@@ -168,15 +251,14 @@ impl AiProvider for OllamaProvider {
         let client = self.client.clone();
         let model = self.model.clone();
         let base_url = self.base_url.clone();
+        let max_request_bytes = self.max_request_bytes;
 
-        let system = Some(request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        }));
+        let PromptPreview { system, user } = self.preview_prompt(&request);
 
-        let temperature = request.slot.temperature.unwrap_or(0.7);
+        let temperature = request.slot.temperature.or(request.default_temperature).unwrap_or(0.7);
         let api_request = GenerateRequest {
             model: request.model.clone().unwrap_or_else(|| model.clone()),
-            prompt: request.slot.prompt.clone(),
+            prompt: user,
             system,
             stream: true,
             options: Some(GenerateOptions {
@@ -186,6 +268,11 @@ impl AiProvider for OllamaProvider {
         };
 
         let stream = async_stream::stream! {
+            if let Err(e) = crate::util::check_request_size(&api_request, max_request_bytes) {
+                yield Err(e);
+                return;
+            }
+
             let response = client
                 .post(&base_url)
                 .json(&api_request)
@@ -211,29 +298,26 @@ impl AiProvider for OllamaProvider {
                 return;
             }
 
-            let mut stream = response.bytes_stream();
-            
-            while let Some(chunk_result) = stream.next().await {
-                let chunk = match chunk_result {
-                    Ok(c) => c,
+            let mut lines = Box::pin(crate::util::lines(response.bytes_stream()));
+
+            while let Some(line_result) = lines.next().await {
+                let line = match line_result {
+                    Ok(l) => l,
                     Err(e) => {
                         yield Err(aether_core::AetherError::NetworkError(e.to_string()));
                         break;
                     }
                 };
 
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    let line = line.trim();
-                    if line.is_empty() { continue; }
-                    
-                    if let Ok(gen_resp) = serde_json::from_str::<GenerateResponse>(line) {
-                        yield Ok(StreamResponse {
-                            delta: gen_resp.response,
-                            metadata: None,
-                        });
-                        if gen_resp.done { break; }
-                    }
+                let line = line.trim();
+                if line.is_empty() { continue; }
+                
+                if let Ok(gen_resp) = serde_json::from_str::<GenerateResponse>(line) {
+                    yield Ok(StreamResponse {
+                        delta: gen_resp.response,
+                        metadata: None,
+                    });
+                    if gen_resp.done { break; }
                 }
             }
         };
@@ -252,20 +336,16 @@ impl AiProvider for OllamaProvider {
 
         Ok(response.status().is_success())
     }
-}
 
-/// Strip markdown code blocks from generated code.
-fn strip_code_blocks(code: &str) -> String {
-    let code = code.trim();
-
-    if code.starts_with("```") && code.ends_with("```") {
-        let lines: Vec<&str> = code.lines().collect();
-        if lines.len() >= 2 {
-            return lines[1..lines.len() - 1].join("\n");
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        let system = request.system_prompt.clone().unwrap_or_else(|| {
+            self.build_system_prompt(&request.slot.kind, request.kind_instruction.as_deref(), request.context.as_deref())
+        });
+        PromptPreview {
+            system: Some(system),
+            user: request.slot.prompt.clone(),
         }
     }
-
-    code.to_string()
 }
 
 #[cfg(test)]
@@ -277,4 +357,79 @@ mod tests {
         let provider = OllamaProvider::new("codellama");
         assert_eq!(provider.model, "codellama");
     }
+
+    #[tokio::test]
+    async fn test_auto_pull_missing_model() {
+        use aether_core::Slot;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({
+                "error": "model 'codellama' not found, try pulling it first"
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/pull"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "status": "success" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "response": "fn main() {}",
+                "done": true
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OllamaProvider::with_options("codellama", format!("{}/api/generate", server.uri()))
+            .with_auto_pull(true);
+        let request = GenerationRequest::builder(Slot::new("main", "Write main")).build();
+        let response = provider.generate(request).await.unwrap();
+        assert_eq!(response.code, "fn main() {}");
+
+        let paths: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.url.path().to_string())
+            .collect();
+        assert_eq!(paths, vec!["/api/generate", "/api/pull", "/api/generate"]);
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_mock_server() {
+        let server = crate::testing::ollama_server("fn main() {}").await;
+        let provider = OllamaProvider::with_options("codellama", crate::testing::url(&server, "/api/generate"));
+
+        let response = provider.generate(crate::testing::request("main", "Write main")).await.unwrap();
+        assert_eq!(response.code, "fn main() {}");
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_connection_pool() {
+        let (base, connections) = crate::testing::connection_counting_server(serde_json::json!({
+            "response": "fn main() {}",
+            "done": true
+        }))
+        .await;
+        let provider = OllamaProvider::with_options("codellama", format!("{}/api/generate", base));
+
+        for _ in 0..3 {
+            let response = provider
+                .clone()
+                .generate(crate::testing::request("main", "Write main"))
+                .await
+                .unwrap();
+            assert_eq!(response.code, "fn main() {}");
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
 }
@@ -4,7 +4,7 @@
 
 use aether_core::{
     AetherError, AiProvider, ProviderConfig, Result,
-    provider::{GenerationRequest, GenerationResponse},
+    provider::{GenerationRequest, GenerationResponse, PromptPreview},
     SlotKind,
 };
 use async_trait::async_trait;
@@ -14,10 +14,14 @@ use tracing::{debug, instrument};
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 
+/// OpenAI accepts temperatures in 0.0–2.0; values outside are clamped.
+const MAX_TEMPERATURE: f32 = 2.0;
+
 /// OpenAI provider for code generation.
 #[derive(Debug, Clone)]
 pub struct OpenAiProvider {
     client: Client,
+    stream_client: Client,
     config: ProviderConfig,
 }
 
@@ -32,6 +36,12 @@ struct ChatRequest {
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    /// Number of completions to generate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    /// End-user identifier for abuse monitoring.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
 }
 
 /// Chat message.
@@ -46,16 +56,40 @@ struct ChatMessage {
 struct ChatResponse {
     choices: Vec<ChatChoice>,
     usage: Option<Usage>,
+    /// Backend configuration identifier; changes when the model is updated.
+    system_fingerprint: Option<String>,
+    model: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ChatChoice {
     message: ChatMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Usage {
     total_tokens: u32,
+    #[serde(default)]
+    prompt_tokens: Option<u32>,
+    #[serde(default)]
+    completion_tokens: Option<u32>,
+}
+
+/// Model, token split and fingerprint of a response, for reports and pricing.
+fn response_metadata(response: &ChatResponse, requested_model: &str) -> serde_json::Value {
+    let mut metadata = serde_json::json!({
+        "model": response.model.as_deref().unwrap_or(requested_model),
+    });
+    if let Some(usage) = &response.usage {
+        metadata["input_tokens"] = usage.prompt_tokens.into();
+        metadata["output_tokens"] = usage.completion_tokens.into();
+    }
+    if let Some(fp) = &response.system_fingerprint {
+        metadata["system_fingerprint"] = fp.clone().into();
+    }
+    metadata
 }
 
 /// OpenAI streaming response chunk.
@@ -79,13 +113,18 @@ struct ChatStreamDelta {
 impl OpenAiProvider {
     /// Create a new OpenAI provider with the given configuration.
     pub fn new(config: ProviderConfig) -> Result<Self> {
-        let timeout = config.timeout_seconds.unwrap_or(60);
         let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(timeout))
+            .timeout(config.request_timeout())
+            .user_agent(config.user_agent())
+            .build()
+            .map_err(|e| AetherError::NetworkError(e.to_string()))?;
+        let stream_client = Client::builder()
+            .timeout(config.stream_timeout())
+            .user_agent(config.user_agent())
             .build()
             .map_err(|e| AetherError::NetworkError(e.to_string()))?;
 
-        Ok(Self { client, config })
+        Ok(Self { client, stream_client, config })
     }
 
     /// Create a provider from environment variables.
@@ -106,7 +145,7 @@ impl OpenAiProvider {
     }
 
     /// Build the system prompt for code generation.
-    fn build_system_prompt(&self, kind: &SlotKind, context: Option<&str>) -> String {
+    fn build_system_prompt(&self, kind: &SlotKind, kind_instruction: Option<&str>, context: Option<&str>) -> String {
         let base = "You are a code generation assistant. Generate only the requested code without explanations or markdown code blocks. Output raw code only.";
 
         let kind_specific = match kind {
@@ -116,9 +155,15 @@ impl OpenAiProvider {
             SlotKind::Function => "\nGenerate a complete function definition.",
             SlotKind::Class => "\nGenerate a complete class/struct definition.",
             SlotKind::Component => "\nGenerate a complete component with HTML, CSS, and JavaScript as needed.",
+            SlotKind::Json => "\nGenerate a single valid JSON value.",
             _ => "",
         };
 
+        // Custom kinds carry their instruction from the engine's kind registry
+        let kind_specific = kind_instruction
+            .map(|i| format!("\n{}", i))
+            .unwrap_or_else(|| kind_specific.to_string());
+
         let context_part = context
             .filter(|c| !c.is_empty())
             .map(|c| format!("\n\nContext:\n{}", c))
@@ -126,54 +171,53 @@ impl OpenAiProvider {
 
         format!("{}{}{}", base, kind_specific, context_part)
     }
-}
-
-use aether_core::provider::StreamResponse;
-use futures::stream::{BoxStream, StreamExt};
-
-#[async_trait]
-impl AiProvider for OpenAiProvider {
-    fn name(&self) -> &str {
-        "openai"
-    }
 
-    #[instrument(skip(self, request), fields(slot = %request.slot.name))]
-    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+    /// Send one chat completion request and return a response per choice.
+    async fn complete(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
         debug!("Generating code with OpenAI for slot: {}", request.slot.name);
 
         let api_key = self.config.resolve_api_key().await?;
 
-        let system_prompt = request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        });
-
+        let preview = self.preview_prompt(&request);
         let messages = vec![
             ChatMessage {
                 role: "system".to_string(),
-                content: system_prompt,
+                content: preview.system.unwrap_or_default(),
             },
             ChatMessage {
                 role: "user".to_string(),
-                content: request.slot.prompt.clone(),
+                content: preview.user,
             },
         ];
 
-        let temperature = request.slot.temperature.or(self.config.temperature);
+        let temperature = request.slot.temperature.or(self.config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = ChatRequest {
             model: request.model.clone().unwrap_or_else(|| self.config.model.clone()),
             messages,
             max_tokens: request.max_tokens.or(self.config.max_tokens),
             temperature,
             stream: None,
+            n: request.n.filter(|n| *n > 1),
+            user: request.end_user_id.clone(),
         };
 
         let url = self.config.base_url.as_deref().unwrap_or(OPENAI_API_URL);
 
-        let response = self
+        crate::util::check_request_size(&api_request, self.config.max_request_bytes)?;
+
+        let mut http_request = self
             .client
             .post(url)
             .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
+            .header("Content-Type", "application/json");
+        if let Some(ref key) = request.idempotency_key {
+            http_request = http_request.header("Idempotency-Key", key);
+        }
+        if let Some(ref id) = request.request_id {
+            http_request = http_request.header("X-Client-Request-Id", id);
+        }
+
+        let response = http_request
             .json(&api_request)
             .send()
             .await
@@ -181,6 +225,9 @@ impl AiProvider for OpenAiProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                self.config.report_rate_limited(&api_key);
+            }
             let body = response.text().await.unwrap_or_default();
             return Err(AetherError::ProviderError(format!(
                 "API error {}: {}",
@@ -193,56 +240,104 @@ impl AiProvider for OpenAiProvider {
             .await
             .map_err(|e| AetherError::ProviderError(e.to_string()))?;
 
-        let code = chat_response
+        let metadata = response_metadata(&chat_response, &api_request.model);
+        let total_tokens = chat_response.usage.as_ref().map(|u| u.total_tokens);
+
+        let responses = chat_response
             .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| {
+                // Strip markdown code blocks if present
+                let code = request.output_code(&choice.message.content);
+
+                // Validate against slot constraints
+                if let Err(errors) = request.slot.validate(&code) {
+                    debug!("Generated code failed validation: {:?}", errors);
+                    // For now, we'll still return the code but log the warning
+                }
 
-        // Strip markdown code blocks if present
-        let code = strip_code_blocks(&code);
+                let mut metadata = metadata.clone();
+                if let Some(ref reason) = choice.finish_reason {
+                    metadata["finish_reason"] = reason.clone().into();
+                }
 
-        // Validate against slot constraints
-        if let Err(errors) = request.slot.validate(&code) {
-            debug!("Generated code failed validation: {:?}", errors);
-            // For now, we'll still return the code but log the warning
-        }
+                GenerationResponse {
+                    code,
+                    // Usage covers the whole call; count it once
+                    tokens_used: if i == 0 { total_tokens } else { None },
+                    metadata: Some(metadata),
+                }
+            })
+            .collect();
+
+        Ok(responses)
+    }
+}
+
+use aether_core::provider::StreamResponse;
+use futures::stream::{BoxStream, StreamExt};
+
+#[async_trait]
+impl AiProvider for OpenAiProvider {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
 
-        Ok(GenerationResponse {
-            code,
-            tokens_used: chat_response.usage.map(|u| u.total_tokens),
-            metadata: None,
+    #[instrument(skip(self, request), fields(slot = %request.slot.name, request_id = ?request.request_id))]
+    async fn generate(&self, mut request: GenerationRequest) -> Result<GenerationResponse> {
+        // Only `generate_n` asks for more than one choice; don't pay for the rest
+        request.n = None;
+        let mut responses = self.complete(request).await?;
+        Ok(if responses.is_empty() {
+            GenerationResponse { code: String::new(), tokens_used: None, metadata: None }
+        } else {
+            responses.swap_remove(0)
         })
     }
 
+    /// Uses the native `n` parameter: one request returns every completion.
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        self.complete(request).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
     ) -> BoxStream<'static, Result<StreamResponse>> {
-        let client = self.client.clone();
+        let client = self.stream_client.clone();
         let config = self.config.clone();
-        let system_prompt = request.system_prompt.unwrap_or_else(|| {
-            self.build_system_prompt(&request.slot.kind, request.context.as_deref())
-        });
-        let user_prompt = request.slot.prompt.clone();
+        let preview = self.preview_prompt(&request);
+        let idempotency_key = request.idempotency_key.clone();
+        let request_id = request.request_id.clone();
         let url = config.base_url.as_deref().unwrap_or(OPENAI_API_URL).to_string();
 
-        let temperature = request.slot.temperature.or(config.temperature);
+        let temperature = request.slot.temperature.or(config.temperature).or(request.default_temperature).map(|t| t.clamp(0.0, MAX_TEMPERATURE));
         let api_request = ChatRequest {
             model: request.model.clone().unwrap_or_else(|| config.model.clone()),
             messages: vec![
                 ChatMessage {
                     role: "system".to_string(),
-                    content: system_prompt,
+                    content: preview.system.unwrap_or_default(),
                 },
                 ChatMessage {
                     role: "user".to_string(),
-                    content: user_prompt,
+                    content: preview.user,
                 },
             ],
             max_tokens: request.max_tokens.or(config.max_tokens),
             temperature,
             stream: Some(true),
+            n: None,
+            user: request.end_user_id.clone(),
         };
 
         let stream = async_stream::stream! {
@@ -254,10 +349,23 @@ impl AiProvider for OpenAiProvider {
                 }
             };
 
-            let response = client
+            if let Err(e) = crate::util::check_request_size(&api_request, config.max_request_bytes) {
+                yield Err(e);
+                return;
+            }
+
+            let mut http_request = client
                 .post(&url)
                 .header("Authorization", format!("Bearer {}", api_key))
-                .header("Content-Type", "application/json")
+                .header("Content-Type", "application/json");
+            if let Some(ref key) = idempotency_key {
+                http_request = http_request.header("Idempotency-Key", key);
+            }
+            if let Some(ref id) = request_id {
+                http_request = http_request.header("X-Client-Request-Id", id);
+            }
+
+            let response = http_request
                 .json(&api_request)
                 .send()
                 .await
@@ -273,6 +381,9 @@ impl AiProvider for OpenAiProvider {
 
             if !response.status().is_success() {
                 let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    config.report_rate_limited(&api_key);
+                }
                 let body = response.text().await.unwrap_or_default();
                 yield Err(aether_core::AetherError::ProviderError(format!(
                     "API error {}: {}",
@@ -281,11 +392,11 @@ impl AiProvider for OpenAiProvider {
                 return;
             }
 
-            let mut stream = response.bytes_stream();
-            
-            while let Some(chunk_result) = stream.next().await {
-                let chunk = match chunk_result {
-                    Ok(c) => c,
+            let mut lines = Box::pin(crate::util::lines(response.bytes_stream()));
+
+            while let Some(line_result) = lines.next().await {
+                let line = match line_result {
+                    Ok(l) => l,
                     Err(e) => {
                         yield Err(aether_core::AetherError::NetworkError(e.to_string()));
                         break;
@@ -293,21 +404,18 @@ impl AiProvider for OpenAiProvider {
                 };
 
                 // OpenAI stream format is SSE: "data: {...}"
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    let line = line.trim();
-                    if line.is_empty() { continue; }
-                    if line == "data: [DONE]" { break; }
-                    
-                    if let Some(data) = line.strip_prefix("data: ") {
-                        if let Ok(stream_resp) = serde_json::from_str::<ChatStreamResponse>(data) {
-                            if let Some(choice) = stream_resp.choices.first() {
-                                if let Some(content) = &choice.delta.content {
-                                    yield Ok(StreamResponse {
-                                        delta: content.clone(),
-                                        metadata: None,
-                                    });
-                                }
+                let line = line.trim();
+                if line.is_empty() { continue; }
+                if line == "data: [DONE]" { break; }
+                
+                if let Some(data) = line.strip_prefix("data: ") {
+                    if let Ok(stream_resp) = serde_json::from_str::<ChatStreamResponse>(data) {
+                        if let Some(choice) = stream_resp.choices.first() {
+                            if let Some(content) = &choice.delta.content {
+                                yield Ok(StreamResponse {
+                                    delta: content.clone(),
+                                    metadata: None,
+                                });
                             }
                         }
                     }
@@ -330,34 +438,35 @@ impl AiProvider for OpenAiProvider {
 
         Ok(response.status().is_success())
     }
-}
-
-/// Strip markdown code blocks from generated code.
-fn strip_code_blocks(code: &str) -> String {
-    let code = code.trim();
 
-    // Check for ```language\n...\n``` pattern
-    if code.starts_with("```") && code.ends_with("```") {
-        let lines: Vec<&str> = code.lines().collect();
-        if lines.len() >= 2 {
-            return lines[1..lines.len() - 1].join("\n");
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        let system = request.system_prompt.clone().unwrap_or_else(|| {
+            self.build_system_prompt(&request.slot.kind, request.kind_instruction.as_deref(), request.context.as_deref())
+        });
+        PromptPreview {
+            system: Some(system),
+            user: request.slot.prompt.clone(),
         }
     }
-
-    code.to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_strip_code_blocks() {
-        let input = "```html\n<div>Hello</div>\n```";
-        assert_eq!(strip_code_blocks(input), "<div>Hello</div>");
+    #[tokio::test]
+    async fn test_component_keeps_inner_fences() {
+        // Sibling blocks would lose their first and last fence lines to a
+        // plain strip
+        let component = "```html\n<button>Go</button>\n```\n\n```css\nbutton { color: red; }\n```";
+        let server = crate::testing::openai_server(component).await;
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(crate::testing::url(&server, "/v1/chat/completions"));
+        let provider = OpenAiProvider::new(config).unwrap();
 
-        let input = "<div>Already clean</div>";
-        assert_eq!(strip_code_blocks(input), "<div>Already clean</div>");
+        let mut request = crate::testing::request("button", "Write a button");
+        request.slot.kind = SlotKind::Component;
+        assert_eq!(provider.generate(request).await.unwrap().code, component);
     }
 
     #[test]
@@ -365,7 +474,342 @@ mod tests {
         let config = ProviderConfig::new("test-key", "gpt-4");
         let provider = OpenAiProvider::new(config).unwrap();
 
-        let prompt = provider.build_system_prompt(&SlotKind::Html, None);
+        let prompt = provider.build_system_prompt(&SlotKind::Html, None, None);
         assert!(prompt.contains("HTML5"));
     }
+
+    #[tokio::test]
+    async fn test_key_pool_skips_rate_limited_key() {
+        use aether_core::{KeyPool, Slot};
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("Authorization", "Bearer key-a"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header("Authorization", "Bearer key-b"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                "usage": {"total_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("unused", "gpt-4")
+            .with_base_url(format!("{}/v1/chat/completions", server.uri()))
+            .with_key_pool(KeyPool::new(["key-a", "key-b"]));
+        let provider = OpenAiProvider::new(config).unwrap();
+        let request = || GenerationRequest::builder(Slot::new("slot", "Say ok")).build();
+
+        assert!(provider.generate(request()).await.is_err());
+        assert_eq!(provider.generate(request()).await.unwrap().code, "ok");
+        assert_eq!(provider.generate(request()).await.unwrap().code, "ok");
+
+        let keys: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.headers.get("Authorization").unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(keys, ["Bearer key-a", "Bearer key-b", "Bearer key-b"]);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_stable_across_retries() {
+        use aether_core::{AetherConfig, InjectionEngine, Template};
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                "usage": {"total_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(format!("{}/v1/chat/completions", server.uri()));
+        let mut engine_config = AetherConfig::default().with_idempotency_keys(true);
+        engine_config.retry_backoff_ms = 1;
+        let engine = InjectionEngine::with_config(OpenAiProvider::new(config).unwrap(), engine_config);
+
+        let template = Template::new("{{AI:slot}}").with_slot("slot", "Say ok");
+        assert_eq!(engine.render(&template).await.unwrap(), "ok");
+
+        let keys: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.headers.get("Idempotency-Key").unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].starts_with("aether-"));
+        assert_eq!(keys[0], keys[1]);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_header_matches_observer() {
+        use aether_core::provider::GenerationResponse;
+        use aether_core::{EngineObserver, InjectionEngine, Template};
+        use std::sync::{Arc, Mutex};
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        struct IdObserver(Arc<Mutex<Vec<String>>>);
+
+        impl EngineObserver for IdObserver {
+            fn on_start(&self, id: &str, _template: &str, _slot: &str, _request: &GenerationRequest) {
+                self.0.lock().unwrap().push(id.to_string());
+            }
+            fn on_success(&self, _id: &str, _response: &GenerationResponse) {}
+            fn on_healing_step(&self, _id: &str, _attempt: u32, _error: &str) {}
+            fn on_failure(&self, _id: &str, _error: &str) {}
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                "usage": {"total_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(format!("{}/v1/chat/completions", server.uri()));
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let engine = InjectionEngine::new(OpenAiProvider::new(config).unwrap())
+            .with_observer(IdObserver(Arc::clone(&ids)));
+
+        let template = Template::new("{{AI:slot}}").with_slot("slot", "Say ok");
+        engine.render(&template).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let header = received[0].headers.get("X-Client-Request-Id").unwrap().to_str().unwrap();
+        assert_eq!(ids.lock().unwrap().as_slice(), [header.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_header() {
+        use aether_core::provider::DEFAULT_USER_AGENT;
+        use aether_core::Slot;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "ok"}}],
+                "usage": {"total_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+
+        let request = GenerationRequest::builder(Slot::new("slot", "Say ok")).build();
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(format!("{}/v1/chat/completions", server.uri()));
+        OpenAiProvider::new(config.clone()).unwrap().generate(request.clone()).await.unwrap();
+        OpenAiProvider::new(config.with_user_agent("ci-gateway/1.0"))
+            .unwrap()
+            .generate(request)
+            .await
+            .unwrap();
+
+        let agents: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.headers.get("User-Agent").unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert!(DEFAULT_USER_AGENT.starts_with("aether/"));
+        assert_eq!(agents, [DEFAULT_USER_AGENT, "ci-gateway/1.0"]);
+    }
+
+    #[tokio::test]
+    async fn test_generate_n_uses_native_n() {
+        use aether_core::Slot;
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [
+                    {"message": {"role": "assistant", "content": "a"}},
+                    {"message": {"role": "assistant", "content": "b"}}
+                ],
+                "usage": {"total_tokens": 8}
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(format!("{}/v1/chat/completions", server.uri()));
+        let provider = OpenAiProvider::new(config).unwrap();
+        let request = GenerationRequest::builder(Slot::new("slot", "Say something"))
+            .with_n(2)
+            .build();
+
+        let responses = provider.generate_n(request.clone()).await.unwrap();
+        let codes: Vec<&str> = responses.iter().map(|r| r.code.as_str()).collect();
+        assert_eq!(codes, ["a", "b"]);
+        assert_eq!(responses[0].tokens_used, Some(8));
+        assert_eq!(responses[1].tokens_used, None);
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["n"], 2);
+
+        // A single generation never asks for extra choices
+        let response = provider.generate(request).await.unwrap();
+        assert_eq!(response.code, "a");
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[1].body).unwrap();
+        assert!(body.get("n").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_continuation_joins_raw_chunks() {
+        use aether_core::{AetherConfig, InjectionEngine, Template};
+        use wiremock::matchers::{body_string_contains, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let completion = |content: &str, reason: &str| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": content}, "finish_reason": reason}],
+                "usage": {"total_tokens": 5}
+            }))
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("OUTPUT SO FAR"))
+            .respond_with(completion("    let y = 2;\n}\n```", "stop"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(completion("```rust\nfn main() {\n    let x = 1;\n", "length"))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(crate::testing::url(&server, "/v1/chat/completions"));
+        let engine_config = AetherConfig::default().with_continue_until_complete(true, 3);
+        let engine = InjectionEngine::with_config(OpenAiProvider::new(config).unwrap(), engine_config);
+
+        let output = engine.render(&Template::new("{{AI:main}}")).await.unwrap();
+        assert_eq!(output, "fn main() {\n    let x = 1;\n    let y = 2;\n}");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_max_cost_prices_default_model() {
+        use aether_core::{provider::DEFAULT_OPENAI_MODEL, AetherConfig, AetherError, InjectionEngine, Template};
+
+        let server = crate::testing::openai_server("ok").await;
+        let config = ProviderConfig::new("test-key", DEFAULT_OPENAI_MODEL)
+            .with_base_url(crate::testing::url(&server, "/v1/chat/completions"));
+        let engine_config = AetherConfig::default()
+            .with_parallel(false)
+            .with_max_cost_usd(Some(0.0));
+        let engine = InjectionEngine::with_config(OpenAiProvider::new(config).unwrap(), engine_config);
+
+        let result = engine.render(&Template::new("{{AI:a}} {{AI:b}}")).await;
+        assert!(matches!(result, Err(AetherError::CostLimitExceeded { .. })), "got {:?}", result);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        let server = crate::testing::openai_stream_server(&["fn ", "main() ", "{}"]).await;
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(crate::testing::url(&server, "/v1/chat/completions"));
+        let provider = OpenAiProvider::new(config).unwrap();
+
+        let chunks: Vec<String> = provider
+            .generate_stream(crate::testing::request("main", "Write main"))
+            .map(|chunk| chunk.unwrap().delta)
+            .collect()
+            .await;
+        assert_eq!(chunks, ["fn ", "main() ", "{}"]);
+    }
+
+    #[tokio::test]
+    async fn test_preview_matches_sent_messages() {
+        let server = crate::testing::openai_server("ok").await;
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(crate::testing::url(&server, "/v1/chat/completions"));
+        let provider = OpenAiProvider::new(config).unwrap();
+        let mut request = crate::testing::request("main", "Write main");
+        request.slot.kind = SlotKind::Function;
+        request.context = Some("Project: demo".to_string());
+
+        let preview = provider.preview_prompt(&request);
+        provider.generate(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["messages"][0]["content"], preview.system.unwrap().as_str());
+        assert_eq!(body["messages"][1]["content"], preview.user.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_end_user_id_sent_as_user() {
+        use crate::testing;
+
+        let server = testing::openai_server("fn main() {}").await;
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(testing::url(&server, "/v1/chat/completions"));
+        let provider = OpenAiProvider::new(config).unwrap();
+
+        provider.generate(testing::request("anon", "Say hi")).await.unwrap();
+        let mut request = testing::request("tenant", "Say hi");
+        request.end_user_id = Some("tenant-42".to_string());
+        provider.generate(request).await.unwrap();
+
+        let bodies: Vec<serde_json::Value> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.body_json().unwrap())
+            .collect();
+        assert!(bodies[0].get("user").is_none());
+        assert_eq!(bodies[1]["user"], "tenant-42");
+    }
+
+    #[tokio::test]
+    async fn test_request_too_large_fails_before_sending() {
+        use crate::testing;
+
+        let server = testing::openai_server("fn main() {}").await;
+        let config = ProviderConfig::new("test-key", "gpt-4")
+            .with_base_url(testing::url(&server, "/v1/chat/completions"))
+            .with_max_request_bytes(256);
+        let provider = OpenAiProvider::new(config).unwrap();
+
+        let mut request = testing::request("big", "Summarize the context");
+        request.context = Some("x".repeat(4096));
+        let result = provider.generate(request).await;
+
+        assert!(matches!(
+            result,
+            Err(AetherError::RequestTooLarge { limit: 256, bytes }) if bytes > 4096
+        ));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
 }
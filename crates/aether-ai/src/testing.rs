@@ -0,0 +1,162 @@
+//! In-process mock servers for end-to-end provider tests.
+//!
+//! Each helper starts a `wiremock` server that answers every POST with a
+//! canned response in the provider's wire format. Point the provider's
+//! `base_url` at the returned server to exercise the full HTTP round trip:
+//!
+//! ```rust,ignore
+//! let server = aether_ai::testing::openai_server("fn main() {}").await;
+//! let config = ProviderConfig::new("test-key", "gpt-4")
+//!     .with_base_url(aether_ai::testing::url(&server, "/v1/chat/completions"));
+//! ```
+//!
+//! Available to other crates with the `dev` feature.
+
+use aether_core::provider::GenerationRequest;
+use aether_core::Slot;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Full URL of `path` on `server`.
+pub fn url(server: &MockServer, path: &str) -> String {
+    format!("{}{}", server.uri(), path)
+}
+
+/// A plain request for `slot` with the given prompt.
+pub fn request(slot: &str, prompt: &str) -> GenerationRequest {
+    GenerationRequest::builder(Slot::new(slot, prompt)).build()
+}
+
+async fn serve(body: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(body).mount(&server).await;
+    server
+}
+
+/// Server answering with an OpenAI chat completion.
+pub async fn openai_server(content: &str) -> MockServer {
+    serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "choices": [{"message": {"role": "assistant", "content": content}, "finish_reason": "stop"}],
+        "usage": {"total_tokens": 5, "prompt_tokens": 3, "completion_tokens": 2}
+    })))
+    .await
+}
+
+/// Server streaming `chunks` as OpenAI SSE deltas, ending with `[DONE]`.
+pub async fn openai_stream_server(chunks: &[&str]) -> MockServer {
+    let mut body = String::new();
+    for chunk in chunks {
+        let event = serde_json::json!({"choices": [{"delta": {"content": chunk}, "finish_reason": null}]});
+        body.push_str(&format!("data: {}\n\n", event));
+    }
+    body.push_str("data: [DONE]\n\n");
+    serve(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream")).await
+}
+
+/// Server answering with an Anthropic message.
+pub async fn anthropic_server(content: &str) -> MockServer {
+    serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "content": [{"type": "text", "text": content}],
+        "usage": {"input_tokens": 3, "output_tokens": 2},
+        "stop_reason": "end_turn"
+    })))
+    .await
+}
+
+/// Server answering with a Gemini `generateContent` response.
+pub async fn gemini_server(content: &str) -> MockServer {
+    serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "candidates": [{"content": {"parts": [{"text": content}], "role": "model"}, "finishReason": "STOP"}]
+    })))
+    .await
+}
+
+/// Server answering with a Cohere chat response.
+pub async fn cohere_server(content: &str) -> MockServer {
+    serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "text": content,
+        "finish_reason": "COMPLETE",
+        "meta": {"billed_units": {"input_tokens": 3, "output_tokens": 2}}
+    })))
+    .await
+}
+
+/// Server streaming `chunks` as Cohere `text-generation` events, one JSON
+/// object per line, ending with `stream-end`.
+pub async fn cohere_stream_server(chunks: &[&str]) -> MockServer {
+    let mut body = String::new();
+    body.push_str(&format!("{}\n", serde_json::json!({"is_finished": false, "event_type": "stream-start"})));
+    for chunk in chunks {
+        let event = serde_json::json!({"is_finished": false, "event_type": "text-generation", "text": chunk});
+        body.push_str(&format!("{}\n", event));
+    }
+    let end = serde_json::json!({"is_finished": true, "event_type": "stream-end", "finish_reason": "COMPLETE"});
+    body.push_str(&format!("{}\n", end));
+    serve(ResponseTemplate::new(200).set_body_raw(body, "application/stream+json")).await
+}
+
+/// Server answering with a non-streamed Ollama generation.
+pub async fn ollama_server(content: &str) -> MockServer {
+    serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "response": content,
+        "done": true
+    })))
+    .await
+}
+
+/// Keep-alive HTTP server answering every request with `body` as JSON.
+///
+/// Returns the base URL and the number of TCP connections accepted so far,
+/// so tests can check that a client reuses its connection pool.
+pub async fn connection_counting_server(body: serde_json::Value) -> (String, Arc<AtomicUsize>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&connections);
+    let body = body.to_string();
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let body = body.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = socket.into_split();
+                let mut reader = BufReader::new(read);
+                loop {
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line == "\r\n" {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                length = value.trim().parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    let mut payload = vec![0; length];
+                    if reader.read_exact(&mut payload).await.is_err() {
+                        return;
+                    }
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    if write.write_all(response.as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    (base, connections)
+}
@@ -0,0 +1,112 @@
+//! Shared helpers for provider implementations.
+
+use aether_core::{AetherError, Result};
+use futures::stream::{Stream, StreamExt};
+use serde::Serialize;
+
+/// Fail with `RequestTooLarge` if `body` serializes to more than `limit` bytes.
+/// The body is only serialized when a limit is set.
+pub(crate) fn check_request_size<T: Serialize>(body: &T, limit: Option<usize>) -> Result<()> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+    let bytes = serde_json::to_vec(body)?.len();
+    if bytes > limit {
+        return Err(AetherError::RequestTooLarge { bytes, limit });
+    }
+    Ok(())
+}
+
+/// Accumulates bytes and yields only complete lines.
+///
+/// Network chunks can end in the middle of a line (or a UTF-8 sequence),
+/// so the trailing partial line is kept until the next chunk completes it.
+#[derive(Debug, Default)]
+pub(crate) struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Append a chunk and return the lines it completed, without line endings.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+
+        let mut lines = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        lines
+    }
+
+    /// Return the final unterminated line, if any.
+    pub(crate) fn finish(self) -> Option<String> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(&self.pending).into_owned())
+        }
+    }
+}
+
+/// Split a byte stream into complete lines.
+pub(crate) fn lines<S, B, E>(bytes: S) -> impl Stream<Item = std::result::Result<String, E>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    async_stream::stream! {
+        let mut bytes = Box::pin(bytes);
+        let mut buffer = LineBuffer::default();
+
+        while let Some(chunk) = bytes.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    for line in buffer.push(chunk.as_ref()) {
+                        yield Ok(line);
+                    }
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            }
+        }
+
+        if let Some(line) = buffer.finish() {
+            yield Ok(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_event_split_across_chunks() {
+        let event = "data: {\"choices\":[{\"delta\":{\"content\":\"héllo wörld\"}}]}\n\n";
+        let (first, second) = event.as_bytes().split_at(30);
+        let chunks: Vec<std::result::Result<&[u8], ()>> = vec![Ok(first), Ok(second)];
+
+        let lines: Vec<String> = lines(futures::stream::iter(chunks))
+            .map(|l| l.unwrap())
+            .collect()
+            .await;
+        assert_eq!(lines, vec![event.trim_end().to_string(), String::new()]);
+
+        let data: serde_json::Value = serde_json::from_str(lines[0].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(data["choices"][0]["delta"]["content"], "héllo wörld");
+    }
+
+    #[test]
+    fn test_split_utf8_sequence() {
+        let mut buffer = LineBuffer::default();
+        let bytes = "ö\n".as_bytes();
+
+        assert!(buffer.push(&bytes[..1]).is_empty());
+        assert_eq!(buffer.push(&bytes[1..]), vec!["ö".to_string()]);
+        assert!(buffer.finish().is_none());
+    }
+}
@@ -0,0 +1,18 @@
+use aether_core::provider::MockProvider;
+use aether_macros::aether_secure;
+
+#[aether_secure(prompt = "Add the two inputs.")]
+async fn add(a: i64, b: i64) -> i64 {
+    unreachable!()
+}
+
+#[tokio::test]
+async fn test_secure_script_generated_once() {
+    std::env::set_var("AETHER_PROVIDER", "mock");
+    std::env::set_var("AETHER_MOCK_SCRIPT", "a + b");
+    std::env::remove_var("AETHER_SECURE_REFRESH");
+
+    assert_eq!(add(2, 3).await, 5);
+    assert_eq!(add(10, 20).await, 30);
+    assert_eq!(MockProvider::shared().call_count(), 1);
+}
@@ -0,0 +1,19 @@
+use aether_core::provider::MockProvider;
+use aether_macros::aether_secure;
+
+#[aether_secure(prompt = "Tell whether the count exceeds the limit.")]
+async fn over_limit(count: i64, limit: i64) -> bool {
+    unreachable!()
+}
+
+#[tokio::test]
+async fn test_secure_prompt_includes_signature_types() {
+    std::env::set_var("AETHER_PROVIDER", "mock");
+    std::env::set_var("AETHER_MOCK_SCRIPT", "count > limit");
+
+    assert!(over_limit(5, 3).await);
+
+    let prompt = MockProvider::shared().requests()[0].slot.prompt.clone();
+    assert!(prompt.contains("(count: i64, limit: i64)"), "prompt: {}", prompt);
+    assert!(prompt.contains("Rust type `bool`"), "prompt: {}", prompt);
+}
@@ -1,10 +1,11 @@
-use aether_core::{InjectionEngine, Template};
+use aether_core::provider::{DEFAULT_ANTHROPIC_MODEL, DEFAULT_COHERE_MODEL, DEFAULT_GEMINI_MODEL, DEFAULT_GROK_MODEL, DEFAULT_OLLAMA_MODEL, DEFAULT_OPENAI_MODEL};
+use aether_core::{default_model, AetherConfig, AiProvider, InjectionEngine, PromptPreview, ProviderConfig, RenderReport, SlotKind, Template};
 use std::sync::Arc;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use dotenvy::dotenv;
 use log::{info, error};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -32,6 +33,14 @@ enum Commands {
         /// Model name (optional, uses provider default if not specified)
         #[arg(short, long)]
         model: Option<String>,
+
+        /// API key for the provider (bypasses environment variables)
+        #[arg(long, conflicts_with = "api_key_file")]
+        api_key: Option<String>,
+
+        /// Read the provider API key from a file
+        #[arg(long)]
+        api_key_file: Option<PathBuf>,
         
         /// Specific prompt override for a slot (format: slot_name=prompt)
         #[arg(long)]
@@ -64,6 +73,22 @@ enum Commands {
         /// Port for Aether Inspector UI (default: 3000)
         #[arg(long, default_value_t = 3000)]
         inspect_port: u16,
+
+        /// Output format for stdout
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Ask the model to explain each slot; written to <output>.md (or stderr)
+        #[arg(long)]
+        explain: bool,
+
+        /// Abort if the render's estimated or actual cost exceeds this many USD
+        #[arg(long)]
+        max_cost: Option<f64>,
+
+        /// Print the prompts each slot would send, without calling the provider
+        #[arg(long)]
+        dry_run: bool,
     },
     
     /// Initialize a new Aether configuration (Coming Soon)
@@ -79,6 +104,48 @@ enum ProviderType {
     Gemini,
     Ollama,
     Grok,
+    Cohere,
+    /// First provider whose API key is set (OpenAI > Anthropic > Gemini > Cohere), else Ollama
+    Auto,
+}
+
+impl ProviderType {
+    /// Resolve `Auto` to a concrete provider, reading keys through `env`.
+    ///
+    /// An explicit `--api-key` cannot say which provider it belongs to, so
+    /// `Auto` refuses to fall back to Ollama when one is given.
+    fn resolve(self, env: impl Fn(&str) -> Option<String>, explicit_key: bool) -> Result<ProviderType> {
+        if self != ProviderType::Auto {
+            return Ok(self);
+        }
+        let candidates = [
+            ("OPENAI_API_KEY", ProviderType::Openai),
+            ("ANTHROPIC_API_KEY", ProviderType::Anthropic),
+            ("GOOGLE_API_KEY", ProviderType::Gemini),
+            ("COHERE_API_KEY", ProviderType::Cohere),
+        ];
+        let chosen = candidates
+            .into_iter()
+            .find(|(var, _)| env(var).is_some_and(|v| !v.trim().is_empty()))
+            .map(|(_, provider)| provider);
+        let chosen = match chosen {
+            Some(provider) => provider,
+            None if explicit_key => anyhow::bail!(
+                "--provider auto found no provider API key in the environment; pass --provider to say which provider the given API key is for"
+            ),
+            None => ProviderType::Ollama,
+        };
+        info!("Auto-selected provider: {:?}", chosen);
+        Ok(chosen)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum OutputFormat {
+    /// Rendered output as plain text
+    Text,
+    /// A single JSON object with output, tokens, timing and per-slot details
+    Json,
 }
 
 #[tokio::main]
@@ -93,9 +160,9 @@ async fn main() -> Result<()> {
 
     match &cli.command {
         Commands::Generate { 
-            template, output, provider, model, set, 
+            template, output, provider, model, api_key, api_key_file, set, 
             stream, heal, cache, toon, temp, 
-            inspect, inspect_port 
+            inspect, inspect_port, format, explain, max_cost, dry_run
         } => {
             info!("Reading template from {:?}", template);
             
@@ -122,40 +189,44 @@ async fn main() -> Result<()> {
                 }
             }
 
-            let provider_obj: Arc<dyn aether_core::AiProvider + Send + Sync> = match provider {
-                ProviderType::Openai => {
-                    if let Some(m) = model { Arc::new(aether_ai::openai(m)?) } 
-                    else { Arc::new(aether_ai::OpenAiProvider::from_env()?) }
-                }
-                ProviderType::Anthropic => {
-                    if let Some(m) = model { Arc::new(aether_ai::anthropic(m)?) } 
-                    else { Arc::new(aether_ai::AnthropicProvider::from_env()?) }
-                }
-                ProviderType::Gemini => {
-                    if let Some(m) = model { Arc::new(aether_ai::gemini(m)?) } 
-                    else { Arc::new(aether_ai::GeminiProvider::from_env()?) }
-                }
-                ProviderType::Ollama => {
-                    let m = model.as_deref().unwrap_or("codellama");
-                    Arc::new(aether_ai::ollama(m))
-                }
-                ProviderType::Grok => {
-                    let m = model.as_deref().unwrap_or("grok-1");
-                    Arc::new(aether_ai::grok(m)?)
-                }
+            if let Some(out_path) = output {
+                tmpl = apply_default_kind(tmpl, out_path);
+            }
+
+            let api_key = resolve_api_key(api_key.clone(), api_key_file.as_deref())?;
+            let provider_obj = if *dry_run {
+                dry_run_provider(*provider, model.as_deref(), api_key)?
+            } else {
+                build_provider(*provider, model.as_deref(), api_key)?
             };
 
             let mut config = AetherConfig::from_env()
                 .with_healing(*heal)
                 .with_toon(*toon)
                 .with_inspector(*inspect)
-                .with_inspector_port(*inspect_port);
+                .with_inspector_port(*inspect_port)
+                .with_explain(*explain);
             
             if *cache {
                 config = config.with_cache(true);
             }
+            if max_cost.is_some() {
+                config = config.with_max_cost_usd(*max_cost);
+            }
+            let max_cost = config.max_cost_usd;
 
             let mut engine = InjectionEngine::with_config_arc(provider_obj, config);
+            if *dry_run {
+                let previews = engine.preview_prompts(&tmpl).context("Failed to compose prompts")?;
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&dry_run_json(&previews))?),
+                    OutputFormat::Text => print!("{}", dry_run_text(&previews)),
+                }
+                return Ok(());
+            }
+            if let Some(cap) = max_cost {
+                check_max_cost(&engine, &tmpl, model.as_deref(), cap)?;
+            }
             
             // Setup Inspector if enabled
             if *inspect {
@@ -174,7 +245,43 @@ async fn main() -> Result<()> {
                 info!("🚀 Aether Inspector UI active at http://localhost:{}", port);
             }
 
-            run_generation(engine, tmpl, output, *stream).await?;
+            if *format == OutputFormat::Json || *explain {
+                if *stream {
+                    info!("Streaming is not supported with --format json or --explain. Rendering normally.");
+                }
+                let report = engine.render_detailed(&tmpl).await.context("Code generation failed")?;
+                if let Some(out_path) = output {
+                    tokio::fs::write(out_path, &report.output)
+                        .await
+                        .context("Failed to write output file")?;
+                }
+                if let Some(markdown) = report.explanations_markdown() {
+                    match output {
+                        Some(out_path) => {
+                            let md_path = explanation_path(out_path);
+                            tokio::fs::write(&md_path, markdown)
+                                .await
+                                .context("Failed to write explanation file")?;
+                            info!("Explanation written to {:?}", md_path);
+                        }
+                        None => eprintln!("{}", markdown),
+                    }
+                }
+                if *format == OutputFormat::Json {
+                    println!("{}", report_json(&report)?);
+                } else if output.is_none() {
+                    println!("{}", report.output);
+                }
+            } else {
+                // Streamed chunks report no token usage to check the cap against
+                let stream = if *stream && max_cost.is_some() {
+                    info!("Streaming is not supported with --max-cost. Rendering normally.");
+                    false
+                } else {
+                    *stream
+                };
+                run_generation(engine, tmpl, output, stream).await?;
+            }
         }
         Commands::Init => {
             println!("Initializing Aether project... (Not implemented yet)");
@@ -184,10 +291,158 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Human-readable listing of the prompts each slot would send.
+fn dry_run_text(previews: &[(String, PromptPreview)]) -> String {
+    let mut text = String::new();
+    for (slot, preview) in previews {
+        text.push_str(&format!("=== {} ===\n", slot));
+        if let Some(system) = &preview.system {
+            text.push_str(&format!("[system]\n{}\n", system));
+        }
+        text.push_str(&format!("[user]\n{}\n\n", preview.user));
+    }
+    text
+}
+
+/// `--dry-run --format json` output: one `{ slot, system, user }` object
+/// per slot, in generation order.
+fn dry_run_json(previews: &[(String, PromptPreview)]) -> serde_json::Value {
+    previews
+        .iter()
+        .map(|(slot, preview)| {
+            serde_json::json!({
+                "slot": slot,
+                "system": preview.system,
+                "user": preview.user,
+            })
+        })
+        .collect()
+}
+
+/// Build the provider for `--dry-run`, which composes prompts but never
+/// sends them, so a missing API key is replaced by an empty one.
+fn dry_run_provider(
+    provider: ProviderType,
+    model: Option<&str>,
+    api_key: Option<String>,
+) -> Result<Arc<dyn AiProvider + Send + Sync>> {
+    let provider = provider.resolve(|var| std::env::var(var).ok(), false)?;
+    let api_key = api_key.or_else(|| (provider != ProviderType::Ollama).then(String::new));
+    build_provider(provider, model, api_key)
+}
+
+/// Abort before rendering when the planned cost exceeds `cap` USD, or when
+/// it cannot be priced at all, since the cap could then not be enforced.
+fn check_max_cost<P>(engine: &InjectionEngine<P>, tmpl: &Template, model: Option<&str>, cap: f64) -> Result<()>
+where
+    P: AiProvider + ?Sized + 'static,
+{
+    let plan = engine.plan(tmpl).context("Failed to plan render")?;
+    let default = model
+        .or_else(|| engine.provider().model())
+        .or_else(|| default_model(engine.provider().name()))
+        .context("--max-cost needs a model to price; pass --model")?;
+    let estimate = engine
+        .estimate_plan_cost(&plan, default)
+        .context("--max-cost needs a known price for every slot")?;
+    if estimate > cap {
+        anyhow::bail!(
+            "Estimated cost ${:.4} for {} slot(s) exceeds --max-cost ${:.4}; aborting before rendering",
+            estimate,
+            plan.slots.len(),
+            cap
+        );
+    }
+    info!("Estimated cost ${:.4} (limit ${:.4})", estimate, cap);
+    Ok(())
+}
+
+/// Resolve an explicitly passed API key, from the flag or a key file.
+/// Returns `None` when neither is given so providers fall back to env vars.
+fn resolve_api_key(api_key: Option<String>, api_key_file: Option<&Path>) -> Result<Option<String>> {
+    if let Some(key) = api_key {
+        return Ok(Some(key));
+    }
+    if let Some(path) = api_key_file {
+        let key = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read API key file {:?}", path))?;
+        let key = key.trim().to_string();
+        if key.is_empty() {
+            anyhow::bail!("API key file {:?} is empty", path);
+        }
+        return Ok(Some(key));
+    }
+    Ok(None)
+}
+
+/// Build the provider. An explicit API key is passed straight into the
+/// `ProviderConfig`, so it never has to be placed in the environment.
+fn build_provider(
+    provider: ProviderType,
+    model: Option<&str>,
+    api_key: Option<String>,
+) -> Result<Arc<dyn AiProvider + Send + Sync>> {
+    let provider = provider.resolve(|var| std::env::var(var).ok(), api_key.is_some())?;
+    let Some(key) = api_key else {
+        let provider_obj: Arc<dyn AiProvider + Send + Sync> = match provider {
+            ProviderType::Openai => {
+                if let Some(m) = model { Arc::new(aether_ai::openai(m)?) } 
+                else { Arc::new(aether_ai::OpenAiProvider::from_env()?) }
+            }
+            ProviderType::Anthropic => {
+                if let Some(m) = model { Arc::new(aether_ai::anthropic(m)?) } 
+                else { Arc::new(aether_ai::AnthropicProvider::from_env()?) }
+            }
+            ProviderType::Gemini => {
+                if let Some(m) = model { Arc::new(aether_ai::gemini(m)?) } 
+                else { Arc::new(aether_ai::GeminiProvider::from_env()?) }
+            }
+            ProviderType::Ollama => {
+                let m = model.unwrap_or(DEFAULT_OLLAMA_MODEL);
+                Arc::new(aether_ai::ollama(m))
+            }
+            ProviderType::Grok => {
+                let m = model.unwrap_or(DEFAULT_GROK_MODEL);
+                Arc::new(aether_ai::grok(m)?)
+            }
+            ProviderType::Cohere => {
+                if let Some(m) = model { Arc::new(aether_ai::cohere(m)?) }
+                else { Arc::new(aether_ai::CohereProvider::from_env()?) }
+            }
+            ProviderType::Auto => unreachable!("resolved above"),
+        };
+        return Ok(provider_obj);
+    };
+
+    let config = |default: &str| ProviderConfig::new(key.clone(), model.unwrap_or(default));
+    let provider_obj: Arc<dyn AiProvider + Send + Sync> = match provider {
+        ProviderType::Openai => Arc::new(aether_ai::OpenAiProvider::new(config(DEFAULT_OPENAI_MODEL))?),
+        ProviderType::Anthropic => Arc::new(aether_ai::AnthropicProvider::new(config(DEFAULT_ANTHROPIC_MODEL))?),
+        ProviderType::Gemini => Arc::new(aether_ai::GeminiProvider::new(config(DEFAULT_GEMINI_MODEL))?),
+        ProviderType::Ollama => {
+            info!("Ollama does not use an API key; ignoring it.");
+            Arc::new(aether_ai::ollama(model.unwrap_or(DEFAULT_OLLAMA_MODEL)))
+        }
+        ProviderType::Grok => Arc::new(aether_ai::OpenAiProvider::new(
+            config(DEFAULT_GROK_MODEL).with_base_url("https://api.x.ai/v1/chat/completions"),
+        )?),
+        ProviderType::Cohere => Arc::new(aether_ai::CohereProvider::new(config(DEFAULT_COHERE_MODEL))?),
+        ProviderType::Auto => unreachable!("resolved above"),
+    };
+    Ok(provider_obj)
+}
+
 async fn run_generation<P>(engine: InjectionEngine<P>, tmpl: Template, output: &Option<PathBuf>, stream: bool) -> Result<()> 
 where 
-    P: aether_core::AiProvider + Send + Sync + 'static,
+    P: aether_core::AiProvider + Send + Sync + ?Sized + 'static,
 {
+    let stream = if stream && !engine.provider().supports_streaming() {
+        info!("Provider '{}' does not support streaming. Falling back to normal rendering.", engine.provider().name());
+        false
+    } else {
+        stream
+    };
+
     if stream && tmpl.slots.len() == 1 {
         let slot_name = tmpl.slots.keys().next().unwrap().clone();
         info!("Streaming code generation for slot: {}", slot_name);
@@ -242,3 +497,165 @@ where
     }
     Ok(())
 }
+
+/// Give slots that still have the default kind the kind implied by the
+/// output file extension, so e.g. `--heal` validates `.rs` output as Rust.
+fn apply_default_kind(mut tmpl: Template, output: &Path) -> Template {
+    let Some(kind) = output
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(SlotKind::from_extension)
+    else {
+        return tmpl;
+    };
+
+    for slot in tmpl.slots.values_mut() {
+        if slot.kind == SlotKind::Raw {
+            slot.kind = kind.clone();
+        }
+    }
+    tmpl
+}
+
+/// Sidecar path for explanations: `<output>.md`.
+fn explanation_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".md");
+    PathBuf::from(path)
+}
+
+/// Serialize a render report as a single-line JSON object.
+fn report_json(report: &RenderReport) -> Result<String> {
+    serde_json::to_string(report).context("Failed to serialize report")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aether_core::provider::MockProvider;
+
+    #[tokio::test]
+    async fn test_json_report_round_trip() {
+        let engine = InjectionEngine::new(MockProvider::new().with_response("body", "fn main() {}"));
+        let report = engine
+            .render_detailed(&Template::new("{{AI:body}}"))
+            .await
+            .unwrap();
+
+        let json = report_json(&report).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["output"], "fn main() {}");
+        assert!(value["tokens"].is_u64());
+        assert!(value["elapsed_ms"].is_u64());
+        assert_eq!(value["slots"]["body"]["code"], "fn main() {}");
+    }
+
+    #[test]
+    fn test_max_cost_aborts_before_render() {
+        let provider = Arc::new(MockProvider::new().with_model("mock-model"));
+        let config = AetherConfig::default()
+            .with_pricing(aether_core::PricingTable::new().with_price("mock", "", 1.0, 1.0));
+        let engine = InjectionEngine::with_config_arc(Arc::clone(&provider), config);
+        let tmpl = Template::new("{{AI:body}}").with_slot("body", "Write a large web server with routing and auth");
+
+        let err = check_max_cost(&engine, &tmpl, None, 0.0001).unwrap_err();
+        assert!(err.to_string().contains("exceeds --max-cost"), "got {}", err);
+        assert!(check_max_cost(&engine, &tmpl, None, 100.0).is_ok());
+        assert_eq!(provider.call_count(), 0);
+    }
+
+    #[test]
+    fn test_max_cost_with_default_provider_and_model() {
+        let tmpl = Template::new("{{AI:body}}").with_slot("body", "Write a large web server with routing and auth");
+
+        // The default provider and model are priced, so the cap applies
+        let provider = build_provider(ProviderType::Openai, None, Some("sk-test".to_string())).unwrap();
+        let engine = InjectionEngine::with_config_arc(provider, AetherConfig::default());
+        let err = check_max_cost(&engine, &tmpl, None, 0.0).unwrap_err();
+        assert!(err.to_string().contains("exceeds --max-cost"), "got {}", err);
+        assert!(check_max_cost(&engine, &tmpl, None, 100.0).is_ok());
+
+        // An unpriced model fails closed instead of rendering unchecked
+        let provider = build_provider(ProviderType::Openai, Some("my-finetune"), Some("sk-test".to_string())).unwrap();
+        let engine = InjectionEngine::with_config_arc(provider, AetherConfig::default());
+        let err = check_max_cost(&engine, &tmpl, Some("my-finetune"), 100.0).unwrap_err();
+        assert!(format!("{:#}", err).contains("no price for model 'my-finetune'"), "got {:#}", err);
+    }
+
+    #[test]
+    fn test_dry_run_lists_slot_prompts() {
+        let provider = Arc::new(MockProvider::new());
+        let engine = InjectionEngine::with_config_arc(Arc::clone(&provider), AetherConfig::default());
+        let tmpl = Template::new("{{AI:body}}").with_slot("body", "Write a greeting");
+
+        let text = dry_run_text(&engine.preview_prompts(&tmpl).unwrap());
+        assert!(text.starts_with("=== body ===\n"));
+        assert!(text.contains("[user]\nWrite a greeting"));
+        assert_eq!(provider.call_count(), 0);
+
+        let json = dry_run_json(&engine.preview_prompts(&tmpl).unwrap());
+        assert_eq!(json, serde_json::json!([{ "slot": "body", "system": null, "user": "Write a greeting" }]));
+    }
+
+    #[test]
+    fn test_dry_run_provider_needs_no_api_key() {
+        for provider in [ProviderType::Anthropic, ProviderType::Cohere, ProviderType::Ollama] {
+            assert!(dry_run_provider(provider, None, None).is_ok(), "{:?}", provider);
+        }
+    }
+
+    #[test]
+    fn test_explanation_path() {
+        assert_eq!(explanation_path(Path::new("out/index.html")), PathBuf::from("out/index.html.md"));
+    }
+
+    #[test]
+    fn test_default_kind_from_output_path() {
+        let tmpl = Template::new("{{AI:body}} {{AI:style:css}}");
+        let tmpl = apply_default_kind(tmpl, Path::new("src/lib.rs"));
+        assert_eq!(tmpl.slots["body"].kind, SlotKind::Function);
+        assert_eq!(tmpl.slots["style"].kind, SlotKind::Css);
+
+        let tmpl = apply_default_kind(Template::new("{{AI:body}}"), Path::new("notes.txt"));
+        assert_eq!(tmpl.slots["body"].kind, SlotKind::Raw);
+    }
+
+    #[test]
+    fn test_api_key_file_builds_provider_without_env() {
+        std::env::remove_var("OPENAI_API_KEY");
+        std::env::remove_var("AETHER_API_KEY");
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"sk-from-file\n").unwrap();
+
+        let key = resolve_api_key(None, Some(file.path())).unwrap();
+        assert_eq!(key.as_deref(), Some("sk-from-file"));
+
+        let provider = build_provider(ProviderType::Openai, None, key).unwrap();
+        assert_eq!(provider.name(), "openai");
+    }
+
+    #[test]
+    fn test_auto_provider_picks_anthropic() {
+        let env: std::collections::HashMap<&str, &str> = [("ANTHROPIC_API_KEY", "sk-ant-test"), ("GOOGLE_API_KEY", " ")].into();
+        let lookup = |var: &str| env.get(var).map(|v| v.to_string());
+
+        assert_eq!(ProviderType::Auto.resolve(lookup, false).unwrap(), ProviderType::Anthropic);
+        assert_eq!(ProviderType::Auto.resolve(lookup, true).unwrap(), ProviderType::Anthropic);
+        assert_eq!(ProviderType::Cohere.resolve(lookup, false).unwrap(), ProviderType::Cohere);
+    }
+
+    #[test]
+    fn test_auto_provider_without_env_keys() {
+        assert_eq!(ProviderType::Auto.resolve(|_| None, false).unwrap(), ProviderType::Ollama);
+        // An explicit key must not be silently dropped on Ollama
+        assert!(ProviderType::Auto.resolve(|_| None, true).is_err());
+    }
+
+    #[test]
+    fn test_build_cohere_provider_with_key() {
+        let provider = build_provider(ProviderType::Cohere, None, Some("co-test".to_string())).unwrap();
+        assert_eq!(provider.name(), "cohere");
+    }
+}
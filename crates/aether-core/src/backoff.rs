@@ -0,0 +1,76 @@
+//! Retry backoff with jitter.
+//!
+//! Delays grow linearly with the attempt number and are spread by a random
+//! factor so that parallel slots failing together do not retry in lockstep.
+//! The random source is seedable, making delays reproducible in tests.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Computes retry delays: `base * (attempt + 1)`, scaled by `1 ± jitter`.
+#[derive(Debug)]
+pub struct Backoff {
+    base_ms: u64,
+    jitter: f64,
+    state: Mutex<u64>,
+}
+
+impl Backoff {
+    /// Create a backoff with a randomly seeded jitter source.
+    ///
+    /// `jitter` is the maximum relative deviation, clamped to `0.0..=1.0`.
+    pub fn new(base_ms: u64, jitter: f64) -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Self::seeded(base_ms, jitter, seed)
+    }
+
+    /// Create a backoff whose jitter sequence is fixed by `seed`.
+    pub fn seeded(base_ms: u64, jitter: f64, seed: u64) -> Self {
+        Self {
+            base_ms,
+            jitter: jitter.clamp(0.0, 1.0),
+            state: Mutex::new(seed),
+        }
+    }
+
+    /// Delay before retrying after failed attempt `attempt` (0-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.base_ms * (attempt as u64 + 1);
+        if self.jitter == 0.0 {
+            return Duration::from_millis(base);
+        }
+
+        let factor = 1.0 + self.jitter * (2.0 * self.next_unit() - 1.0);
+        Duration::from_millis((base as f64 * factor).round() as u64)
+    }
+
+    /// Next value in `[0, 1)` from a SplitMix64 sequence.
+    fn next_unit(&self) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_backoff_sequence() {
+        let backoff = Backoff::seeded(100, 0.5, 42);
+        let delays: Vec<u64> = (0..3).map(|a| backoff.delay(a).as_millis() as u64).collect();
+        assert_eq!(delays, vec![124, 132, 234]);
+
+        // Without jitter the delays are exactly linear
+        let plain = Backoff::seeded(100, 0.0, 42);
+        let delays: Vec<u64> = (0..3).map(|a| plain.delay(a).as_millis() as u64).collect();
+        assert_eq!(delays, vec![100, 200, 300]);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Mutex;
 use dashmap::DashMap;
 use fastembed::{TextEmbedding, InitOptions, EmbeddingModel};
@@ -11,15 +13,59 @@ pub trait Cache: Send + Sync {
     
     /// Store a response in the cache.
     fn set(&self, prompt: &str, response: String);
+
+    /// All stored key/response pairs. Caches that cannot enumerate their
+    /// contents return nothing, which makes `export` write an empty file.
+    fn entries(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// Write all entries to `path` as a JSON object of key -> response.
+    /// Returns the number of entries written.
+    fn export(&self, path: &Path) -> Result<usize> {
+        let entries: BTreeMap<String, String> = self.entries().into_iter().collect();
+        std::fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(entries.len())
+    }
+
+    /// Load entries previously written by `export` into this cache.
+    /// Returns the number of entries imported.
+    fn import(&self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        let entries: BTreeMap<String, String> = serde_json::from_str(&content)?;
+        let count = entries.len();
+        for (key, response) in entries {
+            self.set(&key, response);
+        }
+        info!("Imported {} cache entries from {}", count, path.display());
+        Ok(count)
+    }
+}
+
+/// Computes the embedding of a cache key, see [`SemanticCache::with_embedder`].
+pub type EmbedFn = dyn Fn(&str) -> Option<Vec<f32>> + Send + Sync;
+
+/// Namespace of an engine cache key (`aether:cache:{namespace}:{fingerprint}`).
+///
+/// Keys without a namespace, and keys not written by the engine, share the
+/// empty namespace.
+fn key_namespace(key: &str) -> &str {
+    key.strip_prefix("aether:cache:")
+        .and_then(|rest| rest.rsplit_once(':'))
+        .map(|(namespace, _)| namespace)
+        .unwrap_or("")
 }
 
 /// A cache that uses semantic similarity to find matches.
 /// Useful when prompts are slightly different but intent is the same.
+///
+/// Entries are partitioned by key namespace, so a similar entry stored for
+/// one project is never served to another.
 pub struct SemanticCache {
-    model: Mutex<TextEmbedding>,
-    // Storage: Embedding -> Response
+    embed: Box<EmbedFn>,
+    // Storage: Namespace -> Key -> (Embedding, Response)
     // We use a simple in-memory map and search for now.
-    storage: DashMap<String, (Vec<f32>, String)>,
+    storage: DashMap<String, DashMap<String, (Vec<f32>, String)>>,
     threshold: f32,
 }
 
@@ -32,11 +78,21 @@ impl SemanticCache {
                 .with_show_download_progress(true)
         ).map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
 
-        Ok(Self {
-            model: Mutex::new(model),
+        let model = Mutex::new(model);
+        Ok(Self::with_embedder(move |text| {
+            let mut model = model.lock().ok()?;
+            model.embed(vec![text], None).ok()?.into_iter().next()
+        }))
+    }
+
+    /// Create a semantic cache that embeds keys with `embed` instead of the
+    /// default local model.
+    pub fn with_embedder(embed: impl Fn(&str) -> Option<Vec<f32>> + Send + Sync + 'static) -> Self {
+        Self {
+            embed: Box::new(embed),
             storage: DashMap::new(),
             threshold: 0.90, // Default 90% similarity
-        })
+        }
     }
 
     /// Set similarity threshold (0.0 to 1.0).
@@ -55,13 +111,13 @@ impl SemanticCache {
 
 impl Cache for SemanticCache {
     fn get(&self, prompt: &str) -> Option<String> {
-        let mut model = self.model.lock().ok()?;
-        let embedding = model.embed(vec![prompt], None).ok()?.first()?.clone();
+        let embedding = (self.embed)(prompt)?;
+        let namespace = self.storage.get(key_namespace(prompt))?;
         
         // Linear search for similarity (O(N) - fine for small/medium local caches)
         let mut best_match: Option<(f32, String)> = None;
 
-        for entry in self.storage.iter() {
+        for entry in namespace.iter() {
             let (stored_embedding, response) = entry.value();
             let similarity = Self::cosine_similarity(&embedding, stored_embedding);
             
@@ -81,16 +137,26 @@ impl Cache for SemanticCache {
     }
 
     fn set(&self, prompt: &str, response: String) {
-        let mut model = match self.model.lock() {
-            Ok(m) => m,
-            Err(_) => return,
-        };
-        if let Ok(embeddings) = model.embed(vec![prompt], None) {
-            if let Some(embedding) = embeddings.first() {
-                self.storage.insert(prompt.to_string(), (embedding.clone(), response));
-            }
+        if let Some(embedding) = (self.embed)(prompt) {
+            self.storage
+                .entry(key_namespace(prompt).to_string())
+                .or_default()
+                .insert(prompt.to_string(), (embedding, response));
         }
     }
+
+    fn entries(&self) -> Vec<(String, String)> {
+        self.storage
+            .iter()
+            .flat_map(|namespace| {
+                namespace
+                    .value()
+                    .iter()
+                    .map(|e| (e.key().clone(), e.value().1.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 }
 
 /// A simple exact match cache.
@@ -112,6 +178,13 @@ impl Cache for ExactCache {
     fn set(&self, prompt: &str, response: String) {
         self.storage.insert(prompt.to_string(), response);
     }
+
+    fn entries(&self) -> Vec<(String, String)> {
+        self.storage
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect()
+    }
 }
 
 /// A hybrid cache that balances speed (exact) and flexibility (semantic).
@@ -146,4 +219,43 @@ impl Cache for TieredCache {
         self.exact.set(prompt, response.clone());
         self.semantic.set(prompt, response);
     }
+
+    fn entries(&self) -> Vec<(String, String)> {
+        // Both tiers hold the same keys; the exact tier is cheaper to walk.
+        // Importing goes through `set`, so embeddings are rebuilt on load.
+        self.exact.entries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+
+        let source = ExactCache::new();
+        source.set("aether:cache:demo:1f", "fn main() {}".to_string());
+        source.set("aether:cache:demo:2a", "<h1>Hi</h1>".to_string());
+        assert_eq!(source.export(&path).unwrap(), 2);
+
+        let target = ExactCache::new();
+        assert_eq!(target.get("aether:cache:demo:1f"), None);
+        assert_eq!(target.import(&path).unwrap(), 2);
+        assert_eq!(target.get("aether:cache:demo:1f").as_deref(), Some("fn main() {}"));
+        assert_eq!(target.get("aether:cache:demo:2a").as_deref(), Some("<h1>Hi</h1>"));
+    }
+
+    #[test]
+    fn test_semantic_cache_partitioned_by_namespace() {
+        // Every key embeds to the same vector, so any lookup is a semantic match
+        let cache = SemanticCache::with_embedder(|_| Some(vec![1.0, 0.0]));
+        cache.set("aether:cache:web:1f", "web-x".to_string());
+
+        assert_eq!(cache.get("aether:cache:web:2a").as_deref(), Some("web-x"));
+        assert_eq!(cache.get("aether:cache:api:2a"), None);
+        assert_eq!(cache.get("aether:cache:2a"), None);
+    }
 }
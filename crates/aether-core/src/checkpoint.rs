@@ -0,0 +1,103 @@
+//! Checkpointing for long-running batch renders.
+//!
+//! A checkpoint records which entries of a batch (e.g. template files
+//! relative to a source directory) have already been rendered, so an
+//! interrupted job can resume without regenerating completed work.
+
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// On-disk record of completed batch entries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchCheckpoint {
+    /// Completed entries, as paths relative to the batch root.
+    pub completed: BTreeSet<String>,
+
+    /// Where the checkpoint is persisted.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl BatchCheckpoint {
+    /// Create an empty checkpoint persisted at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            completed: BTreeSet::new(),
+            path: path.into(),
+        }
+    }
+
+    /// Load a checkpoint from `path`, or start empty if it does not exist.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if !path.exists() {
+            return Ok(Self::new(path));
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let mut checkpoint: Self = serde_json::from_str(&content)?;
+        checkpoint.path = path;
+        Ok(checkpoint)
+    }
+
+    /// Path the checkpoint is persisted at.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check whether an entry has already been completed.
+    pub fn is_completed(&self, entry: &str) -> bool {
+        self.completed.contains(entry)
+    }
+
+    /// Filter `entries` down to the ones not yet completed.
+    pub fn pending<'a>(&self, entries: &'a [String]) -> Vec<&'a String> {
+        entries.iter().filter(|e| !self.is_completed(e)).collect()
+    }
+
+    /// Mark an entry as completed and persist the checkpoint.
+    pub fn mark_completed(&mut self, entry: impl Into<String>) -> Result<()> {
+        self.completed.insert(entry.into());
+        self.save()
+    }
+
+    /// Persist the checkpoint atomically.
+    ///
+    /// The content is written to a temporary file in the same directory
+    /// and renamed over the target, so a crash never leaves a partial file.
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let dir = self
+            .path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        std::io::Write::write_all(&mut tmp, content.as_bytes())?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_skips_completed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("render.checkpoint.json");
+
+        let mut checkpoint = BatchCheckpoint::load(&path).unwrap();
+        checkpoint.mark_completed("pages/index.html").unwrap();
+
+        // Simulate a restarted job
+        let resumed = BatchCheckpoint::load(&path).unwrap();
+        let entries = vec!["pages/index.html".to_string(), "pages/about.html".to_string()];
+        assert_eq!(resumed.pending(&entries), vec![&entries[1]]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Circuit breaker for failing providers.
+//!
+//! Tracks consecutive transport failures and short-circuits further
+//! provider calls once a threshold is reached within a time window.
+
+use crate::{AetherError, Result};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A simple consecutive-failure circuit breaker.
+///
+/// The circuit opens after `threshold` consecutive failures that all
+/// occurred within `window`. It closes again on the next success, or once
+/// the most recent failures fall outside the window.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: Option<u32>,
+    window: Duration,
+    failures: Mutex<VecDeque<Instant>>,
+}
+
+impl CircuitBreaker {
+    /// Create a new circuit breaker.
+    ///
+    /// A `threshold` of `None` disables the breaker entirely.
+    pub fn new(threshold: Option<u32>, window: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            failures: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Create a breaker that never opens.
+    pub fn disabled() -> Self {
+        Self::new(None, Duration::ZERO)
+    }
+
+    /// Check whether the circuit is currently open.
+    pub fn is_open(&self) -> bool {
+        let Some(threshold) = self.threshold else {
+            return false;
+        };
+        if threshold == 0 {
+            return false;
+        }
+
+        let mut failures = self.failures.lock().unwrap();
+        let now = Instant::now();
+        while let Some(first) = failures.front() {
+            if now.duration_since(*first) > self.window {
+                failures.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        failures.len() >= threshold as usize
+    }
+
+    /// Return `CircuitOpen` if the circuit is open, otherwise `Ok(())`.
+    pub fn check(&self) -> Result<()> {
+        if self.is_open() {
+            let failures = self.failures.lock().unwrap().len() as u32;
+            return Err(AetherError::CircuitOpen { failures });
+        }
+        Ok(())
+    }
+
+    /// Record a transport failure.
+    pub fn record_failure(&self) {
+        if self.threshold.is_none() {
+            return;
+        }
+        self.failures.lock().unwrap().push_back(Instant::now());
+    }
+
+    /// Record a successful call, resetting the consecutive failure count.
+    pub fn record_success(&self) {
+        if self.threshold.is_none() {
+            return;
+        }
+        self.failures.lock().unwrap().clear();
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(Some(2), Duration::from_secs(60));
+        assert!(breaker.check().is_ok());
+
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+        breaker.record_failure();
+        assert!(matches!(breaker.check(), Err(AetherError::CircuitOpen { failures: 2 })));
+
+        breaker.record_success();
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_disabled_never_opens() {
+        let breaker = CircuitBreaker::disabled();
+        for _ in 0..10 {
+            breaker.record_failure();
+        }
+        assert!(!breaker.is_open());
+    }
+}
@@ -3,7 +3,57 @@
 //! Central configuration management for the Aether framework.
 //! Supports loading from environment variables, files, and programmatic defaults.
 
+use crate::pricing::PricingTable;
+use crate::{AetherError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
+use std::path::Path;
+
+/// What to do when a request would exceed the model's context window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextOverflow {
+    /// Truncate `surrounding_code` until the request fits.
+    #[default]
+    Truncate,
+    /// Fail with `AetherError::ContextWindowExceeded`.
+    Error,
+}
+
+/// What to do when user-supplied context looks like a prompt injection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptInjectionPolicy {
+    /// Do not scan context.
+    #[default]
+    Off,
+    /// Replace suspicious phrases with `[REMOVED]`.
+    Strip,
+    /// Fail with `AetherError::PossiblePromptInjection`.
+    Error,
+}
+
+/// Known context windows (in tokens) for common models.
+fn default_context_windows() -> HashMap<String, usize> {
+    [
+        ("gpt-5.2-thinking", 400_000),
+        ("gpt-4o", 128_000),
+        ("gpt-4o-mini", 128_000),
+        ("gpt-4", 8_192),
+        ("claude-3-5-sonnet-latest", 200_000),
+        ("claude-3-opus-20240229", 200_000),
+        ("claude-opus-4-5", 200_000),
+        ("gemini-1.5-pro", 2_000_000),
+        ("command-r-plus", 128_000),
+        ("grok-1", 8_192),
+        ("codellama", 16_384),
+        ("llama3", 8_192),
+    ]
+    .into_iter()
+    .map(|(model, window)| (model.to_string(), window))
+    .collect()
+}
 
 /// Global configuration for the Aether engine.
 /// 
@@ -19,7 +69,12 @@ use std::env;
 ///     .with_toon(true)
 ///     .with_healing(true);
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Settings resolve with the precedence builder > env > file > default:
+/// `from_file` applies environment overrides on top of the file, and
+/// builders called afterwards win over both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AetherConfig {
     /// Enable TOON (Token-Oriented Object Notation) for context compression.
     /// Reduces token usage by 30-60% for structured data.
@@ -55,11 +110,47 @@ pub struct AetherConfig {
     /// Default: Some(2000), Env: AETHER_TOON_THRESHOLD=2000
     pub auto_toon_threshold: Option<usize>,
 
+    /// Partial TOON: when TOON is used, only context fields at least this many
+    /// characters long are compressed; smaller fields stay plain `key: value` lines.
+    /// If None, the whole context is TOON-serialized.
+    /// Default: None, Env: AETHER_TOON_PARTIAL_THRESHOLD=500
+    pub toon_partial_threshold: Option<usize>,
+
     /// Cache similarity threshold (0.0 - 1.0).
     /// Higher values require more similar prompts to hit the cache.
     /// Default: 0.90, Env: AETHER_CACHE_THRESHOLD=0.90
     pub cache_threshold: f32,
 
+    /// Include a hash of the template content in cache keys, so editing the
+    /// literal (non-slot) text of a template invalidates its cached slots.
+    /// Default: false, Env: AETHER_CACHE_TEMPLATE_HASH=true
+    pub cache_include_template_hash: bool,
+
+    /// Prefix for cache keys, so projects sharing one cache don't share entries.
+    /// If None, `InjectionContext::project` is used when set.
+    /// Default: None, Env: AETHER_CACHE_NAMESPACE=my-project
+    pub cache_namespace: Option<String>,
+
+    /// Collapse whitespace in slot prompts before computing cache and
+    /// incremental session keys, so cosmetic edits don't bust them.
+    /// Default: false, Env: AETHER_NORMALIZE_PROMPTS=true
+    pub normalize_prompts_for_cache: bool,
+
+    /// Re-run cache hits through the current validator and regenerate if they fail,
+    /// e.g. after adding a TDD harness to a slot with cached results.
+    /// Default: false, Env: AETHER_REVALIDATE_CACHE=true
+    pub revalidate_cache_hits: bool,
+
+    /// Send a deterministic idempotency key with each request (OpenAI only),
+    /// so retries after a network error are not billed twice.
+    /// Default: false, Env: AETHER_IDEMPOTENCY_KEYS=true
+    pub use_idempotency_keys: bool,
+
+    /// Repair near-miss JSON (fences, prose, trailing commas) in `Json` slots
+    /// before validation.
+    /// Default: false, Env: AETHER_JSON_REPAIR=true
+    pub json_repair: bool,
+
     /// Prompt header for TOON context block.
     pub prompt_toon_header: String,
 
@@ -69,11 +160,151 @@ pub struct AetherConfig {
     /// Feedback prefix for self-healing retries.
     pub prompt_healing_feedback: String,
 
+    /// Re-state slot constraints (limits, imports, test harness) in healing feedback.
+    /// Default: true, Env: AETHER_HEALING_CONSTRAINTS=false
+    pub healing_include_constraints: bool,
+
+    /// Maximum number of diagnostics quoted in healing feedback (errors first).
+    /// Default: 10, Env: AETHER_HEALING_MAX_DIAGNOSTICS=5
+    pub healing_max_diagnostics: usize,
+
     /// Notice added when TDD mode is active.
     pub prompt_tdd_notice: String,
 
+    /// Text prepended to every slot prompt (e.g. a team style guide).
+    /// The order is prefix, slot prompt, suffix; self-healing feedback is
+    /// appended after the suffix on retries.
+    /// Default: None, Env: AETHER_PROMPT_PREFIX
+    pub global_prompt_prefix: Option<String>,
+
+    /// Text appended to every slot prompt, after the slot's own prompt.
+    /// Default: None, Env: AETHER_PROMPT_SUFFIX
+    pub global_prompt_suffix: Option<String>,
+
+    /// Ask the model for a short rationale of each slot in `render_detailed`.
+    /// Default: false, Env: AETHER_EXPLAIN=true
+    pub explain: bool,
+
+    /// Instruction for the explanation call.
+    pub prompt_explain: String,
+
+    /// After a slot is generated (and validated), ask the model to critique
+    /// its draft against the prompt and revise it once. The revision replaces
+    /// the draft only if it passes validation. Independent of healing.
+    /// Default: false, Env: AETHER_SELF_CRITIQUE=true
+    pub self_critique: bool,
+
+    /// Instruction for the critique-and-revise call.
+    pub prompt_self_critique: String,
+
+    /// Send a slot's `default` with its prompt as a baseline to improve on.
+    /// Default: false, Env: AETHER_SEED_WITH_DEFAULT=true
+    pub seed_with_default: bool,
+
+    /// Introduces the slot default when `seed_with_default` is on.
+    pub prompt_default_seed: String,
+
+    /// When a response stops because it hit `max_tokens` (finish reason
+    /// `length`), ask the model to continue and append the result, up to
+    /// `max_continuations` extra calls. Needs a provider that reports the
+    /// finish reason; Ollama only does so from 0.1.39 on.
+    /// Default: false, Env: AETHER_CONTINUE_UNTIL_COMPLETE=true
+    pub continue_until_complete: bool,
+
+    /// Maximum continuation calls per generation.
+    /// Default: 4, Env: AETHER_MAX_CONTINUATIONS
+    pub max_continuations: u32,
+
+    /// Instruction for continuation calls.
+    pub prompt_continuation: String,
+
+    /// When a slot stream drops with a network error, re-issue the request
+    /// with the text received so far and keep streaming, up to
+    /// `max_stream_reconnects` times. Uses `prompt_continuation`.
+    /// Default: false, Env: AETHER_STREAM_RECONNECT=true
+    pub stream_reconnect: bool,
+
+    /// Maximum reconnects per slot stream.
+    /// Default: 2, Env: AETHER_MAX_STREAM_RECONNECTS
+    pub max_stream_reconnects: u32,
+
+    /// Appended to the prompt when the provider blocked the output as too
+    /// close to existing sources (e.g. Gemini `RECITATION`).
+    pub prompt_reword: String,
+
     /// Base delay for retry backoff in milliseconds.
     pub retry_backoff_ms: u64,
+
+    /// Maximum relative random deviation of retry delays (0.0 to 1.0).
+    /// Default: 0.0, Env: AETHER_RETRY_JITTER
+    pub retry_jitter: f64,
+
+    /// Fixed seed for the retry jitter, for reproducible delays in tests.
+    /// Default: None (random), Env: AETHER_RETRY_JITTER_SEED
+    pub retry_jitter_seed: Option<u64>,
+
+    /// Context window sizes in tokens, keyed by model name.
+    /// Looked up with the slot's model override, else the provider's model.
+    pub context_windows: HashMap<String, usize>,
+
+    /// Context window in tokens used when the model is unknown.
+    /// If None, requests for unknown models are not checked.
+    /// Default: None, Env: AETHER_CONTEXT_WINDOW=8192
+    pub context_window: Option<usize>,
+
+    /// Behavior when a request exceeds the context window.
+    /// Default: Truncate, Env: AETHER_CONTEXT_OVERFLOW=error
+    pub context_overflow: ContextOverflow,
+
+    /// Scanning of `surrounding_code` and variables for prompt injection.
+    /// Default: Off, Env: AETHER_PROMPT_INJECTION=strip|error
+    pub prompt_injection: PromptInjectionPolicy,
+
+    /// Consecutive provider failures before the circuit opens.
+    /// If None, the circuit breaker is disabled.
+    /// Default: None, Env: AETHER_CIRCUIT_THRESHOLD=5
+    pub circuit_breaker_threshold: Option<u32>,
+
+    /// Window in which consecutive failures are counted, in milliseconds.
+    /// Default: 30000, Env: AETHER_CIRCUIT_WINDOW_MS=30000
+    pub circuit_breaker_window_ms: u64,
+
+    /// Maximum slot prompt length in characters, checked before rendering.
+    /// If None, prompt length is not limited.
+    /// Default: None, Env: AETHER_MAX_PROMPT_CHARS=8000
+    pub max_prompt_chars: Option<usize>,
+
+    /// Upper bound for a whole render, across all slots and retries.
+    /// In-flight generations are cancelled when it expires.
+    /// If None, renders are not time-limited.
+    /// Default: None, Env: AETHER_RENDER_TIMEOUT_MS=30000
+    pub render_timeout_ms: Option<u64>,
+
+    /// Upper bound in USD for a render, priced with `pricing`. The render
+    /// stops once the cost of finished slots exceeds it, and fails before
+    /// the first request if a slot's model has no price. Streaming reports
+    /// no token usage, so streamed generation is refused while it is set.
+    /// If None, cost is not limited.
+    /// Default: None, Env: AETHER_MAX_COST=0.50
+    pub max_cost_usd: Option<f64>,
+
+    /// End-user identifier attached to every request for provider abuse
+    /// monitoring in multi-tenant apps.
+    /// Default: None
+    pub end_user_id: Option<String>,
+
+    /// Redact secrets from requests before they reach observers.
+    /// Default: true, Env: AETHER_REDACT=false
+    pub redact_enabled: bool,
+
+    /// Regex patterns for secrets to redact.
+    /// Default: AWS access keys, bearer tokens and `sk-` keys.
+    pub redact_patterns: Vec<String>,
+
+    /// Prices used to estimate `RenderReport::cost_usd`.
+    /// Default: `PricingTable::default()`.
+    #[serde(skip)]
+    pub pricing: PricingTable,
 }
 
 impl Default for AetherConfig {
@@ -87,12 +318,53 @@ impl Default for AetherConfig {
             inspector_port: 3000,
             max_retries: 2,
             auto_toon_threshold: Some(2000),
+            toon_partial_threshold: None,
             cache_threshold: 0.90,
+            cache_include_template_hash: false,
+            cache_namespace: None,
+            normalize_prompts_for_cache: false,
+            revalidate_cache_hits: false,
+            use_idempotency_keys: false,
+            json_repair: false,
             prompt_toon_header: "[CONTEXT:TOON]".to_string(),
             prompt_toon_note: "[TOON Protocol Note]\nTOON is a compact key:value mapping protocol. Each line represents 'key: value'. Use this context to inform your code generation, respecting the framework, language, and architectural constraints defined within.".to_string(),
             prompt_healing_feedback: "[SELF-HEALING FEEDBACK]\nYour previous output had validation errors. Please fix them and output ONLY the corrected code.\nERROR:\n".to_string(),
+            healing_include_constraints: true,
+            healing_max_diagnostics: 10,
             prompt_tdd_notice: "\n\nIMPORTANT: The system is running in TDD (Test-Driven Development) mode. Your code will be validated against compiler checks and functional tests. If possible, include unit tests in your response to help self-verify. If validation fails, you will receive feedback to fix the code.".to_string(),
+            global_prompt_prefix: None,
+            global_prompt_suffix: None,
+            explain: false,
+            prompt_explain: "Explain briefly (at most 5 sentences) how the following code fulfils the task and any notable design decisions. Do not repeat the code.".to_string(),
+            self_critique: false,
+            seed_with_default: false,
+            prompt_default_seed: "Start from this known-good baseline and improve on it:".to_string(),
+            continue_until_complete: false,
+            max_continuations: 4,
+            prompt_continuation: "Your previous output was cut off. Continue exactly where it stopped. Output only the remaining code, without repeating anything already written and without explanations or markdown.".to_string(),
+            stream_reconnect: false,
+            max_stream_reconnects: 2,
+            prompt_reword: "Write an original implementation in your own words rather than reproducing existing code.".to_string(),
+            prompt_self_critique: "Review the draft below against the requirements. Check correctness, edge cases and whether every requirement is met. Then output only the revised code, with no explanations or markdown. If the draft is already correct, output it unchanged.".to_string(),
             retry_backoff_ms: 100,
+            retry_jitter: 0.0,
+            retry_jitter_seed: None,
+            context_windows: default_context_windows(),
+            context_window: None,
+            context_overflow: ContextOverflow::Truncate,
+            prompt_injection: PromptInjectionPolicy::Off,
+            circuit_breaker_threshold: None,
+            circuit_breaker_window_ms: 30_000,
+            max_prompt_chars: None,
+            render_timeout_ms: None,
+            max_cost_usd: None,
+            end_user_id: None,
+            redact_enabled: true,
+            redact_patterns: crate::redact::DEFAULT_REDACT_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+            pricing: PricingTable::default(),
         }
     }
 }
@@ -101,60 +373,209 @@ impl AetherConfig {
     /// Create a new config from environment variables.
     /// Falls back to defaults for missing variables.
     pub fn from_env() -> Self {
-        let mut config = Self::default();
+        Self::default().apply_env()
+    }
+
+    /// Load a config from a TOML file, then apply environment overrides.
+    ///
+    /// Keys match the field names (e.g. `toon_enabled = true`); missing keys
+    /// keep their defaults. `pricing` is not read from the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::parse_file(path)?.apply_env())
+    }
+
+    fn parse_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        toml::from_str(&content).map_err(|e| {
+            AetherError::ConfigError(format!("{}: {}", path.as_ref().display(), e))
+        })
+    }
+
+    /// Override fields with any `AETHER_*` environment variables that are set.
+    pub fn apply_env(self) -> Self {
+        let vars: HashMap<String, String> = env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)))
+            .collect();
+        self.apply_vars(&vars)
+    }
+
+    /// Override fields from `AETHER_*` entries in `vars`, as
+    /// [`apply_env`](Self::apply_env) does with the process environment.
+    pub fn apply_vars(self, vars: &HashMap<String, String>) -> Self {
+        let mut config = self;
+        let var = |key: &str| vars.get(key).cloned();
 
-        if let Ok(v) = env::var("AETHER_TOON") {
+        if let Some(v) = var("AETHER_TOON") {
             config.toon_enabled = v.to_lowercase() == "true" || v == "1";
         }
-        if let Ok(v) = env::var("AETHER_HEALING") {
+        if let Some(v) = var("AETHER_HEALING") {
             config.healing_enabled = v.to_lowercase() == "true" || v == "1";
         }
-        if let Ok(v) = env::var("AETHER_CACHE") {
+        if let Some(v) = var("AETHER_CACHE") {
             config.cache_enabled = v.to_lowercase() == "true" || v == "1";
         }
-        if let Ok(v) = env::var("AETHER_PARALLEL") {
+        if let Some(v) = var("AETHER_CACHE_TEMPLATE_HASH") {
+            config.cache_include_template_hash = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_CACHE_NAMESPACE") {
+            config.cache_namespace = Some(v);
+        }
+        if let Some(v) = var("AETHER_NORMALIZE_PROMPTS") {
+            config.normalize_prompts_for_cache = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_REVALIDATE_CACHE") {
+            config.revalidate_cache_hits = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_IDEMPOTENCY_KEYS") {
+            config.use_idempotency_keys = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_JSON_REPAIR") {
+            config.json_repair = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_PARALLEL") {
             config.parallel = v.to_lowercase() != "false" && v != "0";
         }
-        if let Ok(v) = env::var("AETHER_INSPECT") {
+        if let Some(v) = var("AETHER_INSPECT") {
             config.inspector_enabled = v.to_lowercase() == "true" || v == "1";
         }
-        if let Ok(v) = env::var("AETHER_INSPECT_PORT") {
+        if let Some(v) = var("AETHER_INSPECT_PORT") {
             if let Ok(n) = v.parse() {
                 config.inspector_port = n;
             }
         }
-        if let Ok(v) = env::var("AETHER_MAX_RETRIES") {
+        if let Some(v) = var("AETHER_MAX_RETRIES") {
             if let Ok(n) = v.parse() {
                 config.max_retries = n;
             }
         }
-        if let Ok(v) = env::var("AETHER_TOON_THRESHOLD") {
+        if let Some(v) = var("AETHER_TOON_THRESHOLD") {
             if let Ok(n) = v.parse() {
                 config.auto_toon_threshold = Some(n);
             }
         }
-        if let Ok(v) = env::var("AETHER_CACHE_THRESHOLD") {
+        if let Some(v) = var("AETHER_TOON_PARTIAL_THRESHOLD") {
+            if let Ok(n) = v.parse() {
+                config.toon_partial_threshold = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_CACHE_THRESHOLD") {
             if let Ok(n) = v.parse() {
                 config.cache_threshold = n;
             }
         }
-        if let Ok(v) = env::var("AETHER_PROMPT_TOON_HEADER") {
+        if let Some(v) = var("AETHER_PROMPT_TOON_HEADER") {
             config.prompt_toon_header = v;
         }
-        if let Ok(v) = env::var("AETHER_PROMPT_TOON_NOTE") {
+        if let Some(v) = var("AETHER_PROMPT_TOON_NOTE") {
             config.prompt_toon_note = v;
         }
-        if let Ok(v) = env::var("AETHER_PROMPT_HEALING_FEEDBACK") {
+        if let Some(v) = var("AETHER_PROMPT_HEALING_FEEDBACK") {
             config.prompt_healing_feedback = v;
         }
-        if let Ok(v) = env::var("AETHER_PROMPT_TDD_NOTICE") {
+        if let Some(v) = var("AETHER_HEALING_CONSTRAINTS") {
+            config.healing_include_constraints = v.to_lowercase() != "false" && v != "0";
+        }
+        if let Some(v) = var("AETHER_HEALING_MAX_DIAGNOSTICS") {
+            if let Ok(n) = v.parse() {
+                config.healing_max_diagnostics = n;
+            }
+        }
+        if let Some(v) = var("AETHER_PROMPT_TDD_NOTICE") {
             config.prompt_tdd_notice = v;
         }
-        if let Ok(v) = env::var("AETHER_RETRY_BACKOFF") {
+        if let Some(v) = var("AETHER_PROMPT_PREFIX") {
+            config.global_prompt_prefix = Some(v);
+        }
+        if let Some(v) = var("AETHER_PROMPT_SUFFIX") {
+            config.global_prompt_suffix = Some(v);
+        }
+        if let Some(v) = var("AETHER_EXPLAIN") {
+            config.explain = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_SELF_CRITIQUE") {
+            config.self_critique = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_SEED_WITH_DEFAULT") {
+            config.seed_with_default = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_CONTINUE_UNTIL_COMPLETE") {
+            config.continue_until_complete = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_MAX_CONTINUATIONS") {
+            if let Ok(n) = v.parse() {
+                config.max_continuations = n;
+            }
+        }
+        if let Some(v) = var("AETHER_STREAM_RECONNECT") {
+            config.stream_reconnect = v.to_lowercase() == "true" || v == "1";
+        }
+        if let Some(v) = var("AETHER_MAX_STREAM_RECONNECTS") {
+            if let Ok(n) = v.parse() {
+                config.max_stream_reconnects = n;
+            }
+        }
+        if let Some(v) = var("AETHER_RETRY_BACKOFF") {
             if let Ok(n) = v.parse() {
                 config.retry_backoff_ms = n;
             }
         }
+        if let Some(v) = var("AETHER_RETRY_JITTER") {
+            if let Ok(n) = v.parse() {
+                config.retry_jitter = n;
+            }
+        }
+        if let Some(v) = var("AETHER_RETRY_JITTER_SEED") {
+            if let Ok(n) = v.parse() {
+                config.retry_jitter_seed = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_CONTEXT_WINDOW") {
+            if let Ok(n) = v.parse() {
+                config.context_window = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_CONTEXT_OVERFLOW") {
+            config.context_overflow = if v.to_lowercase() == "error" {
+                ContextOverflow::Error
+            } else {
+                ContextOverflow::Truncate
+            };
+        }
+        if let Some(v) = var("AETHER_PROMPT_INJECTION") {
+            config.prompt_injection = match v.to_lowercase().as_str() {
+                "strip" => PromptInjectionPolicy::Strip,
+                "error" => PromptInjectionPolicy::Error,
+                _ => PromptInjectionPolicy::Off,
+            };
+        }
+        if let Some(v) = var("AETHER_CIRCUIT_THRESHOLD") {
+            if let Ok(n) = v.parse() {
+                config.circuit_breaker_threshold = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_CIRCUIT_WINDOW_MS") {
+            if let Ok(n) = v.parse() {
+                config.circuit_breaker_window_ms = n;
+            }
+        }
+        if let Some(v) = var("AETHER_MAX_PROMPT_CHARS") {
+            if let Ok(n) = v.parse() {
+                config.max_prompt_chars = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_RENDER_TIMEOUT_MS") {
+            if let Ok(n) = v.parse() {
+                config.render_timeout_ms = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_MAX_COST") {
+            if let Ok(n) = v.parse() {
+                config.max_cost_usd = Some(n);
+            }
+        }
+        if let Some(v) = var("AETHER_REDACT") {
+            config.redact_enabled = v.to_lowercase() != "false" && v != "0";
+        }
 
         config
     }
@@ -171,12 +592,116 @@ impl AetherConfig {
         self
     }
 
+    /// Builder: Enable or disable per-slot explanations in detailed renders.
+    pub fn with_explain(mut self, enabled: bool) -> Self {
+        self.explain = enabled;
+        self
+    }
+
+    /// Builder: Enable or disable the critique-and-revise pass.
+    pub fn with_self_critique(mut self, enabled: bool) -> Self {
+        self.self_critique = enabled;
+        self
+    }
+
+    /// Builder: Send slot defaults as a baseline in the prompt.
+    pub fn with_seed_with_default(mut self, enabled: bool) -> Self {
+        self.seed_with_default = enabled;
+        self
+    }
+
+    /// Builder: Continue truncated responses, with at most `max` extra calls.
+    pub fn with_continue_until_complete(mut self, enabled: bool, max: u32) -> Self {
+        self.continue_until_complete = enabled;
+        self.max_continuations = max;
+        self
+    }
+
+    /// Builder: Reconnect dropped slot streams, at most `max` times per slot.
+    pub fn with_stream_reconnect(mut self, enabled: bool, max: u32) -> Self {
+        self.stream_reconnect = enabled;
+        self.max_stream_reconnects = max;
+        self
+    }
+
     /// Builder: Enable or disable Semantic Cache.
     pub fn with_cache(mut self, enabled: bool) -> Self {
         self.cache_enabled = enabled;
         self
     }
 
+    /// Builder: Include the template content hash in cache keys.
+    pub fn with_cache_template_hash(mut self, enabled: bool) -> Self {
+        self.cache_include_template_hash = enabled;
+        self
+    }
+
+    /// Builder: Re-validate cache hits before serving them.
+    pub fn with_revalidate_cache_hits(mut self, enabled: bool) -> Self {
+        self.revalidate_cache_hits = enabled;
+        self
+    }
+
+    /// Builder: Send idempotency keys with provider requests.
+    pub fn with_idempotency_keys(mut self, enabled: bool) -> Self {
+        self.use_idempotency_keys = enabled;
+        self
+    }
+
+    /// Builder: Repair near-miss JSON output in `Json` slots.
+    pub fn with_json_repair(mut self, enabled: bool) -> Self {
+        self.json_repair = enabled;
+        self
+    }
+
+    /// Builder: Prefix cache keys with a namespace (e.g. a project id).
+    pub fn with_cache_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.cache_namespace = Some(namespace.into());
+        self
+    }
+
+    /// Builder: Normalize prompt whitespace in cache and session keys.
+    pub fn with_normalized_prompts(mut self, enabled: bool) -> Self {
+        self.normalize_prompts_for_cache = enabled;
+        self
+    }
+
+    /// Builder: Set the pricing table used for cost estimates.
+    pub fn with_pricing(mut self, pricing: PricingTable) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Builder: Set text prepended to every slot prompt.
+    pub fn with_prompt_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.global_prompt_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Builder: Set text appended to every slot prompt.
+    pub fn with_prompt_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.global_prompt_suffix = Some(suffix.into());
+        self
+    }
+
+    /// Builder: Only TOON-compress context fields of at least `threshold` characters.
+    pub fn with_partial_toon(mut self, threshold: Option<usize>) -> Self {
+        self.toon_partial_threshold = threshold;
+        self
+    }
+
+    /// Builder: Spread retry delays by up to `jitter` (0.0 to 1.0) of their length.
+    pub fn with_retry_jitter(mut self, jitter: f64) -> Self {
+        self.retry_jitter = jitter;
+        self
+    }
+
+    /// Builder: Seed the retry jitter so delays are reproducible.
+    pub fn with_retry_jitter_seed(mut self, seed: u64) -> Self {
+        self.retry_jitter_seed = Some(seed);
+        self
+    }
+
     /// Builder: Enable or disable parallel generation.
     pub fn with_parallel(mut self, enabled: bool) -> Self {
         self.parallel = enabled;
@@ -201,12 +726,109 @@ impl AetherConfig {
         self
     }
 
+    /// Builder: Cap the number of diagnostics quoted in healing feedback.
+    pub fn with_healing_max_diagnostics(mut self, max: usize) -> Self {
+        self.healing_max_diagnostics = max;
+        self
+    }
+
     /// Builder: Set auto TOON threshold.
     pub fn with_auto_toon_threshold(mut self, threshold: Option<usize>) -> Self {
         self.auto_toon_threshold = threshold;
         self
     }
 
+    /// Builder: Set the fallback context window (tokens).
+    pub fn with_context_window(mut self, tokens: Option<usize>) -> Self {
+        self.context_window = tokens;
+        self
+    }
+
+    /// Builder: Set the context window for a specific model.
+    pub fn with_model_context_window(mut self, model: impl Into<String>, tokens: usize) -> Self {
+        self.context_windows.insert(model.into(), tokens);
+        self
+    }
+
+    /// Builder: Set the context overflow behavior.
+    pub fn with_context_overflow(mut self, overflow: ContextOverflow) -> Self {
+        self.context_overflow = overflow;
+        self
+    }
+
+    /// Builder: Set how suspected prompt injection in context is handled.
+    pub fn with_prompt_injection(mut self, policy: PromptInjectionPolicy) -> Self {
+        self.prompt_injection = policy;
+        self
+    }
+
+    /// Look up the context window for a model, falling back to `context_window`.
+    pub fn context_window_for(&self, model: Option<&str>) -> Option<usize> {
+        model
+            .and_then(|m| self.context_windows.get(m).copied())
+            .or(self.context_window)
+    }
+
+    /// Builder: Configure the circuit breaker.
+    pub fn with_circuit_breaker(mut self, threshold: Option<u32>, window_ms: u64) -> Self {
+        self.circuit_breaker_threshold = threshold;
+        self.circuit_breaker_window_ms = window_ms;
+        self
+    }
+
+    /// Builder: Limit the total time of a render.
+    pub fn with_render_timeout_ms(mut self, timeout_ms: Option<u64>) -> Self {
+        self.render_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Builder: Limit the total cost of a render in USD.
+    pub fn with_max_cost_usd(mut self, max_cost: Option<f64>) -> Self {
+        self.max_cost_usd = max_cost;
+        self
+    }
+
+    /// Builder: Attach an end-user identifier to every request.
+    pub fn with_end_user_id(mut self, id: impl Into<String>) -> Self {
+        self.end_user_id = Some(id.into());
+        self
+    }
+
+    /// Builder: Limit slot prompt length in characters.
+    pub fn with_max_prompt_chars(mut self, max: Option<usize>) -> Self {
+        self.max_prompt_chars = max;
+        self
+    }
+
+    /// Builder: Enable or disable secret redaction for observers.
+    pub fn with_redaction(mut self, enabled: bool) -> Self {
+        self.redact_enabled = enabled;
+        self
+    }
+
+    /// Builder: Add a regex pattern for secrets to redact.
+    pub fn with_redact_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.redact_patterns.push(pattern.into());
+        self
+    }
+
+    /// Build the redactor described by this config.
+    pub fn redactor(&self) -> crate::Redactor {
+        if self.redact_enabled {
+            crate::Redactor::from_patterns(&self.redact_patterns)
+        } else {
+            crate::Redactor::new()
+        }
+    }
+
+    /// Build the retry backoff described by this config.
+    pub fn backoff(&self) -> crate::Backoff {
+        match self.retry_jitter_seed {
+            Some(seed) => crate::Backoff::seeded(self.retry_backoff_ms, self.retry_jitter, seed),
+            None => crate::Backoff::new(self.retry_backoff_ms, self.retry_jitter),
+        }
+    }
+
     /// Check if TOON should be used for a given context length.
     pub fn should_use_toon(&self, context_length: usize) -> bool {
         if self.toon_enabled {
@@ -250,6 +872,53 @@ mod tests {
         assert_eq!(config.max_retries, 5);
     }
 
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aether.toml");
+        std::fs::write(
+            &path,
+            r#"
+toon_enabled = true
+healing_enabled = true
+context_overflow = "error"
+prompt_toon_header = "[CTX]"
+
+[context_windows]
+my-model = 4096
+"#,
+        )
+        .unwrap();
+
+        let config = AetherConfig::from_file(&path).unwrap();
+        assert!(config.toon_enabled);
+        assert!(config.healing_enabled);
+        assert_eq!(config.context_overflow, ContextOverflow::Error);
+        assert_eq!(config.prompt_toon_header, "[CTX]");
+        assert_eq!(config.context_window_for(Some("my-model")), Some(4096));
+        // Unset keys keep their defaults
+        assert!(config.parallel);
+        assert_eq!(config.retry_backoff_ms, 100);
+
+        std::fs::write(&path, "toon_enabled = \"yes\"").unwrap();
+        assert!(matches!(AetherConfig::from_file(&path), Err(AetherError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aether.toml");
+        std::fs::write(&path, "inspector_port = 4000\nmax_retries = 7").unwrap();
+
+        let vars = HashMap::from([("AETHER_INSPECT_PORT".to_string(), "5000".to_string())]);
+        let config = AetherConfig::parse_file(&path).unwrap().apply_vars(&vars);
+        assert_eq!(config.inspector_port, 5000);
+        assert_eq!(config.max_retries, 7);
+
+        // Builders win over both
+        assert_eq!(config.with_max_retries(1).max_retries, 1);
+    }
+
     #[test]
     fn test_auto_toon() {
         let config = AetherConfig::default();
@@ -38,6 +38,10 @@ pub struct InjectionContext {
 
     /// Additional metadata.
     pub extra: HashMap<String, serde_json::Value>,
+
+    /// Pre-built TOON context, injected verbatim instead of re-serializing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_toon: Option<String>,
 }
 
 /// Coding style preferences.
@@ -109,6 +113,8 @@ impl Hash for InjectionContext {
             k.hash(state);
             serde_json::to_string(v).unwrap_or_default().hash(state);
         }
+
+        self.raw_toon.hash(state);
     }
 }
 
@@ -166,6 +172,48 @@ impl InjectionContext {
         self
     }
 
+    /// Set a pre-built TOON context string.
+    ///
+    /// When set, the engine injects this string verbatim as the TOON block
+    /// instead of serializing the context itself.
+    pub fn with_raw_toon(mut self, toon: impl Into<String>) -> Self {
+        self.raw_toon = Some(toon.into());
+        self
+    }
+
+    /// Layer `other` on top of this context.
+    ///
+    /// Fields set in `other` win; `available_imports` are unioned (keeping
+    /// order, without duplicates) and `variables`/`extra` are merged with
+    /// `other`'s values taking precedence. Useful for combining a base project
+    /// context with a per-feature one before `render_with_context`.
+    pub fn merge(&self, other: &InjectionContext) -> InjectionContext {
+        let mut available_imports = self.available_imports.clone();
+        for import in &other.available_imports {
+            if !available_imports.contains(import) {
+                available_imports.push(import.clone());
+            }
+        }
+
+        let mut variables = self.variables.clone();
+        variables.extend(other.variables.clone());
+        let mut extra = self.extra.clone();
+        extra.extend(other.extra.clone());
+
+        InjectionContext {
+            project: other.project.clone().or_else(|| self.project.clone()),
+            language: other.language.clone().or_else(|| self.language.clone()),
+            framework: other.framework.clone().or_else(|| self.framework.clone()),
+            architecture: other.architecture.clone().or_else(|| self.architecture.clone()),
+            style: other.style.clone().or_else(|| self.style.clone()),
+            surrounding_code: other.surrounding_code.clone().or_else(|| self.surrounding_code.clone()),
+            available_imports,
+            variables,
+            extra,
+            raw_toon: other.raw_toon.clone().or_else(|| self.raw_toon.clone()),
+        }
+    }
+
     /// Convert context to a prompt string for AI.
     pub fn to_prompt(&self) -> String {
         let mut parts = Vec::new();
@@ -201,7 +249,7 @@ impl InjectionContext {
         }
 
         if !self.available_imports.is_empty() {
-            parts.push(format!("Available imports: {}", self.available_imports.join(", ")));
+            parts.push(format!("You may use these imports: {}", self.available_imports.join(", ")));
         }
 
         if let Some(ref code) = self.surrounding_code {
@@ -250,4 +298,26 @@ mod tests {
         assert!(prompt.contains("Project: test"));
         assert!(prompt.contains("Language: rust"));
     }
+
+    #[test]
+    fn test_context_merge() {
+        let base = InjectionContext::new()
+            .with_framework("react")
+            .with_language("javascript")
+            .add_import("react")
+            .set_variable("theme", "light")
+            .set_variable("api", "/v1");
+        let feature = InjectionContext::new()
+            .with_language("ts")
+            .add_import("react")
+            .add_import("zod")
+            .set_variable("theme", "dark");
+
+        let merged = base.merge(&feature);
+        assert_eq!(merged.framework.as_deref(), Some("react"));
+        assert_eq!(merged.language.as_deref(), Some("ts"));
+        assert_eq!(merged.available_imports, vec!["react", "zod"]);
+        assert_eq!(merged.variables["theme"], "dark");
+        assert_eq!(merged.variables["api"], "/v1");
+    }
 }
@@ -59,4 +59,90 @@ pub enum AetherError {
     /// Timeout occurred.
     #[error("Operation timed out after {0} seconds")]
     Timeout(u64),
+
+    /// The composed prompt does not fit in the model's context window.
+    #[error("Context window exceeded: request needs ~{needed} tokens but the limit is {limit}")]
+    ContextWindowExceeded { needed: usize, limit: usize },
+
+    /// A validator's external tool (e.g. `rustc`, `node`) is not installed.
+    #[error("Validation tool '{tool}' not found")]
+    ToolchainMissing { tool: String },
+
+    /// A whole render did not finish within `render_timeout_ms`.
+    #[error("Render timed out after {0} ms")]
+    RenderTimeout(u64),
+
+    /// The estimated or accumulated render cost exceeds `max_cost_usd`.
+    #[error("Cost limit exceeded: ~${spent:.4} against a limit of ${limit:.4}")]
+    CostLimitExceeded { spent: f64, limit: f64 },
+
+    /// A cost limit is set but a slot's model has no price, so the limit
+    /// cannot be enforced.
+    #[error("Cannot enforce the cost limit: no price for model '{model}' of provider '{provider}'")]
+    UnpricedModel { provider: String, model: String },
+
+    /// The serialized provider request exceeds `max_request_bytes`.
+    #[error("Request too large: body is {bytes} bytes but the limit is {limit}")]
+    RequestTooLarge { bytes: usize, limit: usize },
+
+    /// User-supplied context contains an instruction-like phrase.
+    #[error("Possible prompt injection in {field}: \"{phrase}\"")]
+    PossiblePromptInjection { field: String, phrase: String },
+
+    /// A slot is misconfigured (e.g. empty prompt).
+    #[error("Invalid slot '{name}': {reason}")]
+    InvalidSlot { name: String, reason: String },
+
+    /// The provider's system fingerprint differs from the expected one.
+    #[error("System fingerprint mismatch: expected '{expected}', got {}", .actual.as_deref().map(|a| format!("'{}'", a)).unwrap_or_else(|| "none".to_string()))]
+    FingerprintMismatch { expected: String, actual: Option<String> },
+
+    /// Slot dependencies form a cycle.
+    #[error("Dependency cycle between slots: {}", .0.join(", "))]
+    DependencyCycle(Vec<String>),
+
+    /// A slot was not generated because one of its dependencies failed.
+    #[error("Slot '{slot}' not generated: dependency '{dependency}' failed")]
+    DependencyFailed { slot: String, dependency: String },
+
+    /// Circuit breaker is open after repeated provider failures.
+    #[error("Circuit open: provider failed {failures} consecutive times, skipping call")]
+    CircuitOpen { failures: u32 },
+
+    /// The provider stopped generation for policy reasons (e.g. Gemini
+    /// `SAFETY` or `RECITATION`). `retryable` tells whether a reworded
+    /// prompt may succeed.
+    #[error("Generation blocked by provider ({reason})")]
+    ContentBlocked { reason: String, retryable: bool },
+
+    /// Several slots failed in one render; carries each slot's error.
+    #[error("{} slot(s) failed: {}", .0.len(), describe_aggregate(.0))]
+    Aggregate(Vec<(String, AetherError)>),
+}
+
+fn describe_aggregate(errors: &[(String, AetherError)]) -> String {
+    errors
+        .iter()
+        .map(|(slot, error)| format!("'{}': {}", slot, error))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+impl AetherError {
+    /// Whether retrying the same request may succeed.
+    pub fn is_retryable(&self) -> bool {
+        if let AetherError::Aggregate(errors) = self {
+            return errors.iter().any(|(_, e)| e.is_retryable());
+        }
+        !matches!(
+            self,
+            AetherError::ContentBlocked { retryable: false, .. }
+                | AetherError::ConfigError(_)
+                | AetherError::ContextWindowExceeded { .. }
+                | AetherError::RequestTooLarge { .. }
+                | AetherError::ToolchainMissing { .. }
+                | AetherError::CostLimitExceeded { .. }
+                | AetherError::UnpricedModel { .. }
+        )
+    }
 }
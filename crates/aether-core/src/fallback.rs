@@ -0,0 +1,213 @@
+//! Provider fallback chains.
+//!
+//! A `FallbackProvider` tries a list of providers in order and returns the
+//! first successful response, so an outage at one vendor does not fail the
+//! whole render.
+
+use crate::provider::{AiProvider, GenerationRequest, GenerationResponse, PromptPreview, StreamResponse};
+use crate::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Tries providers in order until one succeeds.
+pub struct FallbackProvider {
+    providers: Vec<Arc<dyn AiProvider>>,
+}
+
+impl FallbackProvider {
+    /// Create a chain starting with `primary`.
+    pub fn new(primary: Arc<dyn AiProvider>) -> Self {
+        Self {
+            providers: vec![primary],
+        }
+    }
+
+    /// Append a provider to try when all earlier ones fail.
+    pub fn with_fallback(mut self, provider: Arc<dyn AiProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Providers in the order they are tried.
+    pub fn providers(&self) -> &[Arc<dyn AiProvider>] {
+        &self.providers
+    }
+}
+
+#[async_trait]
+impl AiProvider for FallbackProvider {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    /// Records the member that answered as `provider` in the response
+    /// metadata, so its price is used instead of the chain's.
+    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+        let mut last_error = None;
+        for provider in &self.providers {
+            match provider.generate(request.clone()).await {
+                Ok(mut response) => {
+                    tag_provider(&mut response, provider.name());
+                    return Ok(response);
+                }
+                Err(e) => {
+                    warn!("Provider '{}' failed, trying the next one: {}", provider.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("fallback chain has at least one provider"))
+    }
+
+    /// Asks each member for all `n` completions in turn, so a provider
+    /// with a native `n` parameter still serves them in one request.
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        let mut last_error = None;
+        for provider in &self.providers {
+            match provider.generate_n(request.clone()).await {
+                Ok(mut responses) => {
+                    for response in &mut responses {
+                        tag_provider(response, provider.name());
+                    }
+                    return Ok(responses);
+                }
+                Err(e) => {
+                    warn!("Provider '{}' failed, trying the next one: {}", provider.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.expect("fallback chain has at least one provider"))
+    }
+
+    /// The primary provider's model.
+    fn model(&self) -> Option<&str> {
+        self.providers[0].model()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.providers[0].supports_streaming()
+    }
+
+    /// Previews the primary provider's prompt.
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        self.providers[0].preview_prompt(request)
+    }
+
+    /// Streams from the primary provider; failures mid-stream are not retried.
+    fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> BoxStream<'static, Result<StreamResponse>> {
+        self.providers[0].generate_stream(request)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        Ok(self.health_report().await.iter().any(|(_, healthy)| *healthy))
+    }
+
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        let mut report = Vec::new();
+        for provider in &self.providers {
+            report.extend(provider.health_report().await);
+        }
+        report
+    }
+}
+
+/// Name the member that answered in the response metadata, unless a
+/// nested chain has already named the actual provider.
+fn tag_provider(response: &mut GenerationResponse, name: &str) {
+    let metadata = response.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if let Some(fields) = metadata.as_object_mut() {
+        fields.entry("provider").or_insert_with(|| name.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use crate::{InjectionEngine, Template};
+
+    struct DownProvider;
+
+    #[async_trait]
+    impl AiProvider for DownProvider {
+        fn name(&self) -> &str {
+            "down"
+        }
+
+        async fn generate(&self, _request: GenerationRequest) -> Result<GenerationResponse> {
+            Err(crate::AetherError::ProviderError("service unavailable".to_string()))
+        }
+
+        async fn health_check(&self) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    /// Only answers through `generate_n`, like a provider with native `n`.
+    struct NativeNProvider;
+
+    #[async_trait]
+    impl AiProvider for NativeNProvider {
+        fn name(&self) -> &str {
+            "native"
+        }
+
+        async fn generate(&self, _request: GenerationRequest) -> Result<GenerationResponse> {
+            Err(crate::AetherError::ProviderError("use generate_n".to_string()))
+        }
+
+        async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+            Ok((0..request.n.unwrap_or(1))
+                .map(|i| GenerationResponse { code: i.to_string(), tokens_used: None, metadata: None })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_n_forwarded_to_members() {
+        let chain = FallbackProvider::new(Arc::new(DownProvider)).with_fallback(Arc::new(NativeNProvider));
+        let request = GenerationRequest::builder(crate::Slot::new("x", "Write x")).with_n(2).build();
+
+        let responses = chain.generate_n(request).await.unwrap();
+        let codes: Vec<&str> = responses.iter().map(|r| r.code.as_str()).collect();
+        assert_eq!(codes, ["0", "1"]);
+        assert!(responses.iter().all(|r| r.metadata.as_ref().unwrap()["provider"] == "native"));
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_each_provider() {
+        let chain = FallbackProvider::new(Arc::new(DownProvider))
+            .with_fallback(Arc::new(MockProvider::new().with_response("x", "ok")));
+        let engine = InjectionEngine::new(chain);
+
+        assert_eq!(
+            engine.preflight().await,
+            vec![("down".to_string(), false), ("mock".to_string(), true)]
+        );
+
+        // The healthy fallback serves the render
+        let result = engine.render(&Template::new("{{AI:x}}")).await.unwrap();
+        assert_eq!(result, "ok");
+    }
+
+    #[tokio::test]
+    async fn test_cost_priced_by_serving_provider() {
+        let chain = FallbackProvider::new(Arc::new(DownProvider))
+            .with_fallback(Arc::new(MockProvider::new()));
+        let config = crate::AetherConfig::default()
+            .with_pricing(crate::PricingTable::new().with_price("mock", "m", 1.0, 1.0));
+        let engine = InjectionEngine::with_config(chain, config);
+
+        let template = Template::new("{{AI:x}}")
+            .configure_slot(crate::Slot::new("x", "Write x").with_model("m"));
+        let report = engine.render_detailed(&template).await.unwrap();
+        assert_eq!(report.slots["x"].provider.as_deref(), Some("mock"));
+        assert!(report.cost_usd.is_some());
+    }
+}
@@ -0,0 +1,102 @@
+//! # Code Fences
+//!
+//! Removal of the markdown fence models wrap generated code in. Providers
+//! apply it to each response; the engine applies it once to the joined
+//! chunks of a continued generation (`AetherConfig::continue_until_complete`).
+
+use crate::SlotKind;
+
+/// Strip the markdown fence wrapping a generated output of `kind`.
+///
+/// Only a fence around the whole output is removed. `SlotKind::Component`
+/// output may legitimately hold several fenced sections (html, css, js),
+/// which are kept.
+pub fn strip_code_fences(code: &str, kind: &SlotKind) -> String {
+    match kind {
+        SlotKind::Component => strip_code_blocks_preserving_inner(code),
+        _ => strip_code_blocks(code),
+    }
+}
+
+/// Strip markdown code blocks from generated code.
+fn strip_code_blocks(code: &str) -> String {
+    let code = code.trim();
+
+    // Check for ```language\n...\n``` pattern
+    if code.starts_with("```") && code.ends_with("```") {
+        let lines: Vec<&str> = code.lines().collect();
+        if lines.len() >= 2 {
+            return lines[1..lines.len() - 1].join("\n");
+        }
+    }
+
+    code.to_string()
+}
+
+/// Strip a markdown fence only when it wraps the whole output.
+///
+/// Inner fences are left intact: an opening fence with a language tag
+/// (```` ```css ````) nests, a bare fence closes the innermost open block.
+/// If the first fence closes before the last line, the output is a series
+/// of sibling blocks rather than one wrapped block and is returned as is.
+fn strip_code_blocks_preserving_inner(code: &str) -> String {
+    let code = code.trim();
+    let lines: Vec<&str> = code.lines().collect();
+    if lines.len() < 2 {
+        return code.to_string();
+    }
+
+    let fence = &lines[0][..lines[0].chars().take_while(|&c| c == '`').count()];
+    if fence.len() < 3 || lines[lines.len() - 1].trim() != fence {
+        return code.to_string();
+    }
+
+    let inner = &lines[1..lines.len() - 1];
+    let mut depth = 0usize;
+    for line in inner {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix(fence) else {
+            continue;
+        };
+        if rest.starts_with('`') {
+            continue;
+        }
+        if !rest.trim().is_empty() {
+            depth += 1;
+        } else if depth == 0 {
+            return code.to_string();
+        } else {
+            depth -= 1;
+        }
+    }
+    if depth != 0 {
+        return code.to_string();
+    }
+
+    inner.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_code_blocks() {
+        let input = "```html\n<div>Hello</div>\n```";
+        assert_eq!(strip_code_blocks(input), "<div>Hello</div>");
+
+        let input = "<div>Already clean</div>";
+        assert_eq!(strip_code_blocks(input), "<div>Already clean</div>");
+    }
+
+    #[test]
+    fn test_strip_preserves_inner_fences() {
+        let component = "```html\n<button>Go</button>\n```\n\n```css\nbutton { color: red; }\n```\n\n```js\nconsole.log(1);\n```";
+        assert_eq!(strip_code_blocks_preserving_inner(component), component);
+
+        let wrapped = format!("```\n{}\n```", component);
+        assert_eq!(strip_code_blocks_preserving_inner(&wrapped), component);
+
+        assert_eq!(strip_code_blocks_preserving_inner("```html\n<div>Hello</div>\n```"), "<div>Hello</div>");
+    }
+}
@@ -0,0 +1,111 @@
+//! # JSON Repair
+//!
+//! Best-effort fixes for near-miss JSON returned by models: markdown fences,
+//! surrounding prose and trailing commas. Enabled with `AetherConfig::json_repair`
+//! for slots of kind `SlotKind::Json`.
+
+/// Repair common model mistakes in JSON output.
+///
+/// Strips markdown fences, extracts the first balanced `{...}` or `[...]`
+/// and removes trailing commas before `}` / `]`. Input without any JSON
+/// value is returned trimmed.
+pub fn repair_json(input: &str) -> String {
+    let text = strip_fences(input);
+    let json = extract_balanced(text).unwrap_or(text);
+    remove_trailing_commas(json)
+}
+
+/// Return the body of the first fenced block, or the input if there is none.
+fn strip_fences(input: &str) -> &str {
+    let Some(start) = input.find("```") else {
+        return input.trim();
+    };
+    let body = &input[start + 3..];
+    // Skip the language tag on the opening fence line
+    let body = body.find('\n').map(|i| &body[i + 1..]).unwrap_or(body);
+    match body.find("```") {
+        Some(end) => body[..end].trim(),
+        None => body.trim(),
+    }
+}
+
+/// Find the first balanced JSON object or array, ignoring brackets in strings.
+fn extract_balanced(text: &str) -> Option<&str> {
+    let start = text.find(['{', '['])?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in text[start..].char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[start..start + i + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Drop commas that are followed (after whitespace) by a closing bracket.
+fn remove_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repairs_trailing_commas() {
+        let repaired = repair_json("{\"a\": [1, 2,], \"b\": \"x,}\",\n}");
+        let value: serde_json::Value = serde_json::from_str(&repaired).unwrap();
+        assert_eq!(value, serde_json::json!({"a": [1, 2], "b": "x,}"}));
+    }
+
+    #[test]
+    fn test_extracts_json_from_prose_and_fences() {
+        let input = "Sure! Here is the config:\n```json\n{\"name\": \"aether\", \"tags\": [\"ai\"]}\n```\nLet me know.";
+        assert_eq!(repair_json(input), "{\"name\": \"aether\", \"tags\": [\"ai\"]}");
+
+        let input = "The result is {\"ok\": true} as requested.";
+        assert_eq!(repair_json(input), "{\"ok\": true}");
+    }
+}
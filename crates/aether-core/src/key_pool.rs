@@ -0,0 +1,102 @@
+//! Rotation across several API keys for the same provider.
+//!
+//! A `KeyPool` hands out keys round-robin, one per request. Keys reported
+//! as rate limited (HTTP 429) are skipped until their cooldown expires.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default time a rate-limited key is skipped.
+pub const DEFAULT_KEY_COOLDOWN_SECS: u64 = 60;
+
+/// Round-robin pool of API keys with per-key cooldown.
+#[derive(Debug)]
+pub struct KeyPool {
+    keys: Vec<String>,
+    next: AtomicUsize,
+    cooldown: Duration,
+    /// When each key becomes usable again, if it is cooling down.
+    cooling_until: Mutex<Vec<Option<Instant>>>,
+}
+
+impl KeyPool {
+    /// Create a pool from a list of keys.
+    pub fn new(keys: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        let cooling_until = Mutex::new(vec![None; keys.len()]);
+        Self {
+            keys,
+            next: AtomicUsize::new(0),
+            cooldown: Duration::from_secs(DEFAULT_KEY_COOLDOWN_SECS),
+            cooling_until,
+        }
+    }
+
+    /// Set how long a rate-limited key is skipped.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Number of keys in the pool.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Check if the pool has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Pick the next key that is not cooling down.
+    /// Returns `None` if the pool is empty or every key is cooling down.
+    pub fn next_key(&self) -> Option<String> {
+        let now = Instant::now();
+        let cooling_until = self.cooling_until.lock().unwrap();
+
+        for _ in 0..self.keys.len() {
+            let i = self.next.fetch_add(1, Ordering::Relaxed) % self.keys.len();
+            if cooling_until[i].is_none_or(|until| until <= now) {
+                return Some(self.keys[i].clone());
+            }
+        }
+        None
+    }
+
+    /// Mark a key as rate limited so it is skipped for the cooldown period.
+    pub fn mark_rate_limited(&self, key: &str) {
+        if let Some(i) = self.keys.iter().position(|k| k == key) {
+            self.cooling_until.lock().unwrap()[i] = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_skips_rate_limited_key() {
+        let pool = KeyPool::new(["key-a", "key-b"]);
+
+        assert_eq!(pool.next_key().as_deref(), Some("key-a"));
+        assert_eq!(pool.next_key().as_deref(), Some("key-b"));
+        assert_eq!(pool.next_key().as_deref(), Some("key-a"));
+
+        pool.mark_rate_limited("key-a");
+        assert_eq!(pool.next_key().as_deref(), Some("key-b"));
+        assert_eq!(pool.next_key().as_deref(), Some("key-b"));
+
+        pool.mark_rate_limited("key-b");
+        assert_eq!(pool.next_key(), None);
+    }
+
+    #[test]
+    fn test_cooldown_expires() {
+        let pool = KeyPool::new(["key-a", "key-b"]).with_cooldown(Duration::ZERO);
+        pool.mark_rate_limited("key-a");
+
+        assert_eq!(pool.next_key().as_deref(), Some("key-a"));
+    }
+}
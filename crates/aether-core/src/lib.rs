@@ -37,18 +37,45 @@ pub mod observer;
 pub mod shield;
 pub mod config;
 pub mod script;
+pub mod circuit;
+pub mod checkpoint;
+pub mod rate_limit;
+pub mod redact;
+pub mod metrics;
+pub mod key_pool;
+pub mod json_repair;
+pub mod fence;
+pub mod replay;
+pub mod recording;
+pub mod prompt_guard;
+pub mod fallback;
+pub mod pricing;
+pub mod backoff;
 
 pub use error::{AetherError, Result};
-pub use template::Template;
+pub use template::{SourceMap, SourceSpan, Template, UnfilledStrategy};
 pub use slot::{Slot, SlotKind, SlotConstraints};
-pub use provider::{AiProvider, ProviderConfig};
+pub use provider::{default_model, AiProvider, PromptPreview, ProviderConfig};
 pub use context::InjectionContext;
-pub use engine::{InjectionEngine, RenderSession};
+pub use engine::{default_kind_temperatures, EngineParts, InjectionEngine, PlannedSlot, RenderPlan, RenderSession, RenderReport, SlotReport};
 pub use script::{AetherScript, AetherAgenticRuntime};
 pub use runtime::AetherRuntime;
-pub use config::AetherConfig;
+pub use config::{AetherConfig, ContextOverflow, PromptInjectionPolicy};
 pub use cache::{Cache, ExactCache, SemanticCache, TieredCache};
 pub use observer::{EngineObserver, ObserverPtr};
+pub use toon::{Toon, ToonOptions};
+pub use circuit::CircuitBreaker;
+pub use checkpoint::BatchCheckpoint;
+pub use rate_limit::RateLimiter;
+pub use redact::Redactor;
+pub use metrics::MetricsObserver;
+pub use key_pool::KeyPool;
+pub use replay::ReplayProvider;
+pub use recording::RecordingProvider;
+pub use prompt_guard::PromptGuard;
+pub use fallback::FallbackProvider;
+pub use pricing::PricingTable;
+pub use backoff::Backoff;
 
 /// Re-export commonly used types
 pub mod prelude {
@@ -0,0 +1,114 @@
+//! Prometheus metrics for engine activity.
+//!
+//! `MetricsObserver` counts generations, tokens, cache hits, healing
+//! retries and failures, and renders them in the Prometheus text
+//! exposition format via `metrics_text()`.
+
+use crate::observer::EngineObserver;
+use crate::provider::{GenerationRequest, GenerationResponse};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+struct Counters {
+    generations: AtomicU64,
+    successes: AtomicU64,
+    failures: AtomicU64,
+    tokens: AtomicU64,
+    cache_hits: AtomicU64,
+    healing_retries: AtomicU64,
+}
+
+/// Observer that accumulates counters for Prometheus scraping.
+///
+/// Clones share the same counters, so one clone can be handed to the
+/// engine and another kept for serving `metrics_text()`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsObserver {
+    counters: Arc<Counters>,
+}
+
+impl MetricsObserver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render all counters in the Prometheus text exposition format.
+    pub fn metrics_text(&self) -> String {
+        let c = &self.counters;
+        let metrics = [
+            ("aether_generations_total", "Slot generations started.", &c.generations),
+            ("aether_generations_succeeded_total", "Slot generations that succeeded.", &c.successes),
+            ("aether_generations_failed_total", "Slot generations that failed permanently.", &c.failures),
+            ("aether_tokens_total", "Tokens reported by the provider.", &c.tokens),
+            ("aether_cache_hits_total", "Generations served from the cache.", &c.cache_hits),
+            ("aether_healing_retries_total", "Self-healing retries after failed validation.", &c.healing_retries),
+        ];
+
+        let mut out = String::new();
+        for (name, help, value) in metrics {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+        out
+    }
+}
+
+impl EngineObserver for MetricsObserver {
+    fn on_start(&self, _id: &str, _template: &str, _slot: &str, _request: &GenerationRequest) {
+        self.counters.generations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_success(&self, _id: &str, response: &GenerationResponse) {
+        self.counters.successes.fetch_add(1, Ordering::Relaxed);
+        if let Some(tokens) = response.tokens_used {
+            self.counters.tokens.fetch_add(tokens as u64, Ordering::Relaxed);
+        }
+        let cache_hit = response
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get("cache"))
+            .is_some_and(|v| v == "hit");
+        if cache_hit {
+            self.counters.cache_hits.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn on_healing_step(&self, _id: &str, _attempt: u32, _error: &str) {
+        self.counters.healing_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_failure(&self, _id: &str, _error: &str) {
+        self.counters.failures.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use crate::{ExactCache, InjectionEngine, Template};
+
+    #[tokio::test]
+    async fn test_metrics_after_renders() {
+        let metrics = MetricsObserver::new();
+        let engine = InjectionEngine::new(MockProvider::new())
+            .with_cache(ExactCache::new())
+            .with_observer(metrics.clone());
+
+        let template = Template::new("{{AI:header}} {{AI:footer}}");
+        engine.render(&template).await.unwrap();
+        engine.render(&template).await.unwrap();
+
+        let text = metrics.metrics_text();
+        assert!(text.contains("# HELP aether_generations_total Slot generations started.\n"));
+        assert!(text.contains("# TYPE aether_generations_total counter\n"));
+        assert!(text.contains("aether_generations_total 4\n"));
+        assert!(text.contains("aether_generations_succeeded_total 4\n"));
+        assert!(text.contains("aether_cache_hits_total 2\n"));
+        assert!(text.contains("aether_tokens_total 20\n"));
+        assert!(text.contains("aether_generations_failed_total 0\n"));
+    }
+}
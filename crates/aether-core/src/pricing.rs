@@ -0,0 +1,148 @@
+//! Cost estimation from token usage.
+//!
+//! A `PricingTable` maps `(provider, model)` to USD prices per 1K input and
+//! output tokens. `render_detailed` uses the table in `AetherConfig::pricing`
+//! to fill in `RenderReport::cost_usd`.
+
+use std::collections::HashMap;
+
+/// USD price per 1K tokens for one model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// Price per 1K prompt (input) tokens.
+    pub input_per_1k: f64,
+
+    /// Price per 1K completion (output) tokens.
+    pub output_per_1k: f64,
+}
+
+/// Default prices in USD per 1K tokens (input, output).
+///
+/// Covers every model in `DEFAULT_MODELS`. Grok is served through the
+/// OpenAI-compatible provider, so its models are listed under `openai`.
+pub const DEFAULT_PRICES: &[(&str, &str, f64, f64)] = &[
+    ("openai", "gpt-5.2-thinking", 0.00175, 0.014),
+    ("openai", "gpt-4.1", 0.002, 0.008),
+    ("openai", "gpt-4.1-mini", 0.0004, 0.0016),
+    ("openai", "gpt-4o", 0.0025, 0.01),
+    ("openai", "gpt-4o-mini", 0.00015, 0.0006),
+    ("openai", "gpt-4-turbo", 0.01, 0.03),
+    ("openai", "gpt-4", 0.03, 0.06),
+    ("anthropic", "claude-opus-4-5", 0.005, 0.025),
+    ("anthropic", "claude-3-5-sonnet", 0.003, 0.015),
+    ("anthropic", "claude-3-5-haiku", 0.0008, 0.004),
+    ("anthropic", "claude-3-opus", 0.015, 0.075),
+    ("gemini", "gemini-1.5-pro", 0.00125, 0.005),
+    ("gemini", "gemini-1.5-flash", 0.000075, 0.0003),
+    ("cohere", "command-r-plus", 0.0025, 0.01),
+    ("cohere", "command-r", 0.00015, 0.0006),
+    ("openai", "grok-1", 0.005, 0.015),
+    ("ollama", "", 0.0, 0.0),
+];
+
+/// Prices per `(provider, model)`, used to estimate render cost.
+#[derive(Debug, Clone)]
+pub struct PricingTable {
+    prices: HashMap<(String, String), ModelPrice>,
+}
+
+impl PricingTable {
+    /// Create an empty table (no costs are estimated).
+    pub fn new() -> Self {
+        Self { prices: HashMap::new() }
+    }
+
+    /// Set the price for a model, replacing any existing entry.
+    ///
+    /// An empty `model` prices every model of the provider not listed otherwise.
+    pub fn with_price(
+        mut self,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        input_per_1k: f64,
+        output_per_1k: f64,
+    ) -> Self {
+        self.prices.insert(
+            (provider.into(), model.into()),
+            ModelPrice { input_per_1k, output_per_1k },
+        );
+        self
+    }
+
+    /// Look up the price for a model.
+    ///
+    /// Falls back to the longest listed prefix ending at a `-`, so dated
+    /// snapshots such as `gpt-4o-2024-08-06` use the `gpt-4o` price while
+    /// `gpt-4.1` is not priced as `gpt-4`.
+    pub fn price(&self, provider: &str, model: &str) -> Option<ModelPrice> {
+        let matches = |listed: &str| {
+            listed.is_empty()
+                || model
+                    .strip_prefix(listed)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        };
+        self.prices
+            .iter()
+            .filter(|((p, m), _)| p == provider && matches(m))
+            .max_by_key(|((_, m), _)| m.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// Estimated USD cost of a request, or None if the model is not priced.
+    pub fn estimate(&self, provider: &str, model: &str, input_tokens: u32, output_tokens: u32) -> Option<f64> {
+        let price = self.price(provider, model)?;
+        Some(
+            input_tokens as f64 / 1000.0 * price.input_per_1k
+                + output_tokens as f64 / 1000.0 * price.output_per_1k,
+        )
+    }
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        DEFAULT_PRICES
+            .iter()
+            .fold(Self::new(), |table, &(provider, model, input, output)| {
+                table.with_price(provider, model, input, output)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost() {
+        let table = PricingTable::new().with_price("openai", "gpt-4o", 0.0025, 0.01);
+        let cost = table.estimate("openai", "gpt-4o", 2000, 500).unwrap();
+        assert!((cost - 0.01).abs() < 1e-9);
+
+        // Dated snapshots fall back to the base model price
+        assert!(table.estimate("openai", "gpt-4o-2024-08-06", 1000, 0).is_some());
+        assert!(table.estimate("anthropic", "gpt-4o", 1000, 0).is_none());
+
+        let defaults = PricingTable::default();
+        let mini = defaults.price("openai", "gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.input_per_1k, 0.00015);
+        assert_eq!(defaults.estimate("ollama", "codellama", 5000, 5000), Some(0.0));
+    }
+
+    #[test]
+    fn test_prefix_matches_stop_at_dash() {
+        let defaults = PricingTable::default();
+        assert_eq!(defaults.price("openai", "gpt-4-0613").unwrap().input_per_1k, 0.03);
+        assert_eq!(defaults.price("openai", "gpt-4.1").unwrap().input_per_1k, 0.002);
+        assert!(defaults.price("openai", "gpt-4.5-preview").is_none());
+        assert!(defaults.price("openai", "gpt-4oo").is_none());
+    }
+
+    #[test]
+    fn test_default_models_are_priced() {
+        let defaults = PricingTable::default();
+        for &(provider, model) in crate::provider::DEFAULT_MODELS {
+            let served_by = if provider == "grok" { "openai" } else { provider };
+            assert!(defaults.price(served_by, model).is_some(), "{} / {} is not priced", provider, model);
+        }
+    }
+}
@@ -2,10 +2,68 @@
 //!
 //! Defines the interface that AI backends must implement.
 
-use crate::{Result, Slot};
+use crate::{KeyPool, Result, Slot};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Default timeout for non-streaming requests, in seconds.
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Default timeout for streaming requests, in seconds.
+/// Long streamed generations regularly exceed the request timeout.
+pub const DEFAULT_STREAM_TIMEOUT_SECS: u64 = 600;
+
+/// Default `User-Agent` sent by the built-in providers.
+pub const DEFAULT_USER_AGENT: &str = concat!("aether/", env!("CARGO_PKG_VERSION"));
+
+/// Default OpenAI model.
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-5.2-thinking";
+
+/// Default Anthropic model.
+pub const DEFAULT_ANTHROPIC_MODEL: &str = "claude-opus-4-5";
+
+/// Default Gemini model.
+pub const DEFAULT_GEMINI_MODEL: &str = "gemini-1.5-pro";
+
+/// Default Ollama model.
+pub const DEFAULT_OLLAMA_MODEL: &str = "codellama";
+
+/// Default Grok (xAI) model.
+pub const DEFAULT_GROK_MODEL: &str = "grok-1";
+
+/// Default Cohere model.
+pub const DEFAULT_COHERE_MODEL: &str = "command-r-plus";
+
+/// Default model for each built-in provider, keyed by provider name.
+pub const DEFAULT_MODELS: &[(&str, &str)] = &[
+    ("openai", DEFAULT_OPENAI_MODEL),
+    ("anthropic", DEFAULT_ANTHROPIC_MODEL),
+    ("gemini", DEFAULT_GEMINI_MODEL),
+    ("ollama", DEFAULT_OLLAMA_MODEL),
+    ("grok", DEFAULT_GROK_MODEL),
+    ("cohere", DEFAULT_COHERE_MODEL),
+];
+
+/// Default model for a provider name, or `None` for an unknown provider.
+/// Prefer the `DEFAULT_*_MODEL` constants when the provider is known.
+///
+/// Accepts the aliases used by the bindings (`claude`, `xai`, `local`).
+pub fn default_model(provider_name: &str) -> Option<&'static str> {
+    let name = provider_name.to_lowercase();
+    let name = match name.as_str() {
+        "claude" => "anthropic",
+        "xai" => "grok",
+        "local" => "ollama",
+        other => other,
+    };
+
+    DEFAULT_MODELS
+        .iter()
+        .find(|(provider, _)| *provider == name)
+        .map(|(_, model)| *model)
+}
 
 /// Configuration for an AI provider.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,8 +86,34 @@ pub struct ProviderConfig {
     /// Request timeout in seconds.
     pub timeout_seconds: Option<u64>,
 
+    /// Timeout for streaming requests in seconds.
+    #[serde(default)]
+    pub stream_timeout_seconds: Option<u64>,
+
     /// Optional URL to fetch the API key from (for stealth/security).
     pub api_key_url: Option<String>,
+
+    /// Optional pool of keys rotated per request, overriding `api_key`.
+    #[serde(skip)]
+    pub key_pool: Option<Arc<KeyPool>>,
+
+    /// API version pinned by providers that version their API with a header
+    /// (Anthropic `anthropic-version`). None uses the provider default.
+    #[serde(default)]
+    pub api_version: Option<String>,
+
+    /// Beta features to opt into (Anthropic `anthropic-beta`).
+    #[serde(default)]
+    pub beta_features: Vec<String>,
+
+    /// `User-Agent` header override. None sends `DEFAULT_USER_AGENT`.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+
+    /// Reject requests whose JSON body exceeds this many bytes before
+    /// sending. None disables the check.
+    #[serde(default)]
+    pub max_request_bytes: Option<usize>,
 }
 
 impl ProviderConfig {
@@ -42,7 +126,13 @@ impl ProviderConfig {
             max_tokens: None,
             temperature: None,
             timeout_seconds: None,
+            stream_timeout_seconds: None,
             api_key_url: None,
+            key_pool: None,
+            api_version: None,
+            beta_features: Vec::new(),
+            user_agent: None,
+            max_request_bytes: None,
         }
     }
 
@@ -52,9 +142,27 @@ impl ProviderConfig {
         self
     }
 
-    /// Resolve the API key (literal or remote).
+    /// Rotate across several API keys, skipping keys that hit rate limits.
+    pub fn with_key_pool(mut self, pool: KeyPool) -> Self {
+        self.key_pool = Some(Arc::new(pool));
+        self
+    }
+
+    /// Report that `api_key` was rate limited (HTTP 429) so the key pool
+    /// skips it for a while. Does nothing without a key pool.
+    pub fn report_rate_limited(&self, api_key: &str) {
+        if let Some(ref pool) = self.key_pool {
+            pool.mark_rate_limited(api_key);
+        }
+    }
+
+    /// Resolve the API key (key pool, remote or literal).
     pub async fn resolve_api_key(&self) -> Result<String> {
-        if let Some(ref url) = self.api_key_url {
+        if let Some(ref pool) = self.key_pool {
+            pool.next_key().ok_or_else(|| {
+                crate::AetherError::ProviderError("All API keys are rate limited".to_string())
+            })
+        } else if let Some(ref url) = self.api_key_url {
             let resp = reqwest::get(url)
                 .await
                 .map_err(|e| crate::AetherError::NetworkError(format!("Failed to fetch API key: {}", e)))?;
@@ -76,13 +184,40 @@ impl ProviderConfig {
         self
     }
 
+    /// Pin the API version header (e.g. Anthropic `anthropic-version`).
+    pub fn with_api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Override the `User-Agent` header sent with every request.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Fail fast with `RequestTooLarge` when a request body exceeds `limit` bytes.
+    pub fn with_max_request_bytes(mut self, limit: usize) -> Self {
+        self.max_request_bytes = Some(limit);
+        self
+    }
+
+    /// Opt into a beta feature (e.g. `prompt-caching-2024-07-31` for Anthropic).
+    pub fn with_beta_feature(mut self, feature: impl Into<String>) -> Self {
+        self.beta_features.push(feature.into());
+        self
+    }
+
     /// Set max tokens.
     pub fn with_max_tokens(mut self, tokens: u32) -> Self {
         self.max_tokens = Some(tokens);
         self
     }
 
-    /// Set temperature.
+    /// Set temperature, clamped to 0.0–2.0.
+    ///
+    /// Providers clamp further to their own range when building requests:
+    /// Anthropic caps at 1.0, OpenAI and Gemini at 2.0.
     pub fn with_temperature(mut self, temp: f32) -> Self {
         self.temperature = Some(temp.clamp(0.0, 2.0));
         self
@@ -94,6 +229,27 @@ impl ProviderConfig {
         self
     }
 
+    /// Set timeout for streaming requests.
+    pub fn with_stream_timeout(mut self, seconds: u64) -> Self {
+        self.stream_timeout_seconds = Some(seconds);
+        self
+    }
+
+    /// Effective timeout for non-streaming requests.
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_seconds.unwrap_or(DEFAULT_REQUEST_TIMEOUT_SECS))
+    }
+
+    /// Effective timeout for streaming requests.
+    pub fn stream_timeout(&self) -> Duration {
+        Duration::from_secs(self.stream_timeout_seconds.unwrap_or(DEFAULT_STREAM_TIMEOUT_SECS))
+    }
+
+    /// Effective `User-Agent` header.
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+
     /// Load config from environment variables.
     ///
     /// Expected variables:
@@ -109,7 +265,7 @@ impl ProviderConfig {
                 )
             })?;
 
-        let model = std::env::var("AETHER_MODEL").unwrap_or_else(|_| "gpt-5.2-thinking".to_string());
+        let model = std::env::var("AETHER_MODEL").unwrap_or_else(|_| DEFAULT_OPENAI_MODEL.to_string());
 
         let mut config = Self::new(api_key, model);
 
@@ -122,7 +278,11 @@ impl ProviderConfig {
 }
 
 /// Request for code generation.
+///
+/// Build one with [`GenerationRequest::builder`]; the struct is
+/// `#[non_exhaustive]` so adding fields is not a breaking change.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct GenerationRequest {
     /// The slot to generate code for.
     pub slot: Slot,
@@ -138,6 +298,198 @@ pub struct GenerationRequest {
 
     /// Maximum tokens for this request.
     pub max_tokens: Option<u32>,
+
+    /// Extra system instruction for `SlotKind::Custom` kinds registered on the engine.
+    pub kind_instruction: Option<String>,
+
+    /// Extra value mixed into the fingerprint, e.g. the template content hash.
+    pub cache_salt: Option<u64>,
+
+    /// Sent as an `Idempotency-Key` header by providers that support it.
+    pub idempotency_key: Option<String>,
+
+    /// Number of completions requested from `generate_n` (None means 1).
+    pub n: Option<u32>,
+
+    /// Engine-assigned id of this generation, the same id observers receive.
+    /// Sent as a request-id header by providers that support one, so logs
+    /// on both sides can be correlated. Not part of the fingerprint.
+    pub request_id: Option<String>,
+
+    /// Temperature used when neither the slot nor the provider config sets
+    /// one; the engine fills it from its per-kind defaults.
+    pub default_temperature: Option<f32>,
+
+    /// Identifier of the end user, sent for provider abuse monitoring
+    /// (OpenAI `user`, Anthropic `metadata.user_id`). Not part of the fingerprint.
+    pub end_user_id: Option<String>,
+
+    /// Return the model's text untouched instead of stripping its code fence.
+    /// The engine sets it on the chunks of a continued generation, which it
+    /// joins and strips once. Not part of the fingerprint.
+    pub raw_output: bool,
+}
+
+impl GenerationRequest {
+    /// Start building a request for `slot`; every other field defaults to None.
+    pub fn builder(slot: Slot) -> GenerationRequestBuilder {
+        GenerationRequestBuilder {
+            request: GenerationRequest {
+                slot,
+                context: None,
+                system_prompt: None,
+                model: None,
+                max_tokens: None,
+                kind_instruction: None,
+                cache_salt: None,
+                idempotency_key: None,
+                n: None,
+                request_id: None,
+                default_temperature: None,
+                end_user_id: None,
+                raw_output: false,
+            },
+        }
+    }
+
+    /// The response code for model output `text`: the wrapping markdown
+    /// fence is stripped unless `raw_output` is set.
+    pub fn output_code(&self, text: &str) -> String {
+        if self.raw_output {
+            text.to_string()
+        } else {
+            crate::fence::strip_code_fences(text, &self.slot.kind)
+        }
+    }
+
+    /// Stable fingerprint of everything that affects the generated output.
+    ///
+    /// Identical requests share a fingerprint, which is used both for cache
+    /// keys and for coalescing duplicate slots within a render. The slot name
+    /// and per-call ids (request id, idempotency key, end user) are left out,
+    /// so same-prompt slots under different names still coalesce.
+    pub fn fingerprint(&self) -> u64 {
+        let slot = &self.slot;
+        let base = crate::RenderSession::hash(&(
+            (
+                &slot.prompt,
+                &slot.kind,
+                &slot.constraints,
+                slot.temperature.map(f32::to_bits),
+                slot.candidates,
+            ),
+            (
+                self.context.as_deref().unwrap_or(""),
+                self.system_prompt.as_deref().unwrap_or(""),
+                self.kind_instruction.as_deref().unwrap_or(""),
+            ),
+            (
+                self.model.as_deref().unwrap_or(""),
+                self.max_tokens.unwrap_or(0),
+                self.n.unwrap_or(1),
+                self.default_temperature.map(f32::to_bits),
+            ),
+        ));
+        match self.cache_salt {
+            Some(salt) => crate::RenderSession::hash(&(base, salt)),
+            None => base,
+        }
+    }
+
+    /// Fingerprint with whitespace in the slot prompt normalized.
+    pub fn normalized_fingerprint(&self) -> u64 {
+        let mut request = self.clone();
+        request.slot.prompt = crate::slot::normalize_prompt(&self.slot.prompt);
+        request.fingerprint()
+    }
+}
+
+/// Fluent builder for [`GenerationRequest`], see [`GenerationRequest::builder`].
+///
+/// Setters accept either a value or an `Option`, so optional settings can be
+/// passed through without branching.
+#[derive(Debug, Clone)]
+pub struct GenerationRequestBuilder {
+    request: GenerationRequest,
+}
+
+impl GenerationRequestBuilder {
+    /// Set additional context (e.g., surrounding code).
+    pub fn with_context(mut self, context: impl Into<Option<String>>) -> Self {
+        self.request.context = context.into();
+        self
+    }
+
+    /// Set the system prompt override.
+    pub fn with_system_prompt(mut self, prompt: impl Into<Option<String>>) -> Self {
+        self.request.system_prompt = prompt.into();
+        self
+    }
+
+    /// Set the model override.
+    pub fn with_model(mut self, model: impl Into<Option<String>>) -> Self {
+        self.request.model = model.into();
+        self
+    }
+
+    /// Set the completion token budget.
+    pub fn with_max_tokens(mut self, max_tokens: impl Into<Option<u32>>) -> Self {
+        self.request.max_tokens = max_tokens.into();
+        self
+    }
+
+    /// Set the extra system instruction for custom kinds.
+    pub fn with_kind_instruction(mut self, instruction: impl Into<Option<String>>) -> Self {
+        self.request.kind_instruction = instruction.into();
+        self
+    }
+
+    /// Set the value mixed into the fingerprint.
+    pub fn with_cache_salt(mut self, salt: impl Into<Option<u64>>) -> Self {
+        self.request.cache_salt = salt.into();
+        self
+    }
+
+    /// Set the `Idempotency-Key` sent by supporting providers.
+    pub fn with_idempotency_key(mut self, key: impl Into<Option<String>>) -> Self {
+        self.request.idempotency_key = key.into();
+        self
+    }
+
+    /// Set the number of completions for `generate_n`.
+    pub fn with_n(mut self, n: impl Into<Option<u32>>) -> Self {
+        self.request.n = n.into();
+        self
+    }
+
+    /// Set the id used to correlate provider and observer logs.
+    pub fn with_request_id(mut self, id: impl Into<Option<String>>) -> Self {
+        self.request.request_id = id.into();
+        self
+    }
+
+    /// Set the temperature used when neither slot nor provider sets one.
+    pub fn with_default_temperature(mut self, temperature: impl Into<Option<f32>>) -> Self {
+        self.request.default_temperature = temperature.into();
+        self
+    }
+
+    /// Set the end-user identifier sent for abuse monitoring.
+    pub fn with_end_user_id(mut self, id: impl Into<Option<String>>) -> Self {
+        self.request.end_user_id = id.into();
+        self
+    }
+
+    /// Ask the provider for the model's text without fence stripping.
+    pub fn with_raw_output(mut self, raw: bool) -> Self {
+        self.request.raw_output = raw;
+        self
+    }
+
+    /// Finish the request.
+    pub fn build(self) -> GenerationRequest {
+        self.request
+    }
 }
 
 use futures::stream::BoxStream;
@@ -155,6 +507,26 @@ pub struct GenerationResponse {
     pub metadata: Option<serde_json::Value>,
 }
 
+impl GenerationResponse {
+    /// Why the model stopped, normalized to `"stop"` or `"length"` by the
+    /// built-in providers. None if the provider did not report it.
+    pub fn finish_reason(&self) -> Option<&str> {
+        self.metadata.as_ref()?.get("finish_reason")?.as_str()
+    }
+}
+
+/// The messages a provider would send for a request, see
+/// [`AiProvider::preview_prompt`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PromptPreview {
+    /// System message, if the provider sends one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+
+    /// User message.
+    pub user: String,
+}
+
 /// A single chunk of a streaming response.
 #[derive(Debug, Clone)]
 pub struct StreamResponse {
@@ -173,6 +545,14 @@ pub trait AiProvider: Send + Sync {
     /// Get the provider name.
     fn name(&self) -> &str;
 
+    /// Model used for requests that do not override it, if known.
+    ///
+    /// Lets the engine apply per-model settings such as context windows to
+    /// slots without a model override.
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
     /// Generate code for a slot.
     ///
     /// # Arguments
@@ -184,6 +564,14 @@ pub trait AiProvider: Send + Sync {
     /// Generated code response or an error.
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse>;
 
+    /// Whether `generate_stream` is implemented by this provider.
+    ///
+    /// Callers should check this before streaming instead of relying on the
+    /// default `generate_stream` error.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
     /// Generate a stream of code for a slot.
     ///
     /// # Arguments
@@ -220,10 +608,52 @@ pub trait AiProvider: Send + Sync {
         Ok(responses)
     }
 
+    /// Generate `request.n` completions for the same request.
+    ///
+    /// Default implementation calls `generate` `n` times sequentially;
+    /// providers with a native `n` parameter (OpenAI) override it.
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        let n = request.n.unwrap_or(1).max(1);
+        let mut responses = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            responses.push(self.generate(request.clone()).await?);
+        }
+        Ok(responses)
+    }
+
     /// Check if the provider is available and configured correctly.
     async fn health_check(&self) -> Result<bool> {
         Ok(true)
     }
+
+    /// Health of every underlying provider as `(name, healthy)`.
+    ///
+    /// Default implementation reports this provider alone; composite
+    /// providers such as `FallbackProvider` report each member.
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        let healthy = self.health_check().await.unwrap_or(false);
+        vec![(self.name().to_string(), healthy)]
+    }
+
+    /// Estimate the number of tokens in `text` for this provider's models.
+    ///
+    /// Default implementation uses the common ~4 characters per token heuristic.
+    fn count_tokens(&self, text: &str) -> usize {
+        text.len().div_ceil(4)
+    }
+
+    /// The system and user messages `generate` would send for `request`,
+    /// without calling the provider.
+    ///
+    /// Default implementation reports the request's system prompt override,
+    /// or else its context, and the slot prompt unchanged; built-in
+    /// providers compose them exactly as they do when sending.
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        PromptPreview {
+            system: request.system_prompt.clone().or_else(|| request.context.clone().filter(|c| !c.is_empty())),
+            user: request.slot.prompt.clone(),
+        }
+    }
 }
 
 #[async_trait]
@@ -232,16 +662,51 @@ impl<T: AiProvider + ?Sized + Send + Sync> AiProvider for Arc<T> {
         (**self).name()
     }
 
+    fn model(&self) -> Option<&str> {
+        (**self).model()
+    }
+
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         (**self).generate(request).await
     }
 
+    fn supports_streaming(&self) -> bool {
+        (**self).supports_streaming()
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
     ) -> BoxStream<'static, Result<StreamResponse>> {
         (**self).generate_stream(request)
     }
+
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_batch(requests).await
+    }
+
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_n(request).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        (**self).health_check().await
+    }
+
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        (**self).health_report().await
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        (**self).preview_prompt(request)
+    }
 }
 
 #[async_trait]
@@ -250,16 +715,104 @@ impl<T: AiProvider + ?Sized + Send + Sync> AiProvider for Box<T> {
         (**self).name()
     }
 
+    fn model(&self) -> Option<&str> {
+        (**self).model()
+    }
+
+    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+        (**self).generate(request).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        (**self).supports_streaming()
+    }
+
+    fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> BoxStream<'static, Result<StreamResponse>> {
+        (**self).generate_stream(request)
+    }
+
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_batch(requests).await
+    }
+
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_n(request).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        (**self).health_check().await
+    }
+
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        (**self).health_report().await
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        (**self).preview_prompt(request)
+    }
+}
+
+#[async_trait]
+impl<T: AiProvider + ?Sized + Send + Sync> AiProvider for &T {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn model(&self) -> Option<&str> {
+        (**self).model()
+    }
+
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         (**self).generate(request).await
     }
 
+    fn supports_streaming(&self) -> bool {
+        (**self).supports_streaming()
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
     ) -> BoxStream<'static, Result<StreamResponse>> {
         (**self).generate_stream(request)
     }
+
+    async fn generate_batch(
+        &self,
+        requests: Vec<GenerationRequest>,
+    ) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_batch(requests).await
+    }
+
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        (**self).generate_n(request).await
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        (**self).health_check().await
+    }
+
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        (**self).health_report().await
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        (**self).preview_prompt(request)
+    }
 }
 
 /// A mock provider for testing.
@@ -267,6 +820,12 @@ impl<T: AiProvider + ?Sized + Send + Sync> AiProvider for Box<T> {
 pub struct MockProvider {
     /// Responses to return (slot_name -> code).
     pub responses: std::collections::HashMap<String, String>,
+
+    /// Model reported by `AiProvider::model`.
+    model: Option<String>,
+
+    /// Requests received so far, in call order.
+    requests: std::sync::Mutex<Vec<GenerationRequest>>,
 }
 
 impl MockProvider {
@@ -280,6 +839,37 @@ impl MockProvider {
         self.responses.insert(slot.into(), code.into());
         self
     }
+
+    /// Report `model` as the provider's model.
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Get a copy of all requests received so far.
+    pub fn requests(&self) -> Vec<GenerationRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of `generate`/`generate_stream` calls received so far.
+    pub fn call_count(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Process-wide mock used by `#[aether_secure]` when `AETHER_PROVIDER=mock`.
+    /// Answers the `script` slot with `AETHER_MOCK_SCRIPT`, read on first use.
+    ///
+    /// Available with the `dev` feature, for tests only.
+    #[cfg(any(test, feature = "dev"))]
+    pub fn shared() -> std::sync::Arc<MockProvider> {
+        static SHARED: std::sync::OnceLock<std::sync::Arc<MockProvider>> = std::sync::OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                let script = std::env::var("AETHER_MOCK_SCRIPT").unwrap_or_default();
+                std::sync::Arc::new(MockProvider::new().with_response("script", script))
+            })
+            .clone()
+    }
 }
 
 #[async_trait]
@@ -288,12 +878,17 @@ impl AiProvider for MockProvider {
         "mock"
     }
 
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
     async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
         let code = self
             .responses
             .get(&request.slot.name)
             .cloned()
             .unwrap_or_else(|| format!("// Generated code for: {}", request.slot.name));
+        self.requests.lock().unwrap().push(request);
 
         Ok(GenerationResponse {
             code,
@@ -302,6 +897,10 @@ impl AiProvider for MockProvider {
         })
     }
 
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
     fn generate_stream(
         &self,
         request: GenerationRequest,
@@ -311,8 +910,10 @@ impl AiProvider for MockProvider {
             .get(&request.slot.name)
             .cloned()
             .unwrap_or_else(|| format!("// Generated code for: {}", request.slot.name));
+        self.requests.lock().unwrap().push(request);
 
-        let words: Vec<String> = code.split_whitespace().map(|s| format!("{} ", s)).collect();
+        // Chunk by word, keeping the separators so the chunks add up to `code`
+        let words: Vec<String> = code.split_inclusive(' ').map(str::to_string).collect();
         
         let stream = async_stream::stream! {
             for word in words {
@@ -331,20 +932,107 @@ impl AiProvider for MockProvider {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_default_models() {
+        assert_eq!(default_model("openai"), Some("gpt-5.2-thinking"));
+        assert_eq!(default_model("anthropic"), Some("claude-opus-4-5"));
+        assert_eq!(default_model("Claude"), Some("claude-opus-4-5"));
+        assert_eq!(default_model("gemini"), Some("gemini-1.5-pro"));
+        assert_eq!(default_model("ollama"), Some("codellama"));
+        assert_eq!(default_model("local"), Some("codellama"));
+        assert_eq!(default_model("grok"), Some("grok-1"));
+        assert_eq!(default_model("xai"), Some("grok-1"));
+        assert_eq!(default_model("cohere"), Some("command-r-plus"));
+        assert_eq!(default_model("unknown"), None);
+        assert_eq!(default_model("mock"), None);
+    }
+
+    #[test]
+    fn test_generation_request_builder() {
+        let request = GenerationRequest::builder(Slot::new("button", "Create a button"))
+            .with_context("<form></form>".to_string())
+            .with_model("gpt-4o".to_string())
+            .with_max_tokens(256)
+            .with_n(Some(2))
+            .with_default_temperature(None)
+            .build();
+
+        assert_eq!(request.slot.name, "button");
+        assert_eq!(request.context.as_deref(), Some("<form></form>"));
+        assert_eq!(request.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(request.max_tokens, Some(256));
+        assert_eq!(request.n, Some(2));
+        assert_eq!(request.system_prompt, None);
+        assert_eq!(request.default_temperature, None);
+        assert_eq!(request.end_user_id, None);
+    }
+
     #[tokio::test]
     async fn test_mock_provider() {
         let provider = MockProvider::new()
             .with_response("button", "<button>Click me</button>");
 
-        let request = GenerationRequest {
-            slot: Slot::new("button", "Create a button"),
-            context: None,
-            system_prompt: None,
-            model: None,
-            max_tokens: None,
-        };
+        let request = GenerationRequest::builder(Slot::new("button", "Create a button")).build();
 
         let response = provider.generate(request).await.unwrap();
         assert_eq!(response.code, "<button>Click me</button>");
     }
+
+    #[tokio::test]
+    async fn test_generate_n_falls_back_to_repeated_calls() {
+        let provider = MockProvider::new().with_response("button", "<button>Click me</button>");
+        let request = GenerationRequest::builder(Slot::new("button", "Create a button"))
+            .with_n(3)
+            .build();
+
+        let responses = provider.generate_n(request).await.unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(provider.call_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_engine_from_boxed_provider() {
+        let provider: Box<dyn AiProvider> = Box::new(MockProvider::new().with_response("greeting", "hello"));
+        assert!(provider.health_check().await.unwrap());
+
+        let engine = crate::InjectionEngine::new(provider);
+        let result = engine.render(&crate::Template::new("{{AI:greeting}}")).await.unwrap();
+        assert_eq!(result, "hello");
+    }
+
+    #[tokio::test]
+    async fn test_stream_through_trait_object() {
+        use futures::StreamExt;
+
+        // Same shape the FFI stores its provider in
+        let provider: Arc<dyn AiProvider + Send + Sync> =
+            Arc::new(MockProvider::new().with_response("code", "let x = 1;"));
+
+        let request = GenerationRequest::builder(Slot::new("code", "Declare x")).build();
+        let chunks: Vec<String> = provider
+            .generate_stream(request)
+            .map(|chunk| chunk.unwrap().delta)
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks.concat(), "let x = 1;");
+
+        // And through an engine wrapping the trait object
+        let engine = crate::InjectionEngine::new(provider);
+        let stream = engine
+            .generate_slot_stream(&crate::Template::new("{{AI:code}}").with_slot("code", "Declare x"), "code")
+            .unwrap();
+        assert_eq!(stream.count().await, 4);
+    }
+
+    #[test]
+    fn test_distinct_timeouts() {
+        let config = ProviderConfig::new("key", "model");
+        assert_eq!(config.request_timeout(), Duration::from_secs(DEFAULT_REQUEST_TIMEOUT_SECS));
+        assert_eq!(config.stream_timeout(), Duration::from_secs(DEFAULT_STREAM_TIMEOUT_SECS));
+
+        let config = config.with_timeout(30).with_stream_timeout(900);
+        assert_eq!(config.request_timeout(), Duration::from_secs(30));
+        assert_eq!(config.stream_timeout(), Duration::from_secs(900));
+    }
 }
@@ -0,0 +1,144 @@
+//! Shared rate limiting for provider calls.
+//!
+//! A `RateLimiter` enforces requests-per-minute and tokens-per-minute
+//! budgets using token buckets. Wrap it in an `Arc` to share one budget
+//! across several engines using the same API key.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A single token bucket refilled continuously.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: u32) -> Self {
+        let capacity = limit.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Time until `amount` units are available (zero if available now).
+    fn wait_for(&self, amount: f64) -> Duration {
+        if self.available >= amount {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((amount - self.available) / self.refill_per_sec)
+        }
+    }
+}
+
+/// Token-bucket rate limiter for requests and tokens per minute.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    requests: Option<Mutex<Bucket>>,
+    tokens: Option<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit requests per minute.
+    pub fn with_rpm(mut self, rpm: u32) -> Self {
+        self.requests = Some(Mutex::new(Bucket::per_minute(rpm)));
+        self
+    }
+
+    /// Limit tokens per minute.
+    pub fn with_tpm(mut self, tpm: u32) -> Self {
+        self.tokens = Some(Mutex::new(Bucket::per_minute(tpm)));
+        self
+    }
+
+    /// Wait until one request costing `tokens` fits in the budget, then consume it.
+    ///
+    /// Requests larger than the whole token budget are clamped to it so they
+    /// can still proceed once the bucket is full.
+    pub async fn acquire(&self, tokens: u32) {
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut requests = self.requests.as_ref().map(|b| b.lock().unwrap());
+                let mut token_bucket = self.tokens.as_ref().map(|b| b.lock().unwrap());
+
+                let mut wait = Duration::ZERO;
+                if let Some(ref mut bucket) = requests {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_for(1.0));
+                }
+                let token_cost = token_bucket
+                    .as_ref()
+                    .map(|b| (tokens as f64).min(b.capacity))
+                    .unwrap_or(0.0);
+                if let Some(ref mut bucket) = token_bucket {
+                    bucket.refill(now);
+                    wait = wait.max(bucket.wait_for(token_cost));
+                }
+
+                if wait.is_zero() {
+                    if let Some(ref mut bucket) = requests {
+                        bucket.available -= 1.0;
+                    }
+                    if let Some(ref mut bucket) = token_bucket {
+                        bucket.available -= token_cost;
+                    }
+                    return;
+                }
+                wait
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rpm_paces_burst() {
+        let limiter = Arc::new(RateLimiter::new().with_rpm(2));
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            limiter.acquire(0).await;
+        }
+
+        // 2 immediate, then one every 30 seconds
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(90), "elapsed: {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(91), "elapsed: {:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tpm_limits_tokens() {
+        let limiter = RateLimiter::new().with_tpm(1000);
+        let start = Instant::now();
+
+        limiter.acquire(1000).await;
+        limiter.acquire(500).await;
+
+        assert!(start.elapsed() >= Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,162 @@
+//! Prompt recording for regression tests.
+//!
+//! A `RecordingProvider` writes the messages of every request it receives
+//! to a JSONL file before delegating to the wrapped provider. Checking the
+//! file in next to a fixture template makes prompt changes show up in code
+//! review diffs.
+
+use crate::provider::{AiProvider, GenerationRequest, GenerationResponse, PromptPreview, StreamResponse};
+use crate::Result;
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Provider wrapper that writes each request to a JSONL file.
+///
+/// The file is truncated when the recorder is created (and by `clear`),
+/// and rewritten sorted by slot name after every request, so re-running a
+/// render produces the same file regardless of completion order.
+pub struct RecordingProvider {
+    inner: Arc<dyn AiProvider>,
+    path: PathBuf,
+    entries: Mutex<Vec<serde_json::Value>>,
+}
+
+impl RecordingProvider {
+    /// Wrap `inner`, recording requests to `path` (created or truncated).
+    pub fn new(inner: Arc<dyn AiProvider>, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        std::fs::write(&path, "")?;
+        Ok(Self {
+            inner,
+            path,
+            entries: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Forget recorded requests and truncate the file, e.g. before reusing
+    /// the recorder for another render.
+    pub fn clear(&self) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clear();
+        std::fs::write(&self.path, "")?;
+        Ok(())
+    }
+
+    /// One JSON object describing what is sent to the model, as composed by
+    /// the wrapped provider.
+    fn record(&self, request: &GenerationRequest) {
+        let preview = self.inner.preview_prompt(request);
+        let entry = serde_json::json!({
+            "slot": request.slot.name,
+            "kind": request.slot.kind,
+            "system": preview.system,
+            "user": preview.user,
+            "model": request.model,
+            "max_tokens": request.max_tokens,
+        });
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(entry);
+        // Stable sort: retries of one slot stay in the order they were sent
+        entries.sort_by(|a, b| a["slot"].as_str().cmp(&b["slot"].as_str()));
+
+        let content: String = entries.iter().map(|e| format!("{}\n", e)).collect();
+        if let Err(e) = std::fs::write(&self.path, content) {
+            // Recording is a test aid; never fail generation because of it
+            warn!("Failed to record request for slot '{}': {}", request.slot.name, e);
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for RecordingProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.inner.model()
+    }
+
+    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+        self.record(&request);
+        self.inner.generate(request).await
+    }
+
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        self.record(&request);
+        self.inner.generate_n(request).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> BoxStream<'static, Result<StreamResponse>> {
+        self.record(&request);
+        self.inner.generate_stream(request)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        self.inner.health_check().await
+    }
+
+    async fn health_report(&self) -> Vec<(String, bool)> {
+        self.inner.health_report().await
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.inner.count_tokens(text)
+    }
+
+    fn preview_prompt(&self, request: &GenerationRequest) -> PromptPreview {
+        self.inner.preview_prompt(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use crate::{InjectionEngine, Slot, Template};
+
+    #[tokio::test]
+    async fn test_records_composed_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.jsonl");
+        std::fs::write(&path, "{\"slot\": \"stale\"}\n").unwrap();
+
+        let template = Template::new("{{AI:header}}\n{{AI:footer}}")
+            .configure_slot(Slot::new("header", "Create a page header"))
+            .configure_slot(Slot::new("footer", "Create a page footer"));
+
+        // Two runs of the same render leave the same file behind
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let mock = MockProvider::new()
+                .with_response("header", "<h1>Hi</h1>")
+                .with_response("footer", "<footer/>");
+            let recorder = RecordingProvider::new(Arc::new(mock), &path).unwrap();
+            let output = InjectionEngine::new(recorder).render(&template).await.unwrap();
+            assert_eq!(output, "<h1>Hi</h1>\n<footer/>");
+            runs.push(std::fs::read_to_string(&path).unwrap());
+        }
+        assert_eq!(runs[0], runs[1]);
+
+        let entries: Vec<serde_json::Value> = runs[0]
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["slot"], "footer");
+        assert_eq!(entries[0]["user"], "Create a page footer");
+        assert_eq!(entries[1]["slot"], "header");
+        assert_eq!(entries[1]["user"], "Create a page header");
+    }
+}
@@ -0,0 +1,205 @@
+//! Offline replay of recorded provider responses.
+//!
+//! `ReplayProvider` serves responses from a JSON fixture keyed by the
+//! request fingerprint, so demos and CI can run without API keys. Wrapping
+//! a real provider with `with_fallback` records responses for requests that
+//! are not in the fixture yet; `save` writes them back out.
+
+use crate::provider::{AiProvider, GenerationRequest, GenerationResponse};
+use crate::{AetherError, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Provider that replays recorded responses keyed by request hash.
+pub struct ReplayProvider {
+    /// Recorded responses (request hash -> generated code).
+    fixtures: RwLock<BTreeMap<String, String>>,
+
+    /// Provider used to record responses missing from the fixtures.
+    fallback: Option<Arc<dyn AiProvider>>,
+}
+
+impl ReplayProvider {
+    /// Create a replay provider with no recorded responses.
+    pub fn new() -> Self {
+        Self::from_fixtures(BTreeMap::new())
+    }
+
+    /// Create a replay provider from in-memory fixtures.
+    pub fn from_fixtures(fixtures: BTreeMap<String, String>) -> Self {
+        Self {
+            fixtures: RwLock::new(fixtures),
+            fallback: None,
+        }
+    }
+
+    /// Load fixtures from a JSON object of `request_hash -> response`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Self::from_fixtures(serde_json::from_str(&content)?))
+    }
+
+    /// Record mode: forward misses to `provider` and keep its responses.
+    pub fn with_fallback(mut self, provider: Arc<dyn AiProvider>) -> Self {
+        self.fallback = Some(provider);
+        self
+    }
+
+    /// Write all fixtures, including newly recorded ones, to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let content = serde_json::to_string_pretty(&*self.fixtures.read().unwrap())?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Fixture key for a request.
+    pub fn key(request: &GenerationRequest) -> String {
+        format!("{:016x}", request.fingerprint())
+    }
+
+    /// Number of recorded responses.
+    pub fn len(&self) -> usize {
+        self.fixtures.read().unwrap().len()
+    }
+
+    /// Whether no responses are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ReplayProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AiProvider for ReplayProvider {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    async fn generate(&self, request: GenerationRequest) -> Result<GenerationResponse> {
+        let key = Self::key(&request);
+        if let Some(code) = self.fixtures.read().unwrap().get(&key).cloned() {
+            return Ok(GenerationResponse {
+                code,
+                tokens_used: None,
+                metadata: None,
+            });
+        }
+
+        let Some(fallback) = &self.fallback else {
+            return Err(AetherError::ProviderError(format!(
+                "No recorded response for request {} (slot '{}')",
+                key, request.slot.name
+            )));
+        };
+
+        let response = fallback.generate(request).await?;
+        self.fixtures
+            .write()
+            .unwrap()
+            .insert(key, response.code.clone());
+        Ok(response)
+    }
+
+    /// Candidates after the first are recorded as `<key>#<index>`.
+    async fn generate_n(&self, request: GenerationRequest) -> Result<Vec<GenerationResponse>> {
+        let key = Self::key(&request);
+        let n = request.n.unwrap_or(1).max(1) as usize;
+        let keys: Vec<String> = (0..n)
+            .map(|i| if i == 0 { key.clone() } else { format!("{}#{}", key, i) })
+            .collect();
+
+        let recorded: Option<Vec<String>> = {
+            let fixtures = self.fixtures.read().unwrap();
+            keys.iter().map(|k| fixtures.get(k).cloned()).collect()
+        };
+        if let Some(codes) = recorded {
+            return Ok(codes
+                .into_iter()
+                .map(|code| GenerationResponse {
+                    code,
+                    tokens_used: None,
+                    metadata: None,
+                })
+                .collect());
+        }
+
+        let Some(fallback) = &self.fallback else {
+            return Err(AetherError::ProviderError(format!(
+                "No recorded responses for request {} (slot '{}', n = {})",
+                key, request.slot.name, n
+            )));
+        };
+
+        let responses = fallback.generate_n(request).await?;
+        let mut fixtures = self.fixtures.write().unwrap();
+        for (key, response) in keys.into_iter().zip(&responses) {
+            fixtures.insert(key, response.code.clone());
+        }
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use crate::Slot;
+
+    fn request(name: &str, prompt: &str) -> GenerationRequest {
+        GenerationRequest::builder(Slot::new(name, prompt)).build()
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.json");
+
+        // Record through a wrapped provider
+        let mock = MockProvider::new().with_response("button", "<button>Go</button>");
+        let recorder = ReplayProvider::new().with_fallback(Arc::new(mock));
+        let recorded = recorder
+            .generate(request("button", "Create a button"))
+            .await
+            .unwrap();
+        assert_eq!(recorded.code, "<button>Go</button>");
+        recorder.save(&path).unwrap();
+
+        // Replay offline, without a fallback
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        assert_eq!(replay.len(), 1);
+        let replayed = replay
+            .generate(request("button", "Create a button"))
+            .await
+            .unwrap();
+        assert_eq!(replayed.code, recorded.code);
+
+        let miss = replay.generate(request("button", "Create a link")).await;
+        assert!(matches!(miss, Err(AetherError::ProviderError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_candidates() {
+        let candidates = || {
+            GenerationRequest::builder(Slot::new("button", "Create a button"))
+                .with_n(2)
+                .build()
+        };
+
+        let mock = Arc::new(MockProvider::new().with_response("button", "<button>Go</button>"));
+        let recorder = ReplayProvider::new().with_fallback(mock.clone());
+        assert_eq!(recorder.generate_n(candidates()).await.unwrap().len(), 2);
+        assert_eq!(recorder.len(), 2);
+
+        // Replayed from the fixtures, without asking the fallback again
+        let replayed = recorder.generate_n(candidates()).await.unwrap();
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(mock.call_count(), 2);
+    }
+}
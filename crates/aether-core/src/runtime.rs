@@ -4,7 +4,7 @@
 //! This module uses the Rhai script engine to execute code generated by AI at runtime.
 
 use crate::{Result, AetherError};
-use rhai::{Engine, Dynamic, Scope};
+use rhai::{Engine, Dynamic, Map, Scope};
 use std::collections::HashMap;
 
 /// A runtime environment capable of executing AI-generated scripts in isolation.
@@ -40,6 +40,48 @@ impl AetherRuntime {
         self.engine.eval_with_scope(&mut scope, script)
             .map_err(|e| AetherError::ConfigError(format!("Runtime execution failed: {}", e)))
     }
+
+    /// Execute a script and convert its result to JSON (see [`AetherRuntime::to_json`]).
+    pub fn execute_json(&self, script: &str, inputs: HashMap<String, Dynamic>) -> Result<serde_json::Value> {
+        self.execute(script, inputs).map(Self::to_json)
+    }
+
+    /// Convert a script result to JSON.
+    ///
+    /// | Rhai             | JSON                               |
+    /// |------------------|------------------------------------|
+    /// | `()`             | `null`                             |
+    /// | `bool`           | boolean                            |
+    /// | `INT` / `FLOAT`  | number (non-finite floats: `null`) |
+    /// | string / `char`  | string                             |
+    /// | `Array`          | array (converted recursively)      |
+    /// | `Map`            | object (converted recursively)     |
+    /// | anything else    | its `to_string()` as a string      |
+    pub fn to_json(value: Dynamic) -> serde_json::Value {
+        use serde_json::Value;
+
+        if value.is_unit() {
+            Value::Null
+        } else if let Ok(b) = value.as_bool() {
+            Value::Bool(b)
+        } else if let Ok(i) = value.as_int() {
+            Value::from(i)
+        } else if let Ok(f) = value.as_float() {
+            serde_json::Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null)
+        } else if let Ok(c) = value.as_char() {
+            Value::String(c.to_string())
+        } else if value.is_string() {
+            Value::String(value.into_string().unwrap_or_default())
+        } else if value.is_array() {
+            let items = value.into_array().unwrap_or_default();
+            Value::Array(items.into_iter().map(Self::to_json).collect())
+        } else if value.is_map() {
+            let map = value.cast::<Map>();
+            Value::Object(map.into_iter().map(|(k, v)| (k.to_string(), Self::to_json(v))).collect())
+        } else {
+            Value::String(value.to_string())
+        }
+    }
 }
 
 impl Default for AetherRuntime {
@@ -47,3 +89,33 @@ impl Default for AetherRuntime {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_returns_string_array_as_json() {
+        let runtime = AetherRuntime::new();
+        let script = r#"
+            let names = [];
+            for n in ["a", "b", "c"] {
+                names.push(n + "!");
+            }
+            names
+        "#;
+
+        let value = runtime.execute_json(script, HashMap::new()).unwrap();
+        assert_eq!(value, serde_json::json!(["a!", "b!", "c!"]));
+    }
+
+    #[test]
+    fn test_script_returns_map_as_json() {
+        let runtime = AetherRuntime::new();
+        let mut inputs = HashMap::new();
+        inputs.insert("x".to_string(), Dynamic::from(2_i64));
+
+        let value = runtime.execute_json("#{ doubled: x * 2, ok: true, none: () }", inputs).unwrap();
+        assert_eq!(value, serde_json::json!({"doubled": 4, "ok": true, "none": null}));
+    }
+}
@@ -3,6 +3,7 @@
 //! Slots are placeholders in templates where AI-generated code will be injected.
 
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Represents a slot in a template where code can be injected.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -33,6 +34,24 @@ pub struct Slot {
 
     /// Maximum tokens to generate for this slot.
     pub max_tokens: Option<u32>,
+
+    /// Number of candidates to generate concurrently; the first valid one wins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<usize>,
+
+    /// Slots that must be generated first; their output is added to this slot's context.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+
+    /// File the slot's code is written to by `render_to_files`, relative to
+    /// the base directory. The rendered template then contains this path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<PathBuf>,
+
+    /// Send the context to this slot as TOON (`true`) or plain text
+    /// (`false`). None follows the engine's TOON setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_toon: Option<bool>,
 }
 
 /// The kind of slot determines how code is generated.
@@ -61,10 +80,52 @@ pub enum SlotKind {
     /// Complete component (HTML + CSS + JS).
     Component,
 
+    /// A single JSON value.
+    Json,
+
     /// Custom kind with user-defined wrapper.
     Custom(String),
 }
 
+impl std::str::FromStr for SlotKind {
+    type Err = std::convert::Infallible;
+
+    /// Parse a kind name as used in `{{AI:name:kind}}` markers.
+    /// Unknown names become `SlotKind::Custom`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "raw" => SlotKind::Raw,
+            "function" | "fn" => SlotKind::Function,
+            "class" | "struct" => SlotKind::Class,
+            "html" => SlotKind::Html,
+            "css" => SlotKind::Css,
+            "js" | "javascript" => SlotKind::JavaScript,
+            "component" => SlotKind::Component,
+            "json" => SlotKind::Json,
+            other => SlotKind::Custom(other.to_string()),
+        })
+    }
+}
+
+impl SlotKind {
+    /// Infer a kind from a file extension (with or without the leading dot),
+    /// e.g. `rs` -> `Function`, `css` -> `Css`.
+    ///
+    /// Only extensions whose language `MultiValidator` checks are mapped;
+    /// others (`ts`, `py`, `vue`, ...) return `None` so their slots are not
+    /// validated with the wrong compiler.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.trim_start_matches('.').to_lowercase().as_str() {
+            "rs" => Some(SlotKind::Function),
+            "js" | "mjs" | "cjs" => Some(SlotKind::JavaScript),
+            "html" | "htm" => Some(SlotKind::Html),
+            "css" => Some(SlotKind::Css),
+            "json" => Some(SlotKind::Json),
+            _ => None,
+        }
+    }
+}
+
 /// Constraints on generated code.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct SlotConstraints {
@@ -89,6 +150,10 @@ pub struct SlotConstraints {
 
     /// Command to execute the test harness (e.g., "cargo test", "node test.js").
     pub test_command: Option<String>,
+
+    /// JSON Schema the output of a `SlotKind::Json` slot must conform to.
+    #[serde(default)]
+    pub json_schema: Option<String>,
 }
 
 impl Eq for Slot {}
@@ -106,9 +171,27 @@ impl std::hash::Hash for Slot {
         }
         self.model.hash(state);
         self.max_tokens.hash(state);
+        self.candidates.hash(state);
+        self.depends_on.hash(state);
+        if let Some(use_toon) = self.use_toon {
+            use_toon.hash(state);
+        }
     }
 }
 
+/// Prompt assigned to slots parsed from a template without an explicit prompt.
+pub(crate) fn placeholder_prompt(name: &str) -> String {
+    format!("Generate code for: {}", name)
+}
+
+/// Collapse runs of whitespace to a single space and trim the ends.
+///
+/// Used for cache and session keys when `normalize_prompts_for_cache` is set,
+/// so cosmetic prompt edits keep hitting earlier results.
+pub fn normalize_prompt(prompt: &str) -> String {
+    prompt.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 impl Slot {
     /// Create a new slot with the given name and prompt.
     ///
@@ -136,6 +219,10 @@ impl Slot {
             temperature: None,
             model: None,
             max_tokens: None,
+            candidates: None,
+            depends_on: Vec::new(),
+            output_path: None,
+            use_toon: None,
         }
     }
 
@@ -157,6 +244,24 @@ impl Slot {
         self
     }
 
+    /// Generate `n` candidates concurrently and keep the first that passes validation.
+    pub fn with_candidates(mut self, n: usize) -> Self {
+        self.candidates = Some(n);
+        self
+    }
+
+    /// Generate this slot after `slot`, with its output in the context.
+    pub fn depends_on(mut self, slot: impl Into<String>) -> Self {
+        self.depends_on.push(slot.into());
+        self
+    }
+
+    /// Write this slot to its own file in `render_to_files`.
+    pub fn with_output_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output_path = Some(path.into());
+        self
+    }
+
     /// Set the slot kind.
     pub fn with_kind(mut self, kind: SlotKind) -> Self {
         self.kind = kind;
@@ -169,6 +274,22 @@ impl Slot {
         self
     }
 
+    /// Force TOON (`true`) or plain (`false`) context for this slot,
+    /// overriding the engine setting.
+    pub fn with_toon(mut self, enabled: bool) -> Self {
+        self.use_toon = Some(enabled);
+        self
+    }
+
+    /// Provide a known-good default while keeping the slot required.
+    ///
+    /// With `AetherConfig::seed_with_default`, the default is also sent as a
+    /// baseline for the model to improve on.
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
     /// Mark this slot as optional with a default value.
     pub fn optional(mut self, default: impl Into<String>) -> Self {
         self.required = false;
@@ -176,6 +297,11 @@ impl Slot {
         self
     }
 
+    /// Whether the prompt is still the auto-generated placeholder.
+    pub fn has_placeholder_prompt(&self) -> bool {
+        self.prompt == placeholder_prompt(&self.name)
+    }
+
     /// Validate the generated code against constraints.
     pub fn validate(&self, code: &str) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
@@ -261,6 +387,49 @@ impl SlotConstraints {
         self.test_command = Some(command.into());
         self
     }
+
+    /// Set a JSON Schema for JSON output.
+    pub fn json_schema(mut self, schema: impl Into<String>) -> Self {
+        self.json_schema = Some(schema.into());
+        self
+    }
+
+    /// Summarize the constraints as prompt text.
+    /// Returns `None` if no constraint is set.
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+
+        if let Some(ref lang) = self.language {
+            parts.push(format!("- Language: {}", lang));
+        }
+        if let Some(max) = self.max_lines {
+            parts.push(format!("- At most {} lines", max));
+        }
+        if let Some(max) = self.max_chars {
+            parts.push(format!("- At most {} characters", max));
+        }
+        if !self.required_imports.is_empty() {
+            parts.push(format!("- Required imports: {}", self.required_imports.join(", ")));
+        }
+        if !self.forbidden_patterns.is_empty() {
+            parts.push(format!("- Must not match: {}", self.forbidden_patterns.join(", ")));
+        }
+        if let Some(ref schema) = self.json_schema {
+            parts.push(format!("- Output must conform to this JSON Schema:\n```json\n{}\n```", schema));
+        }
+        if let Some(ref harness) = self.test_harness {
+            parts.push(format!(
+                "- Your code replaces {{{{CODE}}}} in this test harness and must pass it:\n```\n{}\n```",
+                harness
+            ));
+        }
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("CONSTRAINTS:\n{}", parts.join("\n")))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -275,6 +444,13 @@ mod tests {
         assert!(slot.required);
     }
 
+    #[test]
+    fn test_with_default_keeps_slot_required() {
+        let slot = Slot::new("greet", "Greet the user").with_default("fn greet() {}");
+        assert!(slot.required);
+        assert_eq!(slot.default.as_deref(), Some("fn greet() {}"));
+    }
+
     #[test]
     fn test_slot_validation() {
         let slot = Slot::new("test", "")
@@ -283,4 +459,28 @@ mod tests {
         assert!(slot.validate("line1\nline2\nline3").is_ok());
         assert!(slot.validate("1\n2\n3\n4\n5\n6").is_err());
     }
+
+    #[test]
+    fn test_kind_from_extension() {
+        assert_eq!(SlotKind::from_extension("rs"), Some(SlotKind::Function));
+        assert_eq!(SlotKind::from_extension(".JS"), Some(SlotKind::JavaScript));
+        assert_eq!(SlotKind::from_extension("ts"), None);
+        assert_eq!(SlotKind::from_extension("py"), None);
+        assert_eq!(SlotKind::from_extension("css"), Some(SlotKind::Css));
+        assert_eq!(SlotKind::from_extension("html"), Some(SlotKind::Html));
+        assert_eq!(SlotKind::from_extension("txt"), None);
+    }
+
+    #[test]
+    fn test_constraints_summary() {
+        assert!(SlotConstraints::new().summary().is_none());
+
+        let summary = SlotConstraints::new()
+            .max_lines(10)
+            .require_import("std::fmt")
+            .summary()
+            .unwrap();
+        assert!(summary.contains("At most 10 lines"));
+        assert!(summary.contains("std::fmt"));
+    }
 }
@@ -3,12 +3,14 @@
 //! Templates contain slots marked with `{{AI:slot_name}}` syntax that will be
 //! replaced with AI-generated code.
 
-use crate::{AetherError, Result, Slot, SlotKind};
+use crate::{AetherError, Result, Slot, SlotConstraints, SlotKind};
 use regex::Regex;
 use std::sync::OnceLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::path::Path;
+use std::str::FromStr;
 
 /// Pattern for matching AI slots in templates.
 /// Format: {{AI:slot_name}} or {{AI:slot_name:kind}}
@@ -34,6 +36,22 @@ pub struct Template {
 
     /// Template metadata.
     pub metadata: TemplateMetadata,
+
+    /// How optional slots without an injection or default are rendered.
+    #[serde(default)]
+    pub unfilled_strategy: UnfilledStrategy,
+}
+
+/// Rendering of optional slots that have no injection and no default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnfilledStrategy {
+    /// Replace the marker with an empty string.
+    #[default]
+    Empty,
+    /// Remove the whole line when the marker is alone on it (surrounding
+    /// whitespace aside); markers sharing a line with other text become empty.
+    RemoveLine,
 }
 
 /// Metadata about a template.
@@ -68,6 +86,56 @@ pub struct SlotLocation {
     pub kind: Option<SlotKind>,
 }
 
+/// Output byte range produced by a slot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// Slot name.
+    pub slot: String,
+
+    /// Start byte offset in the rendered output.
+    pub start: usize,
+
+    /// End byte offset (exclusive) in the rendered output.
+    pub end: usize,
+}
+
+/// Maps ranges of rendered output back to the slots that produced them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// Spans in document order. A slot used twice has two spans.
+    pub spans: Vec<SourceSpan>,
+}
+
+impl SourceMap {
+    /// Slot that produced the byte at `offset`, if any.
+    pub fn slot_at(&self, offset: usize) -> Option<&str> {
+        self.spans
+            .iter()
+            .find(|span| span.start <= offset && offset < span.end)
+            .map(|span| span.slot.as_str())
+    }
+
+    /// All spans produced by `slot`.
+    pub fn spans_for<'a>(&'a self, slot: &'a str) -> impl Iterator<Item = &'a SourceSpan> {
+        self.spans.iter().filter(move |span| span.slot == slot)
+    }
+
+    /// Convert a byte offset in `output` to a 1-based `(line, column)`.
+    ///
+    /// Columns count characters, as editors do. An offset inside a
+    /// multi-byte character maps to that character.
+    pub fn line_col(output: &str, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(output.len());
+        while !output.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &output[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
+
 impl Template {
     /// Create a new template from content.
     ///
@@ -92,6 +160,7 @@ impl Template {
             name: String::from("unnamed"),
             slots,
             metadata: TemplateMetadata::default(),
+            unfilled_strategy: UnfilledStrategy::default(),
         }
     }
 
@@ -110,6 +179,7 @@ impl Template {
             slots: Self::parse_slots(&content),
             content,
             metadata: TemplateMetadata::default(),
+            unfilled_strategy: UnfilledStrategy::default(),
         })
     }
 
@@ -119,6 +189,12 @@ impl Template {
         self
     }
 
+    /// Set how unfilled optional slots are rendered.
+    pub fn with_unfilled_strategy(mut self, strategy: UnfilledStrategy) -> Self {
+        self.unfilled_strategy = strategy;
+        self
+    }
+
     /// Set template metadata.
     pub fn with_metadata(mut self, metadata: TemplateMetadata) -> Self {
         self.metadata = metadata;
@@ -147,6 +223,76 @@ impl Template {
         self
     }
 
+    /// Attach constraints to a slot, keeping its parsed kind and prompt.
+    ///
+    /// Creates the slot with the placeholder prompt if it does not exist.
+    pub fn with_slot_constraints(mut self, name: &str, constraints: SlotConstraints) -> Self {
+        self.configure(name, |slot| slot.constraints = Some(constraints));
+        self
+    }
+
+    /// Configure a slot in place, creating it if absent.
+    ///
+    /// ```
+    /// use aether_core::{SlotKind, Template};
+    ///
+    /// let mut template = Template::new("{{AI:a}} {{AI:b}}");
+    /// template
+    ///     .configure("a", |slot| slot.temperature = Some(0.2))
+    ///     .configure("b", |slot| slot.kind = SlotKind::Css);
+    /// ```
+    pub fn configure(&mut self, name: &str, f: impl FnOnce(&mut Slot)) -> &mut Self {
+        let slot = self
+            .slots
+            .entry(name.to_string())
+            .or_insert_with(|| Slot::new(name, crate::slot::placeholder_prompt(name)));
+        f(slot);
+        self
+    }
+
+    /// Insert a `{{AI:name}}` marker (or `{{AI:name:kind}}`) at byte offset
+    /// `pos` and register the slot.
+    ///
+    /// An existing slot of the same name keeps its configuration; only its
+    /// kind is updated when `kind` is given.
+    pub fn insert_slot_marker(&mut self, pos: usize, name: &str, kind: Option<&str>) -> Result<&mut Self> {
+        if pos > self.content.len() || !self.content.is_char_boundary(pos) {
+            return Err(AetherError::TemplateParse(format!("Invalid marker position {}", pos)));
+        }
+
+        let marker = match kind {
+            Some(kind) => format!("{{{{AI:{}:{}}}}}", name, kind),
+            None => format!("{{{{AI:{}}}}}", name),
+        };
+        if get_slot_regex().find(&marker).map(|m| m.len()) != Some(marker.len()) {
+            return Err(AetherError::TemplateParse(format!("Invalid slot marker '{}'", marker)));
+        }
+
+        self.content.insert_str(pos, &marker);
+        self.configure(name, |slot| {
+            if let Some(kind) = kind {
+                slot.kind = Self::parse_kind(kind);
+            }
+        });
+        Ok(self)
+    }
+
+    /// Remove every marker of slot `name` from the content and drop the slot.
+    ///
+    /// Returns the removed slot, or `None` if the template had no such slot.
+    pub fn remove_slot(&mut self, name: &str) -> Option<Slot> {
+        // Locations are last-first, so earlier offsets stay valid
+        for loc in self.find_locations().into_iter().filter(|loc| loc.name == name) {
+            self.content.replace_range(loc.start..loc.end, "");
+        }
+        self.slots.remove(name)
+    }
+
+    /// Iterate mutably over all slots.
+    pub fn slots_mut(&mut self) -> impl Iterator<Item = &mut Slot> {
+        self.slots.values_mut()
+    }
+
     /// Parse slots from template content.
     fn parse_slots(content: &str) -> HashMap<String, Slot> {
         let re = get_slot_regex();
@@ -156,7 +302,7 @@ impl Template {
             let name = cap[1].to_string();
             let kind = cap.get(2).map(|m| Self::parse_kind(m.as_str()));
 
-            let mut slot = Slot::new(&name, format!("Generate code for: {}", name));
+            let mut slot = Slot::new(&name, crate::slot::placeholder_prompt(&name));
             if let Some(k) = kind {
                 slot = slot.with_kind(k);
             }
@@ -168,20 +314,11 @@ impl Template {
 
     /// Parse slot kind from string.
     fn parse_kind(s: &str) -> SlotKind {
-        match s.to_lowercase().as_str() {
-            "raw" => SlotKind::Raw,
-            "function" | "fn" => SlotKind::Function,
-            "class" | "struct" => SlotKind::Class,
-            "html" => SlotKind::Html,
-            "css" => SlotKind::Css,
-            "js" | "javascript" => SlotKind::JavaScript,
-            "component" => SlotKind::Component,
-            other => SlotKind::Custom(other.to_string()),
-        }
+        s.parse().unwrap_or_default()
     }
 
-    /// Find all slot locations in the template content.
-    fn find_locations(&self) -> Vec<SlotLocation> {
+    /// Find all slot locations in the template content, last one first.
+    pub(crate) fn find_locations(&self) -> Vec<SlotLocation> {
         let re = get_slot_regex();
         let mut locations = Vec::new();
 
@@ -206,25 +343,84 @@ impl Template {
     ///
     /// * `injections` - Map of slot names to generated code
     pub fn render(&self, injections: &HashMap<String, String>) -> Result<String> {
-        let mut result = self.content.clone();
-        let locations = self.find_locations();
+        self.render_with_map(injections).map(|(output, _)| output)
+    }
 
+    /// Render the template and record which output range each slot produced.
+    pub fn render_with_map(&self, injections: &HashMap<String, String>) -> Result<(String, SourceMap)> {
+        let mut output = String::with_capacity(self.content.len());
+        let mut map = SourceMap::default();
+        let mut locations = self.find_locations();
+        locations.reverse();
+
+        let mut pos = 0;
         for loc in locations {
             let code = if let Some(code) = injections.get(&loc.name) {
-                code.clone()
+                code.as_str()
             } else if let Some(slot) = self.slots.get(&loc.name) {
                 if slot.required {
                     return Err(AetherError::SlotNotFound(loc.name));
                 }
-                slot.default.clone().unwrap_or_default()
+                match slot.default.as_deref() {
+                    Some(default) => default,
+                    None => {
+                        if let Some((line_start, line_end)) = self.removable_line(&loc, pos) {
+                            output.push_str(&self.content[pos..line_start]);
+                            if line_end == self.content.len() {
+                                // The last line has no break of its own: drop the one
+                                // before it, even if earlier removed lines precede it
+                                if output.ends_with('\n') {
+                                    output.pop();
+                                    if output.ends_with('\r') {
+                                        output.pop();
+                                    }
+                                }
+                            }
+                            map.spans.push(SourceSpan { slot: loc.name, start: output.len(), end: output.len() });
+                            pos = line_end;
+                            continue;
+                        }
+                        ""
+                    }
+                }
             } else {
                 return Err(AetherError::SlotNotFound(loc.name));
             };
 
-            result.replace_range(loc.start..loc.end, &code);
+            output.push_str(&self.content[pos..loc.start]);
+            let start = output.len();
+            output.push_str(code);
+            map.spans.push(SourceSpan { slot: loc.name, start, end: output.len() });
+            pos = loc.end;
         }
+        output.push_str(&self.content[pos..]);
 
-        Ok(result)
+        Ok((output, map))
+    }
+
+    /// Byte range of the line holding `loc`, if the `RemoveLine` strategy
+    /// applies: the marker is alone on its line and the line starts at or
+    /// after `pos`. The range includes the line break; for the last line,
+    /// the caller drops the break before it instead.
+    fn removable_line(&self, loc: &SlotLocation, pos: usize) -> Option<(usize, usize)> {
+        if self.unfilled_strategy != UnfilledStrategy::RemoveLine {
+            return None;
+        }
+
+        let line_start = self.content[..loc.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = self.content[loc.end..].find('\n').map(|i| loc.end + i + 1);
+        let before = &self.content[line_start..loc.start];
+        let after = &self.content[loc.end..line_end.map(|e| e - 1).unwrap_or(self.content.len())];
+        if line_start < pos || !before.trim().is_empty() || !after.trim().is_empty() {
+            return None;
+        }
+
+        Some((line_start, line_end.unwrap_or(self.content.len())))
+    }
+
+    /// Stable hash of the raw template content, including literal text.
+    pub fn content_hash(&self) -> u64 {
+        crate::RenderSession::hash(&self.content)
     }
 
     /// Get a list of slot names.
@@ -232,6 +428,29 @@ impl Template {
         self.slots.keys().map(|s| s.as_str()).collect()
     }
 
+    /// Check that every configured slot has a `{{AI:name}}` marker in the
+    /// content. Slots without one would be generated but never rendered,
+    /// which usually means a typo in `configure_slot`/`with_slot`.
+    pub fn validate_slots(&self) -> Result<()> {
+        let markers: std::collections::HashSet<String> =
+            self.find_locations().into_iter().map(|loc| loc.name).collect();
+        let mut orphans: Vec<&str> = self
+            .slots
+            .keys()
+            .filter(|name| !markers.contains(*name))
+            .map(|name| name.as_str())
+            .collect();
+        orphans.sort();
+
+        match orphans.first() {
+            Some(name) => Err(AetherError::InvalidSlot {
+                name: name.to_string(),
+                reason: format!("no {{{{AI:{}}}}} marker in template content", name),
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Check if template has unfilled required slots.
     pub fn validate(&self, injections: &HashMap<String, String>) -> Result<()> {
         for (name, slot) in &self.slots {
@@ -243,6 +462,16 @@ impl Template {
     }
 }
 
+impl FromStr for Template {
+    type Err = Infallible;
+
+    /// Parse a template from a string. Parsing never fails; unknown
+    /// content is simply treated as a template without slots.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,4 +500,152 @@ mod tests {
         assert_eq!(template.slots.get("func").unwrap().kind, SlotKind::Function);
         assert_eq!(template.slots.get("style").unwrap().kind, SlotKind::Css);
     }
+
+    #[test]
+    fn test_from_str() {
+        let template: Template = "fn {{AI:name}}() { {{AI:body:function}} }".parse().unwrap();
+        let mut names = template.slot_names();
+        names.sort();
+        assert_eq!(names, vec!["body", "name"]);
+        assert_eq!(template.slots.get("body").unwrap().kind, SlotKind::Function);
+    }
+
+    #[test]
+    fn test_render_with_map() {
+        let template = Template::new("<h1>{{AI:title}}</h1>\n<p>{{AI:body}}</p>\n<!-- {{AI:title}} -->");
+        let mut injections = HashMap::new();
+        injections.insert("title".to_string(), "Hello".to_string());
+        injections.insert("body".to_string(), "Line one\nLine two".to_string());
+
+        let (output, map) = template.render_with_map(&injections).unwrap();
+        assert_eq!(output, template.render(&injections).unwrap());
+
+        let body = map.spans_for("body").next().unwrap();
+        assert_eq!(&output[body.start..body.end], "Line one\nLine two");
+        assert_eq!(SourceMap::line_col(&output, body.start), (2, 4));
+        assert_eq!(map.slot_at(body.start), Some("body"));
+        assert_eq!(map.slot_at(0), None);
+
+        let titles: Vec<_> = map.spans_for("title").map(|s| &output[s.start..s.end]).collect();
+        assert_eq!(titles, vec!["Hello", "Hello"]);
+    }
+
+    #[test]
+    fn test_line_col_non_ascii() {
+        let output = "héllo\n日本語";
+        assert_eq!(SourceMap::line_col(output, 3), (1, 3));
+        // Offsets inside a multi-byte character map to that character
+        assert_eq!(SourceMap::line_col(output, 2), (1, 2));
+        assert_eq!(SourceMap::line_col(output, 11), (2, 2));
+        assert_eq!(SourceMap::line_col(output, 100), (2, 4));
+    }
+
+    #[test]
+    fn test_unfilled_strategy_remove_line() {
+        let mut template = Template::new("use std::io;\n    {{AI:imports}}\nfn main() { {{AI:body}} }\n{{AI:tail}}");
+        template
+            .configure("imports", |slot| slot.required = false)
+            .configure("body", |slot| slot.required = false)
+            .configure("tail", |slot| slot.required = false);
+
+        let injections = HashMap::new();
+        assert_eq!(
+            template.render(&injections).unwrap(),
+            "use std::io;\n    \nfn main() {  }\n"
+        );
+
+        let template = template.with_unfilled_strategy(UnfilledStrategy::RemoveLine);
+        let (output, map) = template.render_with_map(&injections).unwrap();
+        assert_eq!(output, "use std::io;\nfn main() {  }");
+        assert!(map.spans_for("imports").all(|s| s.start == s.end));
+
+        // Removing the last lines in succession also drops their separator
+        let mut template = Template::new("fn main() {}\n{{AI:a}}\n{{AI:b}}")
+            .with_unfilled_strategy(UnfilledStrategy::RemoveLine);
+        template
+            .configure("a", |slot| slot.required = false)
+            .configure("b", |slot| slot.required = false);
+        assert_eq!(template.render(&injections).unwrap(), "fn main() {}");
+    }
+
+    #[test]
+    fn test_slot_constraints_preserve_kind() {
+        let harness = SlotConstraints {
+            test_harness: Some("{{CODE}}\n#[test] fn t() { assert_eq!(add(1, 2), 3); }".to_string()),
+            test_command: Some("cargo test".to_string()),
+            ..SlotConstraints::default()
+        };
+        let template = Template::new("{{AI:add:function}}")
+            .with_slot("add", "Add two numbers")
+            .with_slot_constraints("add", harness.clone());
+
+        let slot = &template.slots["add"];
+        assert_eq!(slot.kind, SlotKind::Function);
+        assert_eq!(slot.prompt, "Add two numbers");
+        assert_eq!(slot.constraints, Some(harness));
+    }
+
+    #[test]
+    fn test_insert_and_remove_slot_marker() {
+        let mut template = Template::new("<main></main>\n<footer>{{AI:footer}}</footer>");
+        template.insert_slot_marker(6, "body", Some("html")).unwrap();
+
+        assert_eq!(template.content, "<main>{{AI:body:html}}</main>\n<footer>{{AI:footer}}</footer>");
+        assert_eq!(template.slots["body"].kind, SlotKind::Html);
+        assert_eq!(template.slots.len(), 2);
+
+        assert!(template.insert_slot_marker(0, "bad name", None).is_err());
+        assert!(template.insert_slot_marker(999, "late", None).is_err());
+
+        let removed = template.remove_slot("footer").unwrap();
+        assert_eq!(removed.name, "footer");
+        assert_eq!(template.content, "<main>{{AI:body:html}}</main>\n<footer></footer>");
+        assert!(!template.slots.contains_key("footer"));
+        assert!(template.remove_slot("footer").is_none());
+    }
+
+    #[test]
+    fn test_validate_slots_rejects_missing_marker() {
+        let template = Template::new("<h1>{{AI:title}}</h1>").with_slot("title", "Page title");
+        assert!(template.validate_slots().is_ok());
+
+        let template = template.with_slot("titel", "Typo of title");
+        match template.validate_slots() {
+            Err(AetherError::InvalidSlot { name, reason }) => {
+                assert_eq!(name, "titel");
+                assert!(reason.contains("{{AI:titel}}"));
+            }
+            other => panic!("Expected InvalidSlot, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_configure_closure() {
+        let mut template = Template::new("{{AI:header}} {{AI:style}}");
+        template
+            .configure("header", |slot| {
+                slot.prompt = "Create a page header".to_string();
+                slot.temperature = Some(0.2);
+            })
+            .configure("style", |slot| slot.kind = SlotKind::Css)
+            .configure("footer", |slot| {
+                slot.required = false;
+                slot.max_tokens = Some(64);
+            });
+
+        let header = &template.slots["header"];
+        assert_eq!(header.prompt, "Create a page header");
+        assert_eq!(header.temperature, Some(0.2));
+        assert_eq!(template.slots["style"].kind, SlotKind::Css);
+
+        let footer = &template.slots["footer"];
+        assert!(!footer.required);
+        assert_eq!(footer.max_tokens, Some(64));
+        assert_eq!(footer.prompt, "Generate code for: footer");
+
+        for slot in template.slots_mut() {
+            slot.model = Some("gpt-4o-mini".to_string());
+        }
+        assert!(template.slots.values().all(|s| s.model.as_deref() == Some("gpt-4o-mini")));
+    }
 }
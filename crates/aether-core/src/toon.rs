@@ -1,5 +1,46 @@
 use serde_json::{Value, Map};
 
+/// Formatting options for TOON serialization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToonOptions {
+    /// Indentation unit repeated once per nesting level (e.g. two spaces, one space, a tab).
+    pub indent: String,
+
+    /// Emit `[len]` headers on arrays. They help the model but cost tokens.
+    pub array_lengths: bool,
+}
+
+impl Default for ToonOptions {
+    fn default() -> Self {
+        Self {
+            indent: "  ".to_string(),
+            array_lengths: true,
+        }
+    }
+}
+
+impl ToonOptions {
+    /// Minimal-whitespace options: single-space indent and no array length headers.
+    pub fn compact() -> Self {
+        Self {
+            indent: " ".to_string(),
+            array_lengths: false,
+        }
+    }
+
+    /// Set the indentation unit.
+    pub fn with_indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    /// Enable or disable `[len]` array headers.
+    pub fn with_array_lengths(mut self, enabled: bool) -> Self {
+        self.array_lengths = enabled;
+        self
+    }
+}
+
 /// Token-Oriented Object Notation (TOON) Serializer.
 /// Reduces token usage by 30-60% compared to JSON.
 pub struct Toon;
@@ -7,9 +48,19 @@ pub struct Toon;
 impl Toon {
     /// Serialize a JSON value to TOON format.
     pub fn serialize(value: &Value) -> String {
+        Self::serialize_with(value, &ToonOptions::default())
+    }
+
+    /// Serialize a JSON value to TOON format with minimal whitespace.
+    pub fn serialize_compact(value: &Value) -> String {
+        Self::serialize_with(value, &ToonOptions::compact())
+    }
+
+    /// Serialize a JSON value to TOON format with custom options.
+    pub fn serialize_with(value: &Value, options: &ToonOptions) -> String {
         match value {
-            Value::Object(map) => Self::serialize_object(map, 0),
-            Value::Array(arr) => Self::serialize_array(arr, 0),
+            Value::Object(map) => Self::serialize_object(map, 0, options),
+            Value::Array(arr) => Self::serialize_array(arr, 0, options),
             Value::String(s) => s.clone(),
             Value::Number(n) => n.to_string(),
             Value::Bool(b) => if *b { "T" } else { "F" }.to_string(),
@@ -17,27 +68,84 @@ impl Toon {
         }
     }
 
-    fn serialize_object(map: &Map<String, Value>, indent: usize) -> String {
+    /// Serialize only large top-level fields to TOON.
+    ///
+    /// Fields whose plain rendering is shorter than `threshold` characters
+    /// stay as readable `key: value` lines (JSON for small arrays/objects);
+    /// larger fields are TOON-serialized. Null and empty fields are omitted.
+    /// Non-object values are serialized as full TOON.
+    pub fn serialize_partial(value: &Value, threshold: usize, options: &ToonOptions) -> String {
+        let Value::Object(map) = value else {
+            return Self::serialize_with(value, options);
+        };
+
+        let mut out = String::new();
+        for (k, v) in Self::sorted_entries(map) {
+            let plain = match v {
+                Value::Null => continue,
+                Value::Array(arr) if arr.is_empty() => continue,
+                Value::Object(obj) if obj.is_empty() => continue,
+                Value::String(s) => s.clone(),
+                other => Self::canonical(other).to_string(),
+            };
+
+            if plain.len() < threshold {
+                out.push_str(&format!("{}: {}\n", k, plain));
+            } else {
+                let field = Map::from_iter([(k.clone(), v.clone())]);
+                out.push_str(&Self::serialize_object(&field, 0, options));
+            }
+        }
+        out
+    }
+
+    /// Object entries sorted by key.
+    ///
+    /// `serde_json::Map` keeps insertion order when the `preserve_order`
+    /// feature is enabled anywhere in the build, so iterate in an explicit
+    /// order to keep TOON output (and cache keys hashed from it) stable.
+    fn sorted_entries(map: &Map<String, Value>) -> Vec<(&String, &Value)> {
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    /// Copy of `value` with all object keys in sorted order.
+    fn canonical(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                Self::sorted_entries(map)
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), Self::canonical(v)))
+                    .collect(),
+            ),
+            Value::Array(arr) => Value::Array(arr.iter().map(Self::canonical).collect()),
+            other => other.clone(),
+        }
+    }
+
+    fn serialize_object(map: &Map<String, Value>, indent: usize, options: &ToonOptions) -> String {
         let mut out = String::new();
-        let pad = "  ".repeat(indent);
+        let pad = options.indent.repeat(indent);
         
-        for (k, v) in map {
+        for (k, v) in Self::sorted_entries(map) {
             match v {
                 Value::Object(child_map) => {
-                    out.push_str(&format!("{}{}:\n{}", pad, k, Self::serialize_object(child_map, indent + 1)));
+                    out.push_str(&format!("{}{}:\n{}", pad, k, Self::serialize_object(child_map, indent + 1, options)));
                 }
                 Value::Array(arr) => {
-                    out.push_str(&format!("{}{}[{}]:\n{}", pad, k, arr.len(), Self::serialize_array(arr, indent + 1)));
+                    let len = if options.array_lengths { format!("[{}]", arr.len()) } else { String::new() };
+                    out.push_str(&format!("{}{}{}:\n{}", pad, k, len, Self::serialize_array(arr, indent + 1, options)));
                 }
                 _ => {
-                    out.push_str(&format!("{}{}: {}\n", pad, k, Self::serialize(v)));
+                    out.push_str(&format!("{}{}: {}\n", pad, k, Self::serialize_with(v, options)));
                 }
             }
         }
         out
     }
 
-    fn serialize_array(arr: &[Value], indent: usize) -> String {
+    fn serialize_array(arr: &[Value], indent: usize, options: &ToonOptions) -> String {
         if arr.is_empty() {
             return "[]".to_string();
         }
@@ -45,8 +153,9 @@ impl Toon {
         // Check if it's a homogeneous list of objects to use tabular TOON format
         if let Some(first) = arr.first() {
             if let Value::Object(first_map) = first {
-                let keys: Vec<String> = first_map.keys().cloned().collect();
-                let pad = "  ".repeat(indent);
+                let mut keys: Vec<String> = first_map.keys().cloned().collect();
+                keys.sort();
+                let pad = options.indent.repeat(indent);
                 let mut out = format!("{}{{{}}}:\n", pad, keys.join(","));
 
                 for item in arr {
@@ -63,9 +172,9 @@ impl Toon {
 
         // Fallback for simple arrays
         let mut out = String::new();
-        let pad = "  ".repeat(indent);
+        let pad = options.indent.repeat(indent);
         for v in arr {
-            out.push_str(&format!("{}- {}\n", pad, Self::serialize(v).trim()));
+            out.push_str(&format!("{}- {}\n", pad, Self::serialize_with(v, options).trim()));
         }
         out
     }
@@ -267,6 +376,46 @@ mod tests {
         assert!(toon.contains("meta:"));
     }
 
+    #[test]
+    fn test_toon_key_order_is_stable() {
+        let a = json!({"zeta": 1, "alpha": {"y": true, "b": "x"}, "rows": [{"n": 1, "id": 2}]});
+        let mut b = Map::new();
+        b.insert("rows".to_string(), json!([{"id": 2, "n": 1}]));
+        b.insert("alpha".to_string(), json!({"b": "x", "y": true}));
+        b.insert("zeta".to_string(), json!(1));
+        let b = Value::Object(b);
+
+        assert_eq!(Toon::serialize(&a), Toon::serialize(&a));
+        assert_eq!(Toon::serialize(&a), Toon::serialize(&b));
+        assert!(Toon::serialize(&a).starts_with("alpha:\n  b: x\n  y: T\nrows"));
+        assert_eq!(
+            Toon::serialize_partial(&a, 1000, &ToonOptions::default()),
+            Toon::serialize_partial(&b, 1000, &ToonOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_toon_partial() {
+        let data = json!({
+            "language": "rust",
+            "strict": true,
+            "framework": null,
+            "modules": (0..20).map(|i| json!({"name": format!("mod_{}", i), "lines": i * 10})).collect::<Vec<_>>()
+        });
+        let toon = Toon::serialize_partial(&data, 200, &ToonOptions::default());
+
+        // Small scalars stay plain, nulls are dropped
+        assert!(toon.contains("language: rust\n"));
+        assert!(toon.contains("strict: true\n"));
+        assert!(!toon.contains("framework"));
+
+        // The large array is TOON-compressed into tabular form
+        assert!(toon.contains("modules[20]:"));
+        assert!(toon.contains("{lines,name}:"));
+        assert!(toon.contains("30,mod_3"));
+        assert!(!toon.contains("\"mod_3\""));
+    }
+
     #[test]
     fn test_toon_roundtrip() {
         let original = json!({
@@ -293,4 +442,21 @@ mod tests {
         assert_eq!(deserialized["tags"].as_array().unwrap().len(), 3);
         assert_eq!(deserialized["files"].as_array().unwrap().len(), 2);
     }
+
+    #[test]
+    fn test_toon_compact_is_smaller() {
+        let data = json!({
+            "project": "Aether",
+            "config": { "tags": ["ai", "rust"], "nested": { "depth": 2 } }
+        });
+
+        let default = Toon::serialize(&data);
+        let compact = Toon::serialize_compact(&data);
+        assert!(compact.len() < default.len());
+        assert!(!compact.contains("[2]"));
+
+        let tabbed = Toon::serialize_with(&data, &ToonOptions::default().with_indent("\t"));
+        assert!(tabbed.contains("\n\tnested:"));
+        assert_eq!(Toon::deserialize(&compact).unwrap()["project"], "Aether");
+    }
 }
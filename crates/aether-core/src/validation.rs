@@ -1,15 +1,157 @@
 use crate::{Result, SlotKind};
+use std::collections::HashMap;
+use std::fmt;
 use std::process::Command;
+use std::sync::Arc;
 use std::io::Write;
 use tempfile::NamedTempFile;
+use tracing::warn;
+
+/// Error for a tool that could not be started: `ToolchainMissing` if it is
+/// not installed, `InjectionError` otherwise.
+fn spawn_error(tool: &str, e: std::io::Error) -> crate::AetherError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        crate::AetherError::ToolchainMissing { tool: tool.to_string() }
+    } else {
+        crate::AetherError::InjectionError(e.to_string())
+    }
+}
+
+/// Severity of a validation diagnostic. Ordered from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A single problem reported by a validator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Human-readable description of the problem.
+    pub message: String,
+    /// 1-based line in the generated code, when known.
+    pub line: Option<usize>,
+    /// How serious the problem is.
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self { message: message.into(), line: None, severity: Severity::Error }
+    }
+
+    /// Create a warning diagnostic.
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self { message: message.into(), line: None, severity: Severity::Warning }
+    }
+
+    /// Attach a line number.
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    /// Split rustc output into one diagnostic per `error`/`warning` header.
+    ///
+    /// The trailing "aborting due to" and "N warnings emitted" summaries are
+    /// dropped. If nothing can be parsed, the whole output becomes one error.
+    pub fn parse_rustc(output: &str) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+        for line in output.lines() {
+            let (severity, rest) = if let Some(rest) = line.strip_prefix("error") {
+                (Severity::Error, rest)
+            } else if let Some(rest) = line.strip_prefix("warning") {
+                (Severity::Warning, rest)
+            } else {
+                if let (Some(last), Some(location)) = (diagnostics.last_mut(), line.trim_start().strip_prefix("--> ")) {
+                    if last.line.is_none() {
+                        last.line = location.rsplit(':').nth(1).and_then(|l| l.parse().ok());
+                    }
+                }
+                continue;
+            };
+
+            // Headers look like `error[E0425]: ...` or `warning: ...`
+            let Some((code, message)) = rest.split_once(": ") else { continue };
+            if !(code.is_empty() || code.starts_with('[')) {
+                continue;
+            }
+            if message.starts_with("aborting due to") || message.ends_with("emitted") {
+                continue;
+            }
+
+            let message = if code.is_empty() { message.to_string() } else { format!("{} {}", code, message) };
+            diagnostics.push(Diagnostic { message, line: None, severity });
+        }
+
+        if diagnostics.is_empty() && !output.trim().is_empty() {
+            diagnostics.push(Diagnostic::error(output.trim()));
+        }
+        diagnostics
+    }
+
+    /// Render the most important diagnostics, errors before warnings, keeping
+    /// at most `max` and noting how many were left out.
+    pub fn summarize(diagnostics: &[Diagnostic], max: usize) -> String {
+        let mut sorted: Vec<&Diagnostic> = diagnostics.iter().collect();
+        sorted.sort_by_key(|d| d.severity);
+
+        let mut lines: Vec<String> = sorted.iter().take(max).map(|d| d.to_string()).collect();
+        if sorted.len() > max {
+            lines.push(format!("... and {} more", sorted.len() - max));
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {}): {}", self.severity, line, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
 
 /// Result of a code validation check.
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValidationResult {
     /// Validation passed.
     Valid,
-    /// Validation failed with a specific error message.
-    Invalid(String),
+    /// Validation failed with one or more diagnostics.
+    Invalid(Vec<Diagnostic>),
+}
+
+impl ValidationResult {
+    /// Shorthand for a failure with a single error message.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        ValidationResult::Invalid(vec![Diagnostic::error(message)])
+    }
+}
+
+impl fmt::Display for ValidationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationResult::Valid => write!(f, "valid"),
+            ValidationResult::Invalid(diagnostics) => {
+                let lines: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
 }
 
 /// Trait for implementing code validators and formatters.
@@ -65,14 +207,21 @@ impl Validator for RustValidator {
                     .arg(&out_file)
                     .arg(tmp_file.path())
                     .output()
-                    .map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
+                    .map_err(|e| spawn_error("rustc", e))?;
                 
                 // Clean up output file
                 let _ = std::fs::remove_file(&out_file);
 
                 if !output.status.success() {
                     let err = String::from_utf8_lossy(&output.stderr).to_string();
-                    return Ok(ValidationResult::Invalid(format!("Rust Compilation Error:\n{}", err)));
+                    let mut diagnostics = Diagnostic::parse_rustc(&err);
+                    if !has_tests {
+                        // Report lines relative to the generated code, not the wrapper module
+                        for d in &mut diagnostics {
+                            d.line = d.line.map(|l| l.saturating_sub(2).max(1));
+                        }
+                    }
+                    return Ok(ValidationResult::Invalid(diagnostics));
                 }
 
                 // Run tests if present
@@ -86,11 +235,11 @@ impl Validator for RustValidator {
                         .arg(test_exe.path())
                         .arg(tmp_file.path())
                         .output()
-                        .map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
+                        .map_err(|e| spawn_error("rustc", e))?;
 
                     if !test_compile.status.success() {
                         let err = String::from_utf8_lossy(&test_compile.stderr).to_string();
-                        return Ok(ValidationResult::Invalid(format!("Test Compilation Error:\n{}", err)));
+                        return Ok(ValidationResult::Invalid(Diagnostic::parse_rustc(&err)));
                     }
 
                     let test_run = Command::new(test_exe.path())
@@ -100,7 +249,7 @@ impl Validator for RustValidator {
                     if !test_run.status.success() {
                         let err = String::from_utf8_lossy(&test_run.stdout).to_string();
                         let stderr = String::from_utf8_lossy(&test_run.stderr).to_string();
-                        return Ok(ValidationResult::Invalid(format!("Unit Test Failed:\n{}\n{}", err, stderr)));
+                        return Ok(ValidationResult::invalid(format!("Unit Test Failed:\n{}\n{}", err, stderr)));
                     }
                 }
 
@@ -160,11 +309,11 @@ impl Validator for JsValidator {
                     .arg("--check")
                     .arg(tmp_file.path())
                     .output()
-                    .map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
+                    .map_err(|e| spawn_error("node", e))?;
 
                 if !output.status.success() {
                     let err = String::from_utf8_lossy(&output.stderr).to_string();
-                    return Ok(ValidationResult::Invalid(format!("JavaScript Syntax Error:\n{}", err)));
+                    return Ok(ValidationResult::invalid(format!("JavaScript Syntax Error:\n{}", err)));
                 }
 
                 Ok(ValidationResult::Valid)
@@ -226,11 +375,11 @@ impl Validator for PythonValidator {
                     .arg("py_compile")
                     .arg(tmp_file.path())
                     .output()
-                    .map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
+                    .map_err(|e| spawn_error("python", e))?;
 
                 if !output.status.success() {
                     let err = String::from_utf8_lossy(&output.stderr).to_string();
-                    return Ok(ValidationResult::Invalid(format!("Python Syntax Error:\n{}", err)));
+                    return Ok(ValidationResult::invalid(format!("Python Syntax Error:\n{}", err)));
                 }
 
                 // Optional: Run ruff for linting
@@ -245,7 +394,7 @@ impl Validator for PythonValidator {
                         let warnings = String::from_utf8_lossy(&out.stdout).to_string();
                         if !warnings.is_empty() {
                             // Return as invalid with lint warnings
-                            return Ok(ValidationResult::Invalid(format!("Python Lint Issues:\n{}", warnings)));
+                            return Ok(ValidationResult::invalid(format!("Python Lint Issues:\n{}", warnings)));
                         }
                     }
                 }
@@ -366,13 +515,19 @@ impl Validator for TddValidator {
             .arg(arg)
             .arg(&command_str)
             .output()
-            .map_err(|e| crate::AetherError::InjectionError(e.to_string()))?;
+            .map_err(|e| spawn_error(shell, e))?;
+
+        // POSIX shells exit with 127 when the command itself was not found
+        if cfg!(not(windows)) && output.status.code() == Some(127) {
+            let tool = command_str.split_whitespace().next().unwrap_or(shell);
+            return Err(crate::AetherError::ToolchainMissing { tool: tool.to_string() });
+        }
 
         if !output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout).to_string();
             let stderr = String::from_utf8_lossy(&output.stderr).to_string();
             
-            return Ok(ValidationResult::Invalid(format!(
+            return Ok(ValidationResult::invalid(format!(
                 "TDD Test Failure:\nSTDOUT:\n{}\nSTDERR:\n{}",
                 stdout, stderr
             )));
@@ -386,6 +541,299 @@ impl Validator for TddValidator {
     }
 }
 
+// ============================================================
+// CallbackValidator - Wraps a user-supplied function
+// ============================================================
+
+/// Validation callback: returns `Ok(())` for valid code or the error message.
+pub type ValidatorFn = dyn Fn(&SlotKind, &str) -> std::result::Result<(), String> + Send + Sync;
+
+/// A validator backed by a callback, e.g. a project-specific linter
+/// registered from a host language through the bindings.
+pub struct CallbackValidator {
+    callback: Box<ValidatorFn>,
+}
+
+impl CallbackValidator {
+    pub fn new(
+        callback: impl Fn(&SlotKind, &str) -> std::result::Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            callback: Box::new(callback),
+        }
+    }
+}
+
+impl Validator for CallbackValidator {
+    fn validate(&self, kind: &SlotKind, code: &str) -> Result<ValidationResult> {
+        match (self.callback)(kind, code) {
+            Ok(()) => Ok(ValidationResult::Valid),
+            Err(e) => Ok(ValidationResult::invalid(e)),
+        }
+    }
+
+    fn format(&self, _kind: &SlotKind, code: &str) -> Result<String> {
+        Ok(code.to_string())
+    }
+}
+
+// ============================================================
+// NoMarkdownValidator - Rejects leftover fences and prose
+// ============================================================
+
+/// Openings of a first line that mark chatty prose rather than code
+/// (case-insensitive).
+const PROSE_MARKERS: &[&str] = &[
+    "here is ",
+    "here's ",
+    "sure,",
+    "sure!",
+    "certainly",
+    "i've ",
+    "i have ",
+    "this code ",
+    "explanation:",
+    "note:",
+];
+
+/// Fails if output still contains markdown fences or opens with obvious
+/// prose, so healing asks for raw code. Only the first non-empty line is
+/// checked for prose, since later lines may be docstrings, block comments
+/// or multi-line strings that legitimately read like sentences. Useful in strict pipelines where
+/// `fence::strip_code_fences` only removes fences wrapping the whole response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMarkdownValidator;
+
+impl Validator for NoMarkdownValidator {
+    fn validate(&self, _kind: &SlotKind, code: &str) -> Result<ValidationResult> {
+        let mut diagnostics = Vec::new();
+        let first_line = code.lines().position(|line| !line.trim().is_empty());
+        for (i, line) in code.lines().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                diagnostics.push(Diagnostic::error("Markdown code fence in output").with_line(i + 1));
+            } else if Some(i) == first_line {
+                let lower = trimmed.to_lowercase();
+                if let Some(marker) = PROSE_MARKERS.iter().find(|m| lower.starts_with(*m)) {
+                    diagnostics.push(
+                        Diagnostic::error(format!("Prose instead of code (starts with '{}')", marker.trim_end()))
+                            .with_line(i + 1),
+                    );
+                }
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(ValidationResult::Valid)
+        } else {
+            Ok(ValidationResult::Invalid(diagnostics))
+        }
+    }
+
+    fn format(&self, _kind: &SlotKind, code: &str) -> Result<String> {
+        Ok(code.to_string())
+    }
+}
+
+// ============================================================
+// JsonSchemaValidator - JSON output against SlotConstraints::json_schema
+// ============================================================
+
+/// Parses output as JSON and checks it against the slot's
+/// `SlotConstraints::json_schema`. Slots without a schema only need to parse.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSchemaValidator;
+
+impl Validator for JsonSchemaValidator {
+    fn validate(&self, kind: &SlotKind, code: &str) -> Result<ValidationResult> {
+        self.validate_with_slot(&crate::Slot::new("unknown", "").with_kind(kind.clone()), code)
+    }
+
+    fn validate_with_slot(&self, slot: &crate::Slot, code: &str) -> Result<ValidationResult> {
+        let instance = match serde_json::from_str::<serde_json::Value>(code) {
+            Ok(value) => value,
+            Err(e) => {
+                return Ok(ValidationResult::Invalid(vec![
+                    Diagnostic::error(format!("Invalid JSON: {}", e)).with_line(e.line()),
+                ]))
+            }
+        };
+
+        let schema = match slot.constraints.as_ref().and_then(|c| c.json_schema.as_deref()) {
+            Some(schema) => schema,
+            None => return Ok(ValidationResult::Valid),
+        };
+        // A broken schema is a configuration problem, not something healing can fix
+        let schema: serde_json::Value = serde_json::from_str(schema).map_err(|e| {
+            crate::AetherError::ConfigError(format!("Invalid JSON Schema for slot '{}': {}", slot.name, e))
+        })?;
+        let validator = jsonschema::validator_for(&schema).map_err(|e| {
+            crate::AetherError::ConfigError(format!("Invalid JSON Schema for slot '{}': {}", slot.name, e))
+        })?;
+
+        let diagnostics: Vec<Diagnostic> = validator
+            .iter_errors(&instance)
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                let path = if path.is_empty() { "/" } else { path.as_str() };
+                Diagnostic::error(format!("Schema violation at {}: {}", path, e))
+            })
+            .collect();
+
+        if diagnostics.is_empty() {
+            Ok(ValidationResult::Valid)
+        } else {
+            Ok(ValidationResult::Invalid(diagnostics))
+        }
+    }
+
+    fn format(&self, _kind: &SlotKind, code: &str) -> Result<String> {
+        Ok(code.to_string())
+    }
+}
+
+// ============================================================
+// ImportValidator - Checks imports against an allow-list
+// ============================================================
+
+/// Flags imports that are not covered by an allow-list, typically
+/// `InjectionContext::available_imports`.
+///
+/// An import is covered when its path is an allowed entry or continues it
+/// with a `::`, `.` or `/` separator, so `std` allows `use std::io;` but
+/// not `use stdx;`, and `serde` does not allow `serde_json`. By default unlisted imports are only logged;
+/// with `strict(true)` they fail validation and trigger healing.
+#[derive(Debug, Clone, Default)]
+pub struct ImportValidator {
+    available: Vec<String>,
+    strict: bool,
+}
+
+impl ImportValidator {
+    pub fn new(available: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            available: available.into_iter().map(Into::into).collect(),
+            strict: false,
+        }
+    }
+
+    /// Fail validation on unlisted imports instead of logging a warning.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether `allowed` covers `path` on whole path segments.
+    fn covers(allowed: &str, path: &str) -> bool {
+        path.strip_prefix(allowed).is_some_and(|rest| {
+            rest.is_empty() || ["::", ".", "/"].iter().any(|sep| rest.starts_with(sep))
+        })
+    }
+
+    /// Import paths found in `code` with their 1-based line numbers.
+    ///
+    /// Recognizes Rust `use`, Python `import`/`from`, JS/TS `import`/`require`
+    /// and C `#include`.
+    fn imports(code: &str) -> Vec<(usize, String)> {
+        let quoted = |s: &str| {
+            let start = s.find(['\'', '"', '<'])? + 1;
+            let end = start + s[start..].find(['\'', '"', '>'])?;
+            Some(s[start..end].to_string())
+        };
+
+        code.lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let line = line.trim();
+                let path = if let Some(rest) = line.strip_prefix("use ") {
+                    Some(rest.trim_end_matches(';').to_string())
+                } else if line.starts_with("#include") || line.contains("require(") {
+                    quoted(line)
+                } else if let Some(rest) = line.strip_prefix("from ") {
+                    rest.split_whitespace().next().map(str::to_string)
+                } else if let Some(rest) = line.strip_prefix("import ") {
+                    quoted(rest).or_else(|| rest.split([' ', ',', ';']).next().map(str::to_string))
+                } else {
+                    None
+                };
+                path.filter(|p| !p.is_empty()).map(|p| (i + 1, p))
+            })
+            .collect()
+    }
+}
+
+impl Validator for ImportValidator {
+    fn validate(&self, _kind: &SlotKind, code: &str) -> Result<ValidationResult> {
+        let diagnostics: Vec<Diagnostic> = Self::imports(code)
+            .into_iter()
+            .filter(|(_, path)| !self.available.iter().any(|a| Self::covers(a, path)))
+            .map(|(line, path)| Diagnostic::warning(format!("Import '{}' is not in available imports", path)).with_line(line))
+            .collect();
+
+        if diagnostics.is_empty() {
+            return Ok(ValidationResult::Valid);
+        }
+        if self.strict {
+            return Ok(ValidationResult::Invalid(diagnostics));
+        }
+        for diagnostic in &diagnostics {
+            warn!("{}", diagnostic);
+        }
+        Ok(ValidationResult::Valid)
+    }
+
+    fn format(&self, _kind: &SlotKind, code: &str) -> Result<String> {
+        Ok(code.to_string())
+    }
+}
+
+// ============================================================
+// ValidatorChain - Runs several validators in order
+// ============================================================
+
+/// Runs validators in order; the first failure is reported.
+///
+/// Lets extra checks such as [`NoMarkdownValidator`] be registered
+/// alongside the language validators of [`MultiValidator`].
+#[derive(Default)]
+pub struct ValidatorChain {
+    validators: Vec<Arc<dyn Validator>>,
+}
+
+impl ValidatorChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a validator to the chain.
+    pub fn with(mut self, validator: impl Validator + 'static) -> Self {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+}
+
+impl Validator for ValidatorChain {
+    fn validate(&self, kind: &SlotKind, code: &str) -> Result<ValidationResult> {
+        self.validate_with_slot(&crate::Slot::new("unknown", "").with_kind(kind.clone()), code)
+    }
+
+    fn validate_with_slot(&self, slot: &crate::Slot, code: &str) -> Result<ValidationResult> {
+        for validator in &self.validators {
+            let result = validator.validate_with_slot(slot, code)?;
+            if let ValidationResult::Invalid(_) = result {
+                return Ok(result);
+            }
+        }
+        Ok(ValidationResult::Valid)
+    }
+
+    fn format(&self, kind: &SlotKind, code: &str) -> Result<String> {
+        self.validators
+            .iter()
+            .try_fold(code.to_string(), |code, validator| validator.format(kind, &code))
+    }
+}
+
 // ============================================================
 // MultiValidator - Auto-selects based on SlotKind
 // ============================================================
@@ -396,6 +844,9 @@ pub struct MultiValidator {
     js: JsValidator,
     python: PythonValidator,
     tdd: TddValidator,
+    json: JsonSchemaValidator,
+    /// Per-kind validators that replace the built-in selection.
+    overrides: HashMap<SlotKind, Arc<dyn Validator>>,
 }
 
 impl Default for MultiValidator {
@@ -411,8 +862,30 @@ impl MultiValidator {
             js: JsValidator,
             python: PythonValidator,
             tdd: TddValidator,
+            json: JsonSchemaValidator,
+            overrides: HashMap::new(),
         }
     }
+
+    /// Use `validator` instead of the built-in checks for slots of `kind`.
+    pub fn with_kind_validator(mut self, kind: SlotKind, validator: impl Validator + 'static) -> Self {
+        self.overrides.insert(kind, Arc::new(validator));
+        self
+    }
+
+    /// Use a callback instead of the built-in checks for each listed kind.
+    ///
+    /// Each callback receives the generated code and returns the error
+    /// message to reject it. The bindings register host-language validators
+    /// through this.
+    pub fn with_kind_callbacks<F>(self, callbacks: impl IntoIterator<Item = (SlotKind, F)>) -> Self
+    where
+        F: Fn(&str) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        callbacks.into_iter().fold(self, |validator, (kind, callback)| {
+            validator.with_kind_validator(kind, CallbackValidator::new(move |_, code| callback(code)))
+        })
+    }
 }
 
 impl Validator for MultiValidator {
@@ -423,12 +896,17 @@ impl Validator for MultiValidator {
 
     fn validate_with_slot(&self, slot: &crate::Slot, code: &str) -> Result<ValidationResult> {
         let kind = &slot.kind;
+
+        if let Some(validator) = self.overrides.get(kind) {
+            return validator.validate_with_slot(slot, code);
+        }
         
         // 1. Run language-specific validation first
         let base_result = match kind {
             SlotKind::JavaScript => self.js.validate(kind, code)?,
             SlotKind::Html | SlotKind::Css => ValidationResult::Valid,
             SlotKind::Raw => ValidationResult::Valid,
+            SlotKind::Json => self.json.validate_with_slot(slot, code)?,
             _ => {
                 if code.contains("def ") || code.contains("import ") && code.contains(":") {
                     self.python.validate(kind, code)?
@@ -455,9 +933,13 @@ impl Validator for MultiValidator {
     }
 
     fn format(&self, kind: &SlotKind, code: &str) -> Result<String> {
+        if let Some(validator) = self.overrides.get(kind) {
+            return validator.format(kind, code);
+        }
+
         match kind {
             SlotKind::JavaScript => self.js.format(kind, code),
-            SlotKind::Html | SlotKind::Css | SlotKind::Raw => Ok(code.to_string()),
+            SlotKind::Html | SlotKind::Css | SlotKind::Raw | SlotKind::Json => Ok(code.to_string()),
             _ => {
                 if code.contains("def ") || code.contains("import ") && code.contains(":") {
                     self.python.format(kind, code)
@@ -483,6 +965,118 @@ mod tests {
         assert_eq!(result, ValidationResult::Valid);
     }
 
+    #[test]
+    fn test_extension_kinds_accept_valid_files() {
+        let validator = MultiValidator::new();
+        let files = [
+            ("rs", "fn add(a: i32, b: i32) -> i32 { a + b }"),
+            ("mjs", "const add = (a, b) => a + b;"),
+            ("html", "<p>hi</p>"),
+            ("css", "p { color: red; }"),
+            ("json", "{\"a\": 1}"),
+            // No validator for these, so they must not pick up a checked kind
+            ("ts", "const add = (a: number, b: number): number => a + b;"),
+            ("py", "import os\nprint(os.getcwd())"),
+            ("go", "package main\nfunc main() {}"),
+        ];
+        for (ext, code) in files {
+            let kind = SlotKind::from_extension(ext).unwrap_or_default();
+            match validator.validate(&kind, code) {
+                Ok(ValidationResult::Valid) | Err(crate::AetherError::ToolchainMissing { .. }) => {}
+                other => panic!("valid .{} file rejected as {:?}: {:?}", ext, kind, other),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_missing_test_tool_is_toolchain_missing() {
+        let slot = crate::Slot::new("add", "Add two numbers").with_constraints(
+            crate::SlotConstraints::new()
+                .test_harness("{{CODE}}")
+                .test_command("aether-no-such-tool {{FILE}}"),
+        );
+
+        match TddValidator.validate_with_slot(&slot, "fn add(a: i32, b: i32) -> i32 { a + b }") {
+            Err(crate::AetherError::ToolchainMissing { tool }) => assert_eq!(tool, "aether-no-such-tool"),
+            other => panic!("Expected ToolchainMissing, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_no_markdown_validator() {
+        let validator = NoMarkdownValidator;
+        let fenced = "fn a() {}\n```\nfn b() {}";
+        match validator.validate(&SlotKind::Function, fenced).unwrap() {
+            ValidationResult::Invalid(diagnostics) => assert_eq!(diagnostics[0].line, Some(2)),
+            ValidationResult::Valid => panic!("internal fence should be rejected"),
+        }
+
+        let prose = "Here is the function:\nfn a() {}";
+        assert!(matches!(validator.validate(&SlotKind::Function, prose).unwrap(), ValidationResult::Invalid(_)));
+
+        let clean = "// Note the early return\nfn a() -> i32 {\n    42\n}";
+        assert_eq!(validator.validate(&SlotKind::Function, clean).unwrap(), ValidationResult::Valid);
+
+        // Sentences inside docstrings and block comments are not prose
+        let docstring = "def a():\n    \"\"\"\n    Note: returns 42.\n    \"\"\"\n    return 42";
+        assert_eq!(validator.validate(&SlotKind::Raw, docstring).unwrap(), ValidationResult::Valid);
+        let block = "/*\nThis code is generated.\n*/\nfn a() {}";
+        assert_eq!(validator.validate(&SlotKind::Function, block).unwrap(), ValidationResult::Valid);
+
+        let chain = ValidatorChain::new()
+            .with(CallbackValidator::new(|_, _| Ok(())))
+            .with(NoMarkdownValidator);
+        assert!(matches!(chain.validate(&SlotKind::Raw, fenced).unwrap(), ValidationResult::Invalid(_)));
+    }
+
+    #[test]
+    fn test_json_schema_validator() {
+        let schema = r#"{"type": "object", "required": ["name", "port"], "properties": {"port": {"type": "integer"}}}"#;
+        let slot = crate::Slot::new("config", "Server config")
+            .with_kind(SlotKind::Json)
+            .with_constraints(crate::SlotConstraints::new().json_schema(schema));
+        let validator = MultiValidator::new();
+
+        match validator.validate_with_slot(&slot, r#"{"name": "api"}"#).unwrap() {
+            ValidationResult::Invalid(diagnostics) => assert!(diagnostics[0].message.contains("port")),
+            ValidationResult::Valid => panic!("missing required property should fail"),
+        }
+        assert_eq!(
+            validator.validate_with_slot(&slot, r#"{"name": "api", "port": 8080}"#).unwrap(),
+            ValidationResult::Valid
+        );
+    }
+
+    #[test]
+    fn test_import_validator() {
+        let code = "use std::io;\nuse serde_json::Value;\nimport { z } from 'zod';\nfrom os.path import join";
+        let lenient = ImportValidator::new(["std", "zod", "os"]);
+        assert_eq!(lenient.validate(&SlotKind::Raw, code).unwrap(), ValidationResult::Valid);
+
+        match lenient.strict(true).validate(&SlotKind::Raw, code).unwrap() {
+            ValidationResult::Invalid(diagnostics) => {
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].line, Some(2));
+                assert!(diagnostics[0].message.contains("serde_json::Value"));
+            }
+            ValidationResult::Valid => panic!("unlisted import should fail in strict mode"),
+        }
+
+        // Entries match whole path segments, not string prefixes
+        let strict = ImportValidator::new(["std", "serde", "lodash"]).strict(true);
+        for covered in ["use std;", "use std::io;", "import lodash.merge", "import x from 'lodash/merge';"] {
+            assert_eq!(strict.validate(&SlotKind::Raw, covered).unwrap(), ValidationResult::Valid, "{}", covered);
+        }
+        for uncovered in ["use stdx::io;", "use serde_json::Value;", "import lodashy"] {
+            assert!(
+                matches!(strict.validate(&SlotKind::Raw, uncovered).unwrap(), ValidationResult::Invalid(_)),
+                "{}",
+                uncovered
+            );
+        }
+    }
+
     #[test]
     fn test_multi_validator_detects_python() {
         let validator = MultiValidator::new();
@@ -492,6 +1086,78 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_kind_override_uses_callback() {
+        let validator = MultiValidator::new().with_kind_validator(
+            SlotKind::Html,
+            CallbackValidator::new(|_, code| {
+                if code.contains("<marquee>") {
+                    Err("<marquee> is banned".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+        );
+
+        let result = validator.validate(&SlotKind::Html, "<marquee>hi</marquee>").unwrap();
+        assert_eq!(result, ValidationResult::invalid("<marquee> is banned"));
+        let result = validator.validate(&SlotKind::Html, "<p>hi</p>").unwrap();
+        assert_eq!(result, ValidationResult::Valid);
+    }
+
+    #[test]
+    fn test_kind_callbacks_only_replace_listed_kinds() {
+        let reject_all = |_: &str| Err("rejected".to_string());
+        let validator = MultiValidator::new()
+            .with_kind_callbacks([(SlotKind::Html, reject_all), (SlotKind::Css, reject_all)]);
+
+        let result = validator.validate(&SlotKind::Html, "<p>hi</p>").unwrap();
+        assert_eq!(result, ValidationResult::invalid("rejected"));
+        let result = validator.validate(&SlotKind::Css, "p { color: red; }").unwrap();
+        assert_eq!(result, ValidationResult::invalid("rejected"));
+        let result = validator.validate(&SlotKind::Raw, "anything").unwrap();
+        assert_eq!(result, ValidationResult::Valid);
+    }
+
+    #[test]
+    fn test_parse_rustc_multiple_errors() {
+        let output = "\
+warning: unused variable: `y`
+ --> /tmp/check.rs:2:9
+  |
+2 |     let y = 1;
+  |         ^ help: if this is intentional, prefix it with an underscore: `_y`
+
+error[E0425]: cannot find value `x` in this scope
+ --> /tmp/check.rs:3:5
+  |
+3 |     x + 1
+  |     ^ not found in this scope
+
+error[E0308]: mismatched types
+ --> /tmp/check.rs:7:5
+  |
+7 |     \"hello\"
+  |     ^^^^^^^ expected `i32`, found `&str`
+
+error: aborting due to 2 previous errors; 1 warning emitted
+";
+        let diagnostics = Diagnostic::parse_rustc(output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[1], Diagnostic::error("[E0425] cannot find value `x` in this scope").with_line(3));
+        assert_eq!(diagnostics[2].line, Some(7));
+
+        // Errors are listed before warnings and the rest is elided
+        let summary = Diagnostic::summarize(&diagnostics, 2);
+        assert_eq!(
+            summary,
+            "error (line 3): [E0425] cannot find value `x` in this scope\n\
+             error (line 7): [E0308] mismatched types\n\
+             ... and 1 more"
+        );
+    }
+
     #[test]
     fn test_multi_validator_detects_js() {
         let validator = MultiValidator::new();
@@ -21,6 +21,19 @@ typedef struct AetherProvider AetherProvider;
  */
 typedef struct AetherTemplate AetherTemplate;
 
+/**
+ * Callback type for custom validators.
+ *
+ * # Arguments
+ * * `code` - The generated code (null-terminated C string)
+ * * `user_data` - User-provided context pointer
+ *
+ * # Returns
+ * NULL if the code is valid, otherwise an error message. The message is
+ * copied immediately, so it only needs to stay valid until the callback returns.
+ */
+typedef const char *(*AetherValidateCallback)(const char *code, void *user_data);
+
 /**
  * Callback type for streaming chunks.
  *
@@ -145,6 +158,38 @@ void aether_engine_set_toon(struct AetherEngine *engine, bool enabled);
  */
 void aether_engine_set_max_retries(struct AetherEngine *engine, uint32_t max_retries);
 
+/**
+ * Check that the engine's provider is reachable and accepts its key.
+ * Useful to fail fast (e.g. in CI) before a batch of renders.
+ *
+ * # Arguments
+ * * `engine` - Engine handle
+ *
+ * # Returns
+ * true if the provider is healthy. On false, `aether_last_error()` has the reason.
+ */
+bool aether_engine_health_check(const struct AetherEngine *engine);
+
+/**
+ * Register a custom validator for slots of the given kind.
+ * Used when Self-Healing is enabled; rejected code is regenerated with
+ * the returned error message as feedback.
+ *
+ * # Arguments
+ * * `engine` - Engine handle (must be mutable)
+ * * `kind` - Slot kind name (e.g. "html", "function", or a custom kind)
+ * * `callback` - Function pointer called with the generated code
+ * * `user_data` - User context passed to callback (can be NULL).
+ *   It may be used from several threads concurrently.
+ *
+ * # Returns
+ * true on success, false on failure
+ */
+bool aether_engine_register_validator(struct AetherEngine *engine,
+                                      const char *kind,
+                                      AetherValidateCallback callback,
+                                      void *user_data);
+
 /**
  * Create a template from content string.
  *
@@ -168,6 +213,24 @@ void aether_template_add_slot(struct AetherTemplate *template_,
                               const char *name,
                               const char *prompt);
 
+/**
+ * Add a slot with temperature more detailed settings.
+ *
+ * # Arguments
+ * * `template` - Template handle
+ * * `name` - Slot name
+ * * `prompt` - AI prompt for this slot
+ * * `temperature` - Temperature override (0.0 to 2.0, use negative for default)
+ * * `model` - Model identifier (or NULL for default)
+ * * `max_tokens` - Maximum tokens (0 for default)
+ */
+void aether_template_add_slot_full(struct AetherTemplate *template_,
+                                   const char *name,
+                                   const char *prompt,
+                                   float temperature,
+                                   const char *model,
+                                   uint32_t max_tokens);
+
 /**
  * Free a template handle.
  */
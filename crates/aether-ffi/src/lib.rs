@@ -46,7 +46,8 @@ use std::ptr;
 use std::sync::Arc;
 
 use aether_core::{
-    InjectionEngine, Template, AiProvider,
+    provider::{DEFAULT_ANTHROPIC_MODEL, DEFAULT_GEMINI_MODEL, DEFAULT_GROK_MODEL, DEFAULT_OLLAMA_MODEL, DEFAULT_OPENAI_MODEL},
+    EngineParts, InjectionEngine, Template, AiProvider, Slot, SlotKind,
     validation::MultiValidator,
     cache::SemanticCache,
 };
@@ -84,31 +85,55 @@ pub struct AetherEngine {
     cache_enabled: bool,
     toon_enabled: bool,
     max_retries: usize,
+    validators: Vec<(SlotKind, ValidatorCallback)>,
+}
+
+/// A registered C validator callback and its user data.
+#[derive(Clone, Copy)]
+struct ValidatorCallback {
+    callback: AetherValidateCallback,
+    user_data: *mut libc::c_void,
+}
+
+// The caller guarantees `user_data` may be used from any thread.
+unsafe impl Send for ValidatorCallback {}
+unsafe impl Sync for ValidatorCallback {}
+
+impl ValidatorCallback {
+    fn validate(&self, code: &str) -> std::result::Result<(), String> {
+        let code = CString::new(code).map_err(|e| e.to_string())?;
+        let error = (self.callback)(code.as_ptr(), self.user_data);
+        if error.is_null() {
+            Ok(())
+        } else {
+            Err(unsafe { CStr::from_ptr(error) }.to_string_lossy().into_owned())
+        }
+    }
 }
 
 impl AetherEngine {
     fn rebuild(&mut self) {
-        let mut engine = InjectionEngine::new(self.provider.clone());
-        
+        let mut parts = EngineParts::new(Arc::new(self.provider.clone()));
+        parts.config = parts.config.with_toon(self.toon_enabled);
+        if self.max_retries > 0 {
+            parts.config = parts.config.with_max_retries(self.max_retries as u32);
+        }
+
         if self.healing_enabled {
-            engine = engine.with_validator(MultiValidator::new());
+            let callbacks = self.validators.iter().map(|(kind, callback)| {
+                let callback = *callback;
+                (kind.clone(), move |code: &str| callback.validate(code))
+            });
+            parts.validator = Some(Arc::new(MultiValidator::new().with_kind_callbacks(callbacks)));
         }
-        
+
         if self.cache_enabled {
             if let Ok(cache) = SemanticCache::new() {
-                engine = engine.with_cache(cache);
+                parts.cache = Some(Arc::new(cache));
             }
         }
-        
-        if self.toon_enabled {
-            engine = engine.with_toon(true);
-        }
-        
-        if self.max_retries > 0 {
-            engine = engine.max_retries(self.max_retries as u32);
-        }
-        
-        self.inner = engine;
+
+        self.inner = InjectionEngine::assemble(parts);
     }
 }
 
@@ -158,7 +183,7 @@ pub extern "C" fn aether_last_error() -> *const c_char {
 #[no_mangle]
 pub extern "C" fn aether_create_openai_provider(model: *const c_char) -> *mut AetherProvider {
     let model_str = if model.is_null() {
-        "gpt-4o".to_string()
+        DEFAULT_OPENAI_MODEL.to_string()
     } else {
         unsafe { CStr::from_ptr(model) }.to_string_lossy().into_owned()
     };
@@ -181,7 +206,7 @@ pub extern "C" fn aether_create_openai_provider(model: *const c_char) -> *mut Ae
 #[no_mangle]
 pub extern "C" fn aether_create_anthropic_provider(model: *const c_char) -> *mut AetherProvider {
     let model_str = if model.is_null() {
-        "claude-3-opus-20240229".to_string()
+        DEFAULT_ANTHROPIC_MODEL.to_string()
     } else {
         unsafe { CStr::from_ptr(model) }.to_string_lossy().into_owned()
     };
@@ -204,7 +229,7 @@ pub extern "C" fn aether_create_anthropic_provider(model: *const c_char) -> *mut
 #[no_mangle]
 pub extern "C" fn aether_create_gemini_provider(model: *const c_char) -> *mut AetherProvider {
     let model_str = if model.is_null() {
-        "gemini-1.5-pro".to_string()
+        DEFAULT_GEMINI_MODEL.to_string()
     } else {
         unsafe { CStr::from_ptr(model) }.to_string_lossy().into_owned()
     };
@@ -227,7 +252,7 @@ pub extern "C" fn aether_create_gemini_provider(model: *const c_char) -> *mut Ae
 #[no_mangle]
 pub extern "C" fn aether_create_ollama_provider(model: *const c_char) -> *mut AetherProvider {
     let model_str = if model.is_null() {
-        "llama3".to_string()
+        DEFAULT_OLLAMA_MODEL.to_string()
     } else {
         unsafe { CStr::from_ptr(model) }.to_string_lossy().into_owned()
     };
@@ -249,7 +274,7 @@ pub extern "C" fn aether_create_ollama_provider(model: *const c_char) -> *mut Ae
 #[no_mangle]
 pub extern "C" fn aether_create_grok_provider(model: *const c_char) -> *mut AetherProvider {
     let model_str = if model.is_null() {
-        "grok-1".to_string()
+        DEFAULT_GROK_MODEL.to_string()
     } else {
         unsafe { CStr::from_ptr(model) }.to_string_lossy().into_owned()
     };
@@ -305,6 +330,7 @@ pub extern "C" fn aether_create_engine(provider: *const AetherProvider) -> *mut
         cache_enabled: false,
         toon_enabled: false,
         max_retries: 0,
+        validators: Vec::new(),
     });
     Box::into_raw(handle)
 }
@@ -392,6 +418,82 @@ pub extern "C" fn aether_engine_set_max_retries(engine: *mut AetherEngine, max_r
     engine_ref.rebuild();
 }
 
+/// Check that the engine's provider is reachable and accepts its key.
+/// Useful to fail fast (e.g. in CI) before a batch of renders.
+///
+/// # Arguments
+/// * `engine` - Engine handle
+///
+/// # Returns
+/// true if the provider is healthy. On false, `aether_last_error()` has the reason.
+#[no_mangle]
+pub extern "C" fn aether_engine_health_check(engine: *const AetherEngine) -> bool {
+    if engine.is_null() {
+        set_last_error("Engine is null".to_string());
+        return false;
+    }
+
+    let engine_ref = unsafe { &*engine };
+    match RUNTIME.block_on(engine_ref.provider.health_check()) {
+        Ok(true) => true,
+        Ok(false) => {
+            set_last_error(format!("Provider '{}' is not healthy", engine_ref.provider.name()));
+            false
+        }
+        Err(e) => {
+            set_last_error(e.to_string());
+            false
+        }
+    }
+}
+
+/// Callback type for custom validators.
+///
+/// # Arguments
+/// * `code` - The generated code (null-terminated C string)
+/// * `user_data` - User-provided context pointer
+///
+/// # Returns
+/// NULL if the code is valid, otherwise an error message. The message is
+/// copied immediately, so it only needs to stay valid until the callback returns.
+pub type AetherValidateCallback = extern "C" fn(code: *const c_char, user_data: *mut libc::c_void) -> *const c_char;
+
+/// Register a custom validator for slots of the given kind.
+/// Used when Self-Healing is enabled; rejected code is regenerated with
+/// the returned error message as feedback.
+///
+/// # Arguments
+/// * `engine` - Engine handle (must be mutable)
+/// * `kind` - Slot kind name (e.g. "html", "function", or a custom kind)
+/// * `callback` - Function pointer called with the generated code
+/// * `user_data` - User context passed to callback (can be NULL).
+///   It may be used from several threads concurrently.
+///
+/// # Returns
+/// true on success, false on failure
+#[no_mangle]
+pub extern "C" fn aether_engine_register_validator(
+    engine: *mut AetherEngine,
+    kind: *const c_char,
+    callback: AetherValidateCallback,
+    user_data: *mut libc::c_void,
+) -> bool {
+    if engine.is_null() || kind.is_null() {
+        set_last_error("Engine or kind is null".to_string());
+        return false;
+    }
+
+    let engine_ref = unsafe { &mut *engine };
+    let kind: SlotKind = unsafe { CStr::from_ptr(kind) }
+        .to_string_lossy()
+        .parse()
+        .unwrap_or_default();
+
+    engine_ref.validators.retain(|(k, _)| *k != kind);
+    engine_ref.validators.push((kind, ValidatorCallback { callback, user_data }));
+    engine_ref.rebuild();
+    true
+}
 
 // ============================================================
 // Template Operations
@@ -697,3 +799,68 @@ pub extern "C" fn aether_version() -> *const c_char {
     });
     VERSION.as_ptr()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aether_core::provider::MockProvider;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    extern "C" fn reject_todo(code: *const c_char, user_data: *mut libc::c_void) -> *const c_char {
+        let calls = unsafe { &*(user_data as *const AtomicUsize) };
+        calls.fetch_add(1, Ordering::SeqCst);
+
+        let code = unsafe { CStr::from_ptr(code) }.to_string_lossy();
+        if code.contains("TODO") {
+            b"code must not contain TODO\0".as_ptr() as *const c_char
+        } else {
+            ptr::null()
+        }
+    }
+
+    #[test]
+    fn test_registered_validator_triggers_retry() {
+        let mock = Arc::new(MockProvider::new().with_response("body", "<p>TODO</p>"));
+        let provider = Box::into_raw(Box::new(AetherProvider { inner: mock.clone() }));
+        let engine = aether_create_engine(provider);
+        let calls = AtomicUsize::new(0);
+
+        let kind = CString::new("html").unwrap();
+        assert!(aether_engine_register_validator(
+            engine,
+            kind.as_ptr(),
+            reject_todo,
+            &calls as *const AtomicUsize as *mut libc::c_void,
+        ));
+        assert!(aether_engine_enable_healing(engine));
+        aether_engine_set_max_retries(engine, 2);
+
+        let content = CString::new("{{AI:body:html}}").unwrap();
+        let template = aether_create_template(content.as_ptr());
+        let result = aether_render(engine, template);
+
+        // The banned word is rejected and the slot retried; the mock repeats
+        // itself, so the engine gives up on the identical second answer
+        assert!(result.is_null());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(mock.call_count(), 2);
+
+        aether_free_template(template);
+        aether_free_engine(engine);
+        aether_free_provider(provider);
+    }
+
+    #[test]
+    fn test_engine_health_check() {
+        let provider = Box::into_raw(Box::new(AetherProvider {
+            inner: Arc::new(MockProvider::new()),
+        }));
+        let engine = aether_create_engine(provider);
+
+        assert!(aether_engine_health_check(engine));
+        assert!(!aether_engine_health_check(ptr::null()));
+
+        aether_free_engine(engine);
+        aether_free_provider(provider);
+    }
+}
@@ -7,6 +7,7 @@ use axum::{
     Json, Router,
 };
 use std::sync::Arc;
+use aether_core::MetricsObserver;
 use crate::model::{Inspector, InspectorEvent};
 
 #[derive(RustEmbed)]
@@ -15,17 +16,35 @@ struct Assets;
 
 pub struct InspectorServer {
     inspector: Arc<Inspector>,
+    metrics: Option<MetricsObserver>,
 }
 
 impl InspectorServer {
     pub fn new(inspector: Arc<Inspector>) -> Self {
-        Self { inspector }
+        Self { inspector, metrics: None }
+    }
+
+    /// Serve the given metrics in Prometheus format at `/metrics`.
+    pub fn with_metrics(mut self, metrics: MetricsObserver) -> Self {
+        self.metrics = Some(metrics);
+        self
     }
 
     pub async fn start(self, port: u16) -> Result<(), Box<dyn std::error::Error>> {
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/api/events", get(list_events))
-            .route("/api/events/:id", get(get_event))
+            .route("/api/events/:id", get(get_event));
+
+        if let Some(metrics) = self.metrics {
+            app = app.route("/metrics", get(move || async move {
+                (
+                    [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                    metrics.metrics_text(),
+                )
+            }));
+        }
+
+        let app = app
             .fallback(static_handler)
             .with_state(self.inspector);
 
@@ -6,7 +6,7 @@
 //! and runtime injection helpers.
 
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::{parse_macro_input, LitStr};
 use aes_gcm::{
     aead::{Aead, KeyInit},
@@ -131,6 +131,9 @@ pub fn ai_generate(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// This macro removes the function body and replaces it with logic that:
 /// 1. Fetches a script from AI at runtime.
 /// 2. Executes it using the AetherRuntime (Rhai).
+///
+/// The script is generated once per prompt and model and reused for the rest
+/// of the process. Set `AETHER_SECURE_REFRESH` to regenerate on every call.
 /// 
 /// # Example
 /// 
@@ -175,6 +178,33 @@ pub fn aether_secure(attr: TokenStream, item: TokenStream) -> TokenStream {
         None
     }).collect();
 
+    // Typed signature for the prompt, e.g. "a: i64, b: i64"
+    let signature = fn_args.iter().filter_map(|arg| {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            return Some(format!("{}: {}", type_string(&pat_type.pat), type_string(&pat_type.ty)));
+        }
+        None
+    }).collect::<Vec<_>>().join(", ");
+    let return_type = match fn_output {
+        syn::ReturnType::Default => quote! { () },
+        syn::ReturnType::Type(_, ty) => ty.to_token_stream(),
+    };
+    let return_type_name = type_string(&return_type);
+    let fn_name_str = fn_name.to_string();
+
+    // Test hook, compiled in only with the `dev` feature
+    let mock_arm = if cfg!(feature = "dev") {
+        quote! {
+            "mock" => {
+                let p = aether_core::provider::MockProvider::shared();
+                let engine = InjectionEngine::with_config_arc(p, aether_core::AetherConfig::default());
+                engine.render(&template).await.expect("AI script generation failed")
+            },
+        }
+    } else {
+        quote! {}
+    };
+
     let output = quote! {
         #fn_vis async fn #fn_name(#fn_args) #fn_output {
             use aether_core::prelude::*;
@@ -195,14 +225,28 @@ pub fn aether_secure(attr: TokenStream, item: TokenStream) -> TokenStream {
             };
 
             let script_prompt = format!(
-                "Implement this logic in Rhai script: {}. Output ONLY the raw Rhai script code. The inputs available are: {:?}. Return the result directly. Do not wrap in markdown.",
+                "Implement this logic in Rhai script: {}. Output ONLY the raw Rhai script code. The inputs available are: ({}). The result must be a value of Rust type `{}`. Return the result directly. Do not wrap in markdown.",
                 prompt_text,
-                vec![#(stringify!(#arg_names)),*]
+                #signature,
+                #return_type_name
             );
             
+            // 2. Reuse the script generated for this prompt and model earlier in
+            // the process; AETHER_SECURE_REFRESH forces a regeneration.
+            static SCRIPT_CACHE: std::sync::OnceLock<std::sync::Mutex<HashMap<String, String>>> = std::sync::OnceLock::new();
+            let script_cache = SCRIPT_CACHE.get_or_init(Default::default);
+            let model = std::env::var("AETHER_MODEL").unwrap_or_default();
+            let cache_key = format!("{}:{}:{}", provider_type, model, script_prompt);
+            let cached = if std::env::var("AETHER_SECURE_REFRESH").is_ok() {
+                None
+            } else {
+                script_cache.lock().unwrap().get(&cache_key).cloned()
+            };
+
             let template = Template::new("{{AI:script}}")
                 .configure_slot(Slot::new("script", script_prompt).with_temperature(0.0));
 
+            let script = if let Some(script) = cached { script } else {
             let script = match provider_type.to_lowercase().as_str() {
                 "anthropic" | "claude" => {
                     let p = aether_ai::AnthropicProvider::from_env().expect("Anthropic Provider not configured");
@@ -215,17 +259,21 @@ pub fn aether_secure(attr: TokenStream, item: TokenStream) -> TokenStream {
                     engine.render(&template).await.expect("AI script generation failed")
                 },
                 "ollama" => {
-                    let model = std::env::var("AETHER_MODEL").unwrap_or_else(|_| "llama3".to_string());
+                    let model = if model.is_empty() { aether_core::provider::DEFAULT_OLLAMA_MODEL.to_string() } else { model };
                     let p = aether_ai::OllamaProvider::new(&model);
                     let engine = InjectionEngine::new(p);
                     engine.render(&template).await.expect("AI script generation failed")
                 },
+                #mock_arm
                 _ => {
                    let p = aether_ai::OpenAiProvider::from_env().expect("OpenAI Provider not configured");
                    let engine = InjectionEngine::new(p);
                    engine.render(&template).await.expect("AI script generation failed")
                 }
             };
+            script_cache.lock().unwrap().insert(cache_key, script.clone());
+            script
+            };
 
             // 3. Execute in Runtime
             let runtime = AetherRuntime::new();
@@ -235,15 +283,40 @@ pub fn aether_secure(attr: TokenStream, item: TokenStream) -> TokenStream {
              )*
 
             let result = runtime.execute(&script, inputs).expect("Runtime execution failed");
-            
-            // 4. Return result (simplified cast, needs more robust handling for varied types)
-            result.cast()
+
+            // 4. Return result, checked against the declared return type
+            let actual = result.type_name();
+            result.try_cast::<#return_type>().unwrap_or_else(|| {
+                panic!(
+                    "Aether Secure: script for `{}` returned {} but the declared return type is {}",
+                    #fn_name_str, actual, #return_type_name
+                )
+            })
         }
     };
 
     output.into()
 }
 
+/// Render tokens as compact Rust source (`Vec<i64>` rather than `Vec < i64 >`).
+fn type_string(tokens: &impl ToTokens) -> String {
+    let source = tokens.to_token_stream().to_string();
+    let chars: Vec<char> = source.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(source.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' {
+            let prev = i.checked_sub(1).map(|j| chars[j]);
+            let next = chars.get(i + 1).copied();
+            if !(prev.is_some_and(is_word) && next.is_some_and(is_word)) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
 fn derive_key(key_str: &str) -> [u8; 32] {
     let mut key = [0u8; 32];
     let bytes = key_str.as_bytes();
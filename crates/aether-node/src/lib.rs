@@ -22,6 +22,8 @@
 #![deny(clippy::all)]
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, JsFunction};
 use napi_derive::napi;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -36,9 +38,15 @@ use aether_core::{
     RenderSession as CoreRenderSession,
     AetherRuntime,
     AetherConfig,
+    AetherError,
+    EngineParts,
     toon::Toon,
+    validation::{MultiValidator, Validator},
+};
+use aether_ai::{OpenAiProvider, AnthropicProvider, CohereProvider, OllamaProvider};
+use aether_core::provider::{
+    DEFAULT_ANTHROPIC_MODEL, DEFAULT_COHERE_MODEL, DEFAULT_GEMINI_MODEL, DEFAULT_GROK_MODEL, DEFAULT_OLLAMA_MODEL, DEFAULT_OPENAI_MODEL,
 };
-use aether_ai::{OpenAiProvider, AnthropicProvider, OllamaProvider};
 use aether_core::AiProvider;
 use rhai::Dynamic;
 
@@ -88,6 +96,30 @@ impl Template {
         self.inner.slot_names().iter().map(|s| s.to_string()).collect()
     }
 
+    /// Get every slot with its kind, prompt and settings, sorted by name.
+    #[napi]
+    pub fn get_slots(&self) -> Vec<SlotInfo> {
+        let mut slots: Vec<SlotInfo> = self
+            .inner
+            .slots
+            .values()
+            .map(|slot| SlotInfo {
+                name: slot.name.clone(),
+                kind: kind_name(&slot.kind),
+                prompt: slot.prompt.clone(),
+                temperature: slot.temperature.map(f64::from),
+                max_tokens: slot.max_tokens,
+                max_lines: slot
+                    .constraints
+                    .as_ref()
+                    .and_then(|c| c.max_lines)
+                    .map(|n| n as u32),
+            })
+            .collect();
+        slots.sort_by(|a, b| a.name.cmp(&b.name));
+        slots
+    }
+
     /// Get the template content.
     #[napi(getter)]
     pub fn content(&self) -> String {
@@ -95,6 +127,37 @@ impl Template {
     }
 }
 
+/// Metadata for one slot of a template, as returned by `getSlots`.
+#[napi(object)]
+pub struct SlotInfo {
+    pub name: String,
+    /// Kind name (`raw`, `function`, `html`, ...). Custom kinds from
+    /// `{{AI:name:kind}}` markers keep their marker name, which `Slot.setKind`
+    /// does not accept.
+    pub kind: String,
+    pub prompt: String,
+    pub temperature: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub max_lines: Option<u32>,
+}
+
+/// Name of a slot kind. Built-in kinds use the names `Slot.setKind`
+/// accepts; custom kinds keep their marker name, which `setKind` maps to raw.
+fn kind_name(kind: &CoreSlotKind) -> String {
+    match kind {
+        CoreSlotKind::Raw => "raw",
+        CoreSlotKind::Function => "function",
+        CoreSlotKind::Class => "class",
+        CoreSlotKind::Html => "html",
+        CoreSlotKind::Css => "css",
+        CoreSlotKind::JavaScript => "javascript",
+        CoreSlotKind::Component => "component",
+        CoreSlotKind::Json => "json",
+        CoreSlotKind::Custom(name) => name,
+    }
+    .to_string()
+}
+
 /// JavaScript-accessible RenderSession class for incremental rendering.
 #[napi]
 pub struct RenderSession {
@@ -122,6 +185,24 @@ impl RenderSession {
     pub fn clear(&self) {
         self.inner.blocking_lock().results.clear();
     }
+
+    /// Save the cached results to a JSON file.
+    #[napi]
+    pub fn save(&self, path: String) -> Result<()> {
+        self.inner
+            .blocking_lock()
+            .save(path)
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
+
+    /// Load a session previously written by `save`.
+    #[napi(factory)]
+    pub fn load(path: String) -> Result<Self> {
+        let inner = CoreRenderSession::load(path).map_err(|e| Error::from_reason(e.to_string()))?;
+        Ok(Self {
+            inner: Mutex::new(inner),
+        })
+    }
 }
 
 /// JavaScript-accessible Slot class.
@@ -140,7 +221,7 @@ impl Slot {
         }
     }
 
-    /// Set the slot kind.
+    /// Set the slot kind. Names other than the built-in kinds fall back to raw.
     #[napi]
     pub fn set_kind(&mut self, kind: String) {
         let slot_kind = match kind.to_lowercase().as_str() {
@@ -150,6 +231,7 @@ impl Slot {
             "function" => CoreSlotKind::Function,
             "class" => CoreSlotKind::Class,
             "component" => CoreSlotKind::Component,
+            "json" => CoreSlotKind::Json,
             _ => CoreSlotKind::Raw,
         };
         self.inner.kind = slot_kind;
@@ -189,6 +271,7 @@ pub enum ProviderType {
     Ollama,
     Gemini,
     Grok,
+    Cohere,
 }
 
 /// Configuration for AI providers.
@@ -203,6 +286,22 @@ pub struct ProviderConfig {
     pub api_key_url: Option<String>,
 }
 
+/// Caps for `getStreamChunks`, which buffers the whole stream in memory.
+#[napi(object)]
+pub struct StreamLimits {
+    /// Maximum number of chunks to collect.
+    pub max_chunks: Option<u32>,
+    /// Maximum total size of the collected chunks, in bytes.
+    pub max_bytes: Option<u32>,
+}
+
+/// A chunk of generated output for one slot.
+#[napi(object)]
+pub struct SlotChunk {
+    pub slot: String,
+    pub delta: String,
+}
+
 /// Main Aether engine for JavaScript.
 #[napi]
 pub struct AetherEngine {
@@ -212,6 +311,56 @@ pub struct AetherEngine {
     context: Option<CoreContext>,
     config: AetherConfig,
     api_key_url: Option<String>,
+    base_url: Option<String>,
+    /// Provider built on first use and reused by later calls, so renders
+    /// share one HTTP connection pool. Cleared when the key settings change.
+    provider: std::sync::Mutex<Option<Arc<dyn AiProvider>>>,
+    /// Custom validators registered per slot kind.
+    validators: HashMap<CoreSlotKind, JsValidator>,
+    closed: bool,
+}
+
+/// A JavaScript validator, callable from the engine's worker threads.
+type JsValidator = ThreadsafeFunction<String, ErrorStrategy::Fatal>;
+
+/// Run a JavaScript validator on `code` and wait for its verdict.
+///
+/// Blocks the calling worker until the JS thread has run the function, so it
+/// must not be called from the JS thread itself. On the multi-threaded
+/// runtime the wait runs under `block_in_place`, so other tasks move off the
+/// worker in the meantime.
+fn call_validator(validator: &JsValidator, code: &str) -> std::result::Result<(), String> {
+    use tokio::runtime::{Handle, RuntimeFlavor};
+
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| wait_for_validator(validator, code))
+        }
+        _ => wait_for_validator(validator, code),
+    }
+}
+
+fn wait_for_validator(validator: &JsValidator, code: &str) -> std::result::Result<(), String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let status = validator.call_with_return_value(
+        code.to_string(),
+        ThreadsafeFunctionCallMode::Blocking,
+        move |verdict: Option<Either<bool, String>>| {
+            let _ = tx.send(verdict);
+            Ok(())
+        },
+    );
+    if status != Status::Ok {
+        return Err(format!("Custom validator could not be called: {}", status));
+    }
+
+    // The sender is dropped without a verdict if the function threw or
+    // returned something other than a boolean or string
+    match rx.recv().map_err(|_| "Custom validator did not return a verdict".to_string())? {
+        None | Some(Either::A(true)) => Ok(()),
+        Some(Either::A(false)) => Err("Rejected by custom validator".to_string()),
+        Some(Either::B(message)) => Err(message),
+    }
 }
 
 #[napi]
@@ -221,11 +370,15 @@ impl AetherEngine {
     pub fn openai(model: Option<String>) -> Result<Self> {
         Ok(Self {
             provider_type: ProviderType::OpenAI,
-            model: model.unwrap_or_else(|| "gpt-4o".to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string()),
             api_key: std::env::var("OPENAI_API_KEY").ok(),
             context: None,
             config: AetherConfig::default(),
             api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
         })
     }
 
@@ -234,11 +387,15 @@ impl AetherEngine {
     pub fn anthropic(model: Option<String>) -> Result<Self> {
         Ok(Self {
             provider_type: ProviderType::Anthropic,
-            model: model.unwrap_or_else(|| "claude-3-5-sonnet-latest".to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string()),
             api_key: std::env::var("ANTHROPIC_API_KEY").ok(),
             context: None,
             config: AetherConfig::default(),
             api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
         })
     }
 
@@ -247,11 +404,32 @@ impl AetherEngine {
     pub fn gemini(model: Option<String>) -> Result<Self> {
         Ok(Self {
             provider_type: ProviderType::Gemini,
-            model: model.unwrap_or_else(|| "gemini-1.5-pro".to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string()),
             api_key: std::env::var("GOOGLE_API_KEY").ok(),
             context: None,
             config: AetherConfig::default(),
             api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
+        })
+    }
+
+    /// Create a new engine with Cohere provider.
+    #[napi(factory)]
+    pub fn cohere(model: Option<String>) -> Result<Self> {
+        Ok(Self {
+            provider_type: ProviderType::Cohere,
+            model: model.unwrap_or_else(|| DEFAULT_COHERE_MODEL.to_string()),
+            api_key: std::env::var("COHERE_API_KEY").ok(),
+            context: None,
+            config: AetherConfig::default(),
+            api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
         })
     }
 
@@ -260,11 +438,15 @@ impl AetherEngine {
     pub fn grok(model: Option<String>) -> Result<Self> {
         Ok(Self {
             provider_type: ProviderType::Grok,
-            model: model.unwrap_or_else(|| "grok-1".to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_GROK_MODEL.to_string()),
             api_key: std::env::var("XAI_API_KEY").ok(),
             context: None,
             config: AetherConfig::default(),
             api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
         })
     }
 
@@ -278,6 +460,10 @@ impl AetherEngine {
             context: None,
             config: AetherConfig::default(),
             api_key_url: None,
+            base_url: None,
+            provider: Default::default(),
+            validators: HashMap::new(),
+            closed: false,
         })
     }
 
@@ -285,12 +471,22 @@ impl AetherEngine {
     #[napi]
     pub fn set_api_key(&mut self, key: String) {
         self.api_key = Some(key);
+        self.reset_provider();
     }
 
     /// Set the API key URL for remote resolution.
     #[napi]
     pub fn set_api_key_url(&mut self, url: String) {
         self.api_key_url = Some(url);
+        self.reset_provider();
+    }
+
+    /// Send requests to `url` instead of the provider's default endpoint,
+    /// e.g. a proxy or a remote Ollama server.
+    #[napi]
+    pub fn set_base_url(&mut self, url: String) {
+        self.base_url = Some(url);
+        self.reset_provider();
     }
 
     /// Set context for generation.
@@ -339,6 +535,141 @@ impl AetherEngine {
         self.config.healing_enabled = enabled;
     }
 
+    /// Register a custom validator for slots of the given kind (e.g. "html").
+    /// Throws for a kind name that is not a built-in kind.
+    ///
+    /// The function receives the generated code and returns `undefined` or
+    /// `true` when it is valid, or `false` / an error message to reject it.
+    /// Rejected code is regenerated with the error as feedback when healing is on.
+    ///
+    /// The function must be synchronous: the render waits for its verdict,
+    /// and it runs on the JS thread, so a slow validator stalls both.
+    #[napi]
+    pub fn register_validator(&mut self, env: Env, kind: String, validator: JsFunction) -> Result<()> {
+        let slot_kind = kind.parse::<CoreSlotKind>().unwrap_or_else(|never| match never {});
+        if let CoreSlotKind::Custom(_) = slot_kind {
+            return Err(Error::from_reason(format!(
+                "Unknown slot kind '{}'; expected one of raw, function, class, html, css, javascript, component, json",
+                kind
+            )));
+        }
+        let mut validator: JsValidator = validator.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<String>| {
+            ctx.env.create_string(&ctx.value).map(|code| vec![code])
+        })?;
+        // Do not keep the process alive just for the validator
+        validator.unref(&env)?;
+        self.validators.insert(slot_kind, validator);
+        Ok(())
+    }
+
+    /// Close the engine. Later render calls are rejected.
+    ///
+    /// Work runs on the shared Node-API runtime, so there are no threads to
+    /// join here; this exists so Node and Python share the same lifecycle.
+    #[napi]
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.reset_provider();
+    }
+
+    /// Whether `close` has been called.
+    #[napi(getter)]
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn ensure_open(&self) -> Result<()> {
+        if self.closed {
+            return Err(Error::from_reason("Engine is closed"));
+        }
+        Ok(())
+    }
+
+    /// Drop the cached provider so the next call rebuilds it.
+    fn reset_provider(&mut self) {
+        *self.provider.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// The configured provider, built once and shared by every call.
+    fn shared_provider(&self) -> Result<Arc<dyn AiProvider>> {
+        let mut cached = self.provider.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(provider) = cached.as_ref() {
+            return Ok(Arc::clone(provider));
+        }
+        let provider = self.build_provider()?;
+        *cached = Some(Arc::clone(&provider));
+        Ok(provider)
+    }
+
+    /// Build the configured provider behind a shared pointer.
+    fn build_provider(&self) -> Result<Arc<dyn AiProvider>> {
+        Ok(match self.provider_type {
+            ProviderType::OpenAI => {
+                let api_key = self.api_key.clone().or_else(|| std::env::var("OPENAI_API_KEY").ok()).unwrap_or_default();
+                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
+                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
+                if let Some(ref url) = self.base_url { config = config.with_base_url(url); }
+                Arc::new(OpenAiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
+            }
+            ProviderType::Anthropic => {
+                let api_key = self.api_key.clone().or_else(|| std::env::var("ANTHROPIC_API_KEY").ok()).unwrap_or_default();
+                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
+                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
+                if let Some(ref url) = self.base_url { config = config.with_base_url(url); }
+                Arc::new(AnthropicProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
+            }
+            ProviderType::Gemini => {
+                let api_key = self.api_key.clone().or_else(|| std::env::var("GOOGLE_API_KEY").ok()).unwrap_or_default();
+                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
+                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
+                if let Some(ref url) = self.base_url { config = config.with_base_url(url); }
+                Arc::new(aether_ai::GeminiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
+            }
+            ProviderType::Ollama => match self.base_url {
+                Some(ref url) => Arc::new(OllamaProvider::with_options(&self.model, url)) as Arc<dyn AiProvider>,
+                None => Arc::new(OllamaProvider::new(&self.model)) as Arc<dyn AiProvider>,
+            },
+            ProviderType::Cohere => {
+                let api_key = self.api_key.clone().or_else(|| std::env::var("COHERE_API_KEY").ok()).unwrap_or_default();
+                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
+                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
+                if let Some(ref url) = self.base_url { config = config.with_base_url(url); }
+                Arc::new(CohereProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
+            }
+            ProviderType::Grok => {
+                let api_key = self.api_key.clone().or_else(|| std::env::var("XAI_API_KEY").ok()).unwrap_or_default();
+                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model).with_base_url("https://api.x.ai/v1/chat/completions");
+                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
+                if let Some(ref url) = self.base_url { config = config.with_base_url(url); }
+                Arc::new(OpenAiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
+            }
+        })
+    }
+
+    /// Engine parts shared by every render call: provider, config, context
+    /// and validator.
+    fn engine_parts(&self) -> Result<EngineParts<dyn AiProvider>> {
+        Ok(EngineParts {
+            config: self.config.clone(),
+            context: self.context.clone(),
+            validator: self.custom_validator(),
+            ..EngineParts::new(self.shared_provider()?)
+        })
+    }
+
+    /// Build a validator with the registered per-kind callbacks, if healing is on.
+    fn custom_validator(&self) -> Option<Arc<dyn Validator>> {
+        if !self.config.healing_enabled || self.validators.is_empty() {
+            return None;
+        }
+
+        let callbacks = self.validators.iter().map(|(kind, validator)| {
+            let validator = validator.clone();
+            (kind.clone(), move |code: &str| call_validator(&validator, code))
+        });
+        Some(Arc::new(MultiValidator::new().with_kind_callbacks(callbacks)))
+    }
+
     /// Deserialize a TOON string back into a JSON structure.
     #[napi]
     pub fn toon_deserialize(&self, toon_str: String) -> Result<String> {
@@ -355,7 +686,7 @@ impl AetherEngine {
     /// * `inputs_json` - Optional JSON string of input variables (e.g., '{"x": 10, "name": "Alice"}').
     /// 
     /// # Returns
-    /// The result of the script execution as a string.
+    /// The result of the script execution as a string (arrays and maps are JSON-encoded).
     #[napi]
     pub fn execute_script(&self, script: String, inputs_json: Option<String>) -> Result<String> {
         let runtime = AetherRuntime::new();
@@ -387,6 +718,11 @@ impl AetherEngine {
         let result = runtime.execute(&script, rhai_inputs)
             .map_err(|e| Error::from_reason(e.to_string()))?;
 
+        // Arrays and maps are returned as JSON so callers can JSON.parse them
+        if result.is_array() || result.is_map() {
+            return serde_json::to_string(&AetherRuntime::to_json(result))
+                .map_err(|e| Error::from_reason(e.to_string()));
+        }
         Ok(result.to_string())
     }
 
@@ -406,92 +742,27 @@ impl AetherEngine {
     }
 
     async fn render_internal(&self, template: &CoreTemplate) -> Result<String> {
-        match self.provider_type {
-            ProviderType::OpenAI => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("OPENAI_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url {
-                    config = config.with_api_key_url(url);
-                }
-
-                let provider = OpenAiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.render_with_provider(template, provider).await
-            }
-            ProviderType::Anthropic => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url {
-                    config = config.with_api_key_url(url);
-                }
-
-                let provider = AnthropicProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.render_with_provider(template, provider).await
-            }
-            ProviderType::Gemini => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url {
-                    config = config.with_api_key_url(url);
-                }
-
-                let provider = aether_ai::GeminiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                self.render_with_provider(template, provider).await
-            }
-            ProviderType::Ollama => {
-                let provider = OllamaProvider::new(&self.model);
-                self.render_with_provider(template, provider).await
-            }
-            ProviderType::Grok => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("XAI_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model)
-                    .with_base_url("https://api.x.ai/v1/chat/completions");
-
-                if let Some(ref url) = self.api_key_url {
-                    config = config.with_api_key_url(url);
-                }
+        self.ensure_open()?;
+        let mut parts = self.engine_parts()?;
 
-                let provider = OpenAiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                self.render_with_provider(template, provider).await
-            }
+        // Apply Premium Features if enabled in config
+        if self.config.cache_enabled {
+            parts.cache = Some(Arc::new(aether_core::cache::SemanticCache::new().map_err(|e| Error::from_reason(e.to_string()))?));
         }
-    }
 
-    /// Render with a specific provider.
-    async fn render_with_provider<P: AiProvider + 'static>(
-        &self,
-        template: &CoreTemplate,
-        provider: P,
-    ) -> Result<String> {
-        let mut engine = CoreEngine::with_config(provider, self.config.clone());
-        
-        if let Some(ref ctx) = self.context {
-            engine = engine.with_context(ctx.clone());
-        }
+        CoreEngine::assemble(parts).render(template).await
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
 
-        // Apply Premium Features if enabled in config but not yet in engine
-        if self.config.cache_enabled && engine.cache().is_none() {
-            engine = engine.with_cache(aether_core::cache::SemanticCache::new().map_err(|e| Error::from_reason(e.to_string()))?);
-        }
-        
-        engine.render(template).await
+    /// Check that the provider is reachable and accepts the configured key.
+    ///
+    /// Useful to fail fast (e.g. in CI) before a batch of renders.
+    #[napi]
+    pub async fn health_check(&self) -> Result<bool> {
+        self.ensure_open()?;
+        self.shared_provider()?
+            .health_check()
+            .await
             .map_err(|e| Error::from_reason(e.to_string()))
     }
 
@@ -510,127 +781,96 @@ impl AetherEngine {
         template: &Template,
         session: &RenderSession,
     ) -> Result<String> {
-        let provider = match self.provider_type {
-            ProviderType::OpenAI => {
-                let api_key = self.api_key.clone().or_else(|| std::env::var("OPENAI_API_KEY").ok()).unwrap_or_default();
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
-                Arc::new(OpenAiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
-            }
-            ProviderType::Anthropic => {
-                let api_key = self.api_key.clone().or_else(|| std::env::var("ANTHROPIC_API_KEY").ok()).unwrap_or_default();
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
-                Arc::new(AnthropicProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
-            }
-            ProviderType::Gemini => {
-                let api_key = self.api_key.clone().or_else(|| std::env::var("GOOGLE_API_KEY").ok()).unwrap_or_default();
-                let mut config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                if let Some(ref url) = self.api_key_url { config = config.with_api_key_url(url); }
-                Arc::new(aether_ai::GeminiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
-            }
-            ProviderType::Ollama => Arc::new(OllamaProvider::new(&self.model)) as Arc<dyn AiProvider>,
-            ProviderType::Grok => {
-                let api_key = self.api_key.clone().or_else(|| std::env::var("XAI_API_KEY").ok()).unwrap_or_default();
-                let config = aether_core::ProviderConfig::new(&api_key, &self.model).with_base_url("https://api.x.ai/v1/chat/completions");
-                Arc::new(OpenAiProvider::new(config).map_err(|e| Error::from_reason(e.to_string()))?) as Arc<dyn AiProvider>
-            }
-        };
+        self.ensure_open()?;
+        let engine = CoreEngine::assemble(self.engine_parts()?);
 
-        let mut engine = CoreEngine::with_config_arc(provider, self.config.clone());
-        if let Some(ref ctx) = self.context { engine = engine.with_context(ctx.clone()); }
-        
         engine.render_incremental(&template.inner, &mut *session.inner.lock().await).await
             .map_err(|e| Error::from_reason(e.to_string()))
     }
 
+    /// Incrementally render a template, streaming only the slots that changed.
+    ///
+    /// `onChunk` is called with each `{ slot, delta }` as it arrives: cached
+    /// slots first as a single chunk holding their full value, then the live
+    /// chunks of the others. Resolves to the rendered template; the session is
+    /// updated with the newly generated slots.
+    ///
+    /// # Example (JavaScript)
+    /// ```javascript
+    /// const result = await engine.renderIncrementalStream(template, session, ({ slot, delta }) => {
+    ///     process.stdout.write(delta);
+    /// });
+    /// ```
+    #[napi]
+    pub async fn render_incremental_stream(
+        &self,
+        template: &Template,
+        session: &RenderSession,
+        on_chunk: ThreadsafeFunction<SlotChunk, ErrorStrategy::Fatal>,
+    ) -> Result<String> {
+        self.ensure_open()?;
+        let engine = CoreEngine::assemble(self.engine_parts()?);
+
+        engine
+            .render_incremental_stream(&template.inner, &mut *session.inner.lock().await, |slot, delta| {
+                let chunk = SlotChunk { slot: slot.to_string(), delta: delta.to_string() };
+                match on_chunk.call(chunk, ThreadsafeFunctionCallMode::NonBlocking) {
+                    Status::Ok => Ok(()),
+                    status => Err(AetherError::RenderError(format!("Could not deliver chunk: {}", status))),
+                }
+            })
+            .await
+            .map_err(|e| Error::from_reason(e.to_string()))
+    }
+
     /// Get streaming chunks as an array (alternative to callback-based streaming).
     /// Returns an array of strings, each representing a chunk of the generated content.
+    ///
+    /// `limits` caps the number of chunks and total bytes buffered; exceeding
+    /// either stops generation and rejects with an error.
     #[napi]
     pub async fn get_stream_chunks(
         &self,
         template: &Template,
         slot_name: String,
+        limits: Option<StreamLimits>,
     ) -> Result<Vec<String>> {
         use futures::StreamExt;
+        self.ensure_open()?;
 
-        match self.provider_type {
-            ProviderType::OpenAI => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("OPENAI_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                let provider = OpenAiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.collect_stream_chunks(&template.inner, &slot_name, provider).await
-            }
-            ProviderType::Anthropic => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                let provider = AnthropicProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.collect_stream_chunks(&template.inner, &slot_name, provider).await
-            }
-            ProviderType::Gemini => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let config = aether_core::ProviderConfig::new(&api_key, &self.model);
-                let provider = aether_ai::GeminiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.collect_stream_chunks(&template.inner, &slot_name, provider).await
-            }
-            ProviderType::Ollama => {
-                let provider = OllamaProvider::new(&self.model);
-                self.collect_stream_chunks(&template.inner, &slot_name, provider).await
-            }
-            ProviderType::Grok => {
-                let api_key = self.api_key.clone()
-                    .or_else(|| std::env::var("XAI_API_KEY").ok())
-                    .unwrap_or_default();
-                
-                let config = aether_core::ProviderConfig::new(&api_key, &self.model)
-                    .with_base_url("https://api.x.ai/v1/chat/completions");
-                let provider = OpenAiProvider::new(config)
-                    .map_err(|e| Error::from_reason(e.to_string()))?;
-                
-                self.collect_stream_chunks(&template.inner, &slot_name, provider).await
-            }
-        }
-    }
+        let engine = CoreEngine::assemble(self.engine_parts()?);
+        let mut stream = engine
+            .generate_slot_stream(&template.inner, &slot_name)
+            .map_err(|e| Error::from_reason(e.to_string()))?;
 
-    async fn collect_stream_chunks<P: AiProvider + 'static>(
-        &self,
-        template: &CoreTemplate,
-        slot_name: &str,
-        provider: P,
-    ) -> Result<Vec<String>> {
-        use futures::StreamExt;
+        let max_chunks = limits.as_ref().and_then(|l| l.max_chunks).map(|n| n as usize);
+        let max_bytes = limits.as_ref().and_then(|l| l.max_bytes).map(|n| n as usize);
 
-        let mut engine = CoreEngine::with_config(provider, self.config.clone());
-        if let Some(ref ctx) = self.context { engine = engine.with_context(ctx.clone()); }
-        
-        match engine.generate_slot_stream(template, slot_name) {
-            Ok(mut stream) => {
-                let mut chunks = Vec::new();
-                while let Some(result) = stream.next().await {
-                    match result {
-                        Ok(chunk) => chunks.push(chunk.delta),
-                        Err(e) => return Err(Error::from_reason(e.to_string())),
-                    }
+        let mut chunks = Vec::new();
+        let mut bytes = 0;
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(chunk) => {
+                    bytes += chunk.delta.len();
+                    chunks.push(chunk.delta);
                 }
-                Ok(chunks)
+                Err(e) => return Err(Error::from_reason(e.to_string())),
+            }
+            // Returning drops the stream, which cancels the request
+            if let Some(max) = max_chunks.filter(|&max| chunks.len() > max) {
+                return Err(Error::from_reason(format!(
+                    "Stream for slot '{}' exceeded maxChunks ({})",
+                    slot_name, max
+                )));
+            }
+            if let Some(max) = max_bytes.filter(|&max| bytes > max) {
+                return Err(Error::from_reason(format!(
+                    "Stream for slot '{}' exceeded maxBytes ({})",
+                    slot_name, max
+                )));
             }
-            Err(e) => Err(Error::from_reason(e.to_string()))
         }
+        Ok(chunks)
     }
 }
 
@@ -646,7 +886,8 @@ pub async fn generate(prompt: String, provider: Option<String>) -> Result<String
     
     let engine = match provider_str.to_lowercase().as_str() {
         "anthropic" | "claude" => AetherEngine::anthropic(None)?,
-        "ollama" | "local" => AetherEngine::ollama("llama3".to_string())?,
+        "ollama" | "local" => AetherEngine::ollama(DEFAULT_OLLAMA_MODEL.to_string())?,
+        "cohere" => AetherEngine::cohere(None)?,
         _ => AetherEngine::openai(None)?,
     };
     
@@ -1,8 +1,72 @@
 // Test file for @aether/codegen
-// Run with: node test.js
+// Run with: node test.js (exits non-zero if any check fails)
 
+const assert = require('node:assert/strict');
+const http = require('http');
 const { Template, AetherEngine, generate, renderTemplate } = require('./index');
 
+let failures = 0;
+
+// Run one check, reporting it without stopping the remaining ones
+async function check(name, fn) {
+    try {
+        await fn();
+        console.log(`  ✅ ${name}`);
+    } catch (e) {
+        failures++;
+        console.log(`  ❌ ${name}: ${e.message}`);
+    }
+}
+
+// Minimal Ollama stand-in that streams one NDJSON line per chunk
+function startOllamaStub(chunks) {
+    const server = http.createServer((req, res) => {
+        req.resume();
+        req.on('end', () => {
+            res.writeHead(200, { 'Content-Type': 'application/x-ndjson' });
+            chunks.forEach((chunk, i) => {
+                res.write(JSON.stringify({ response: chunk, done: i === chunks.length - 1 }) + '\n');
+            });
+            res.end();
+        });
+    });
+    return listen(server);
+}
+
+// Ollama stand-in that answers each request with the next response in turn
+function startOllamaSequenceStub(responses) {
+    let calls = 0;
+    const server = http.createServer((req, res) => {
+        req.resume();
+        req.on('end', () => {
+            const response = responses[Math.min(calls++, responses.length - 1)];
+            res.writeHead(200, { 'Content-Type': 'application/x-ndjson' });
+            res.end(JSON.stringify({ response, done: true }) + '\n');
+        });
+    });
+    return listen(server);
+}
+
+// Listen on a free port, so the tests never clash with a running Ollama
+function listen(server) {
+    return new Promise((resolve, reject) => {
+        server.once('error', reject);
+        server.listen(0, '127.0.0.1', () => resolve(server));
+    });
+}
+
+// Ollama engine talking to `server` instead of the default address
+function stubEngine(server) {
+    const engine = AetherEngine.ollama('codellama');
+    engine.setBaseUrl(`http://127.0.0.1:${server.address().port}/api/generate`);
+    return engine;
+}
+
+function stopStub(server) {
+    server.closeAllConnections();
+    return new Promise((resolve) => server.close(resolve));
+}
+
 async function main() {
     console.log('🚀 @aether/codegen - Node.js Bindings Test\n');
 
@@ -10,34 +74,123 @@ async function main() {
     console.log('Test 1: Template creation');
     const template = new Template('<div>{{AI:content}}</div>');
     template.setSlot('content', 'Generate a welcome message');
-    console.log('  ✅ Template created');
-    console.log('  Slots:', template.getSlotNames());
+    await check('Template created', () => {
+        assert.deepEqual(template.getSlotNames(), ['content']);
+    });
+
+    // Test 1b: Slot metadata
+    console.log('\nTest 1b: Slot metadata');
+    await check('Slot metadata matches', () => {
+        const typed = new Template('<style>{{AI:styles:css}}</style><div>{{AI:body:html}}</div>{{AI:main:fn}}');
+        typed.setSlot('main', 'Write the entry point', 0.2, undefined, 256);
+        const slots = typed.getSlots();
+        const byName = Object.fromEntries(slots.map((s) => [s.name, s]));
+        assert.deepEqual(slots.map((s) => s.name), ['body', 'main', 'styles']);
+        assert.equal(byName.styles.kind, 'css');
+        assert.equal(byName.body.kind, 'html');
+        assert.equal(byName.main.prompt, 'Write the entry point');
+        assert.ok(Math.abs(byName.main.temperature - 0.2) < 1e-6);
+        assert.equal(byName.main.maxTokens, 256);
+        assert.equal(byName.body.maxTokens, undefined);
+    });
 
     // Test 2: Engine creation
     console.log('\nTest 2: Engine creation');
-    const engine = AetherEngine.openai('gpt-5.2-thinking');
-    console.log('  ✅ OpenAI engine created');
+    await check('OpenAI, Anthropic and Ollama engines created', () => {
+        AetherEngine.openai('gpt-5.2-thinking');
+        AetherEngine.anthropic();
+        AetherEngine.ollama('codellama');
+    });
+
+    // Test 2b: Engine close
+    console.log('\nTest 2b: Engine close');
+    await check('Closed engine rejects calls', async () => {
+        for (let i = 0; i < 50; i++) {
+            AetherEngine.ollama('codellama').close();
+        }
+        const ollamaEngine = AetherEngine.ollama('codellama');
+        ollamaEngine.close();
+        assert.ok(ollamaEngine.closed);
+        await assert.rejects(ollamaEngine.render(template));
+        await assert.rejects(ollamaEngine.healthCheck());
+    });
+
+    // Test 2c: Stream chunk caps
+    console.log('\nTest 2c: Stream chunk caps');
+    const server = await startOllamaStub(['fn ', 'main() ', '{ ', '}']);
+    const streamEngine = stubEngine(server);
+    const streamTemplate = new Template('{{AI:code}}');
+    streamTemplate.setSlot('code', 'Write a main function');
 
-    const anthropicEngine = AetherEngine.anthropic();
-    console.log('  ✅ Anthropic engine created');
+    await check('Uncapped stream collected', async () => {
+        const chunks = await streamEngine.getStreamChunks(streamTemplate, 'code');
+        assert.equal(chunks.join(''), 'fn main() { }');
+    });
+    for (const [limits, name] of [[{ maxChunks: 2 }, 'maxChunks'], [{ maxBytes: 5 }, 'maxBytes']]) {
+        await check(`${name} enforced`, async () => {
+            await assert.rejects(streamEngine.getStreamChunks(streamTemplate, 'code', limits), new RegExp(name));
+        });
+    }
+    streamEngine.close();
+    await stopStub(server);
+
+    // Test 2d: Renders reuse one provider and its connection pool
+    console.log('\nTest 2d: Connection reuse');
+    const poolServer = await startOllamaStub(['fn main() {}']);
+    let connections = 0;
+    poolServer.on('connection', () => connections++);
+    const poolEngine = stubEngine(poolServer);
+    await check('3 renders over 1 connection', async () => {
+        for (let i = 0; i < 3; i++) {
+            await poolEngine.render(streamTemplate);
+        }
+        assert.equal(connections, 1);
+    });
+    poolEngine.close();
+    await stopStub(poolServer);
 
-    const ollamaEngine = AetherEngine.ollama('codellama');
-    console.log('  ✅ Ollama engine created');
+    // Test 2e: Custom validator rejects a banned word and triggers a retry
+    console.log('\nTest 2e: Custom validator');
+    const healServer = await startOllamaSequenceStub(['<p>TODO: fill in</p>', '<p>Welcome!</p>']);
+    const healEngine = stubEngine(healServer);
+    healEngine.setHeal(true);
+    healEngine.setMaxRetries(2);
+    let validated = 0;
+    await check('Banned word rejected and regenerated', async () => {
+        healEngine.registerValidator('html', (code) => {
+            validated++;
+            return code.includes('TODO') ? 'code must not contain TODO' : true;
+        });
+        const healed = await healEngine.render(new Template('{{AI:greeting:html}}'));
+        assert.equal(healed, '<p>Welcome!</p>');
+        assert.equal(validated, 2);
+    });
+    await check('Unknown validator kind rejected', () => {
+        assert.throws(() => healEngine.registerValidator('htlm', () => true), /htlm/);
+    });
+    healEngine.close();
+    await stopStub(healServer);
 
     // Test 3: One-line generation (requires API key)
     console.log('\nTest 3: Code generation');
     if (process.env.OPENAI_API_KEY) {
-        try {
+        await check('Generated code', async () => {
             const code = await generate('Create a simple HTML button');
-            console.log('  ✅ Generated code:', code.substring(0, 50) + '...');
-        } catch (e) {
-            console.log('  ⚠️ Generation failed:', e.message);
-        }
+            assert.ok(code.length > 0);
+        });
     } else {
         console.log('  ⏭️ Skipped (OPENAI_API_KEY not set)');
     }
 
-    console.log('\n✅ All tests completed!');
+    if (failures > 0) {
+        console.log(`\n❌ ${failures} check(s) failed`);
+        process.exitCode = 1;
+    } else {
+        console.log('\n✅ All tests completed!');
+    }
 }
 
-main().catch(console.error);
+main().catch((e) => {
+    console.error(e);
+    process.exitCode = 1;
+});
@@ -2,9 +2,12 @@
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use aether_core::{
-    AetherRuntime, ProviderConfig, RenderSession as CoreRenderSession,
+    provider::{
+        DEFAULT_ANTHROPIC_MODEL, DEFAULT_COHERE_MODEL, DEFAULT_GEMINI_MODEL, DEFAULT_GROK_MODEL, DEFAULT_OLLAMA_MODEL, DEFAULT_OPENAI_MODEL,
+    },
+    AetherError, AetherRuntime, AiProvider, EngineParts, ProviderConfig, RenderSession as CoreRenderSession,
     cache::SemanticCache,
-    validation::RustValidator,
+    validation::{MultiValidator, RustValidator, Validator},
     AetherConfig,
     InjectionContext as CoreContext,
     InjectionEngine,
@@ -12,8 +15,9 @@ use aether_core::{
     Slot as CoreSlot,
     SlotKind,
 };
-use aether_ai::{OpenAiProvider, AnthropicProvider, GeminiProvider, OllamaProvider};
+use aether_ai::{OpenAiProvider, AnthropicProvider, CohereProvider, GeminiProvider, OllamaProvider};
 use std::collections::HashMap;
+use std::sync::Arc;
 use rhai::Dynamic;
 
 // ============================================================
@@ -26,6 +30,24 @@ enum ProviderKind {
     Gemini(GeminiProvider),
     Ollama(OllamaProvider),
     Grok(OpenAiProvider),  // Grok uses OpenAI-compatible API
+    Cohere(CohereProvider),
+}
+
+impl ProviderKind {
+    /// The wrapped provider behind a shared trait object.
+    ///
+    /// Cloning is cheap: `reqwest::Client` is reference-counted, so every
+    /// clone shares the provider's connection pool.
+    fn shared(&self) -> Arc<dyn AiProvider> {
+        match self {
+            ProviderKind::OpenAi(p) => Arc::new(p.clone()),
+            ProviderKind::Anthropic(p) => Arc::new(p.clone()),
+            ProviderKind::Gemini(p) => Arc::new(p.clone()),
+            ProviderKind::Ollama(p) => Arc::new(p.clone()),
+            ProviderKind::Grok(p) => Arc::new(p.clone()),
+            ProviderKind::Cohere(p) => Arc::new(p.clone()),
+        }
+    }
 }
 
 // ============================================================
@@ -83,6 +105,21 @@ impl RenderSession {
     fn clear(&mut self) {
         self.inner.results.clear();
     }
+
+    /// Save the cached results to a JSON file.
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .save(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// Load a session previously written by `save`.
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        let inner = CoreRenderSession::load(path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(RenderSession { inner })
+    }
 }
 
 // ============================================================
@@ -92,10 +129,12 @@ impl RenderSession {
 #[pyclass(unsendable)]
 struct Engine {
     provider: ProviderKind,
-    runtime: tokio::runtime::Runtime,
+    /// Owned Tokio runtime; `None` once the engine has been closed.
+    runtime: Option<tokio::runtime::Runtime>,
     config: AetherConfig,
     global_context: Option<CoreContext>,
     api_key_url: Option<String>,
+    validators: HashMap<SlotKind, PyObject>,
 }
 
 #[pymethods]
@@ -110,7 +149,7 @@ impl Engine {
                 let key = api_key.or_else(|| std::env::var("OPENAI_API_KEY").ok())
                     .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("OPENAI_API_KEY not set"))?;
                 let mod_name = model.or_else(|| std::env::var("OPENAI_MODEL").ok())
-                    .unwrap_or_else(|| "gpt-4o".to_string());
+                    .unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string());
                 let config = ProviderConfig::new(key, mod_name);
                 let p = OpenAiProvider::new(config).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
                 ProviderKind::OpenAi(p)
@@ -119,7 +158,7 @@ impl Engine {
                 let key = api_key.or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
                     .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("ANTHROPIC_API_KEY not set"))?;
                 let mod_name = model.or_else(|| std::env::var("ANTHROPIC_MODEL").ok())
-                    .unwrap_or_else(|| "claude-3-opus-20240229".to_string());
+                    .unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string());
                 let config = ProviderConfig::new(key, mod_name);
                 let p = AnthropicProvider::new(config).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
                 ProviderKind::Anthropic(p)
@@ -128,14 +167,14 @@ impl Engine {
                 let key = api_key.or_else(|| std::env::var("GOOGLE_API_KEY").ok())
                     .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("GOOGLE_API_KEY not set"))?;
                 let mod_name = model.or_else(|| std::env::var("GEMINI_MODEL").ok())
-                    .unwrap_or_else(|| "gemini-1.5-pro".to_string());
+                    .unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string());
                 let config = ProviderConfig::new(key, mod_name);
                 let p = GeminiProvider::new(config).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
                 ProviderKind::Gemini(p)
             },
             "ollama" => {
                 let mod_name = model.or_else(|| std::env::var("OLLAMA_MODEL").ok())
-                    .unwrap_or_else(|| "llama3".to_string());
+                    .unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string());
                 let p = OllamaProvider::new(mod_name);
                 ProviderKind::Ollama(p)
             },
@@ -143,24 +182,73 @@ impl Engine {
                 let key = api_key.or_else(|| std::env::var("XAI_API_KEY").ok())
                     .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("XAI_API_KEY not set"))?;
                 let mod_name = model.or_else(|| std::env::var("GROK_MODEL").ok())
-                    .unwrap_or_else(|| "grok-1".to_string());
+                    .unwrap_or_else(|| DEFAULT_GROK_MODEL.to_string());
                 let config = ProviderConfig::new(key, mod_name)
                     .with_base_url("https://api.x.ai/v1/chat/completions");
                 let p = OpenAiProvider::new(config).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
                 ProviderKind::Grok(p)
             },
+            "cohere" => {
+                let key = api_key.or_else(|| std::env::var("COHERE_API_KEY").ok())
+                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("COHERE_API_KEY not set"))?;
+                let mod_name = model.or_else(|| std::env::var("COHERE_MODEL").ok())
+                    .unwrap_or_else(|| DEFAULT_COHERE_MODEL.to_string());
+                let config = ProviderConfig::new(key, mod_name);
+                let p = CohereProvider::new(config).map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+                ProviderKind::Cohere(p)
+            },
             _ => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("Unknown provider: {}", provider))),
         };
 
         Ok(Engine { 
             provider: provider_kind, 
-            runtime: rt,
+            runtime: Some(rt),
             config: AetherConfig::default(),
             global_context: None,
             api_key_url: None,
+            validators: HashMap::new(),
         })
     }
 
+    /// Shut down the engine's Tokio runtime and release its worker threads.
+    ///
+    /// Pending tasks are given up to five seconds to finish. Calling `close`
+    /// more than once is a no-op; any later render call raises `RuntimeError`.
+    ///
+    /// # Example
+    /// ```python
+    /// with aether.Engine("ollama") as engine:
+    ///     engine.render(template)
+    /// # runtime is shut down here
+    /// ```
+    fn close(&mut self, py: Python<'_>) {
+        if let Some(rt) = self.runtime.take() {
+            py.allow_threads(|| rt.shutdown_timeout(std::time::Duration::from_secs(5)));
+        }
+    }
+
+    /// Whether `close` has already been called.
+    #[getter]
+    fn closed(&self) -> bool {
+        self.runtime.is_none()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.close(py);
+        false
+    }
+
     /// Enable or disable Self-Healing (automatic validation and retry).
     fn set_healing(&mut self, enabled: bool) {
         self.config.healing_enabled = enabled;
@@ -191,6 +279,16 @@ impl Engine {
         self.api_key_url = Some(url);
     }
 
+    /// Register a custom validator for slots of the given kind (e.g. "html").
+    ///
+    /// The callable receives the generated code and returns `None` or `True`
+    /// when it is valid, or `False` / an error message string to reject it.
+    /// Rejected code is regenerated with the error as feedback when healing is on.
+    fn register_validator(&mut self, kind: &str, validator: PyObject) {
+        let kind: SlotKind = kind.parse().unwrap_or_default();
+        self.validators.insert(kind, validator);
+    }
+
     /// Set global context for all generations.
     #[pyo3(signature = (project=None, language=None, framework=None))]
     fn set_context(&mut self, project: Option<String>, language: Option<String>, framework: Option<String>) {
@@ -208,69 +306,30 @@ impl Engine {
     }
 
     /// Render a template using the AI engine.
-    fn render(&self, template: &Template) -> PyResult<String> {
-        // Clone the provider so we can pass it to InjectionEngine
-        let healing = self.config.healing_enabled;
-        let caching = self.config.cache_enabled;
-        let toon = self.config.toon_enabled;
+    fn render(&self, py: Python<'_>, template: &Template) -> PyResult<String> {
         let template_inner = template.inner.clone();
+        let mut parts = self.engine_parts(self.custom_validator());
+        if self.config.cache_enabled {
+            parts.cache = Some(Arc::new(SemanticCache::new().map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?));
+        }
+        let engine = InjectionEngine::assemble(parts);
 
-        self.runtime.block_on(async {
-            // Build a fresh InjectionEngine with the stored flags
-            let result = match &self.provider {
-                ProviderKind::OpenAi(p) => {
-                    let mut p = p.clone();
-                    // p.config is private, we must rely on InjectionEngine passing the updated config globally
-                    // or if api_key_url needs to set on provider level, we might need a workaround.
-                    // However, OpenAiProvider::new uses the config we pass.
-                    // If we need to update URL late-bound:
-                    if let Some(ref url) = self.api_key_url {
-                        // Workaround: Re-create provider with new config if possible, OR
-                        // since we can't access p.config, we assume standard base URL unless initial config had it.
-                        // Actually aether-core v0.1.5's OpenAiProvider might not expose a setter.
-                        // We will rely on AetherConfig global overrides if implemented, or just use what we have.
-                    }
-                    let mut engine = InjectionEngine::with_config(p, self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render(&template_inner).await
-                },
-                ProviderKind::Anthropic(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render(&template_inner).await
-                },
-                ProviderKind::Gemini(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render(&template_inner).await
-                },
-                ProviderKind::Ollama(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render(&template_inner).await
-                },
-                ProviderKind::Grok(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    if self.config.cache_enabled && engine.cache().is_none() {
-                        engine = engine.with_cache(SemanticCache::new().map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?);
-                    }
-                    engine.render(&template_inner).await
-                },
-            };
+        // Release the GIL so custom validators can run on worker threads
+        let runtime = self.runtime()?;
 
-            result.map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-        })
+        py.allow_threads(|| runtime.block_on(engine.render(&template_inner)))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Check that the provider is reachable and accepts the configured key.
+    ///
+    /// Useful to fail fast (e.g. in CI) before a batch of renders.
+    fn health_check(&self, py: Python<'_>) -> PyResult<bool> {
+        let provider = self.provider.shared();
+        let runtime = self.runtime()?;
+
+        py.allow_threads(|| runtime.block_on(provider.health_check()))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
     }
 
     /// Render a template incrementally using a session to cache results.
@@ -290,50 +349,54 @@ impl Engine {
     /// template.add_slot("new_slot", "New prompt")
     /// result3 = engine.render_incremental(template, session)  # Only renders new_slot
     /// ```
-    fn render_incremental(&self, template: &Template, session: &mut RenderSession) -> PyResult<String> {
+    fn render_incremental(&self, py: Python<'_>, template: &Template, session: &mut RenderSession) -> PyResult<String> {
         let template_inner = template.inner.clone();
+        let engine = InjectionEngine::assemble(self.engine_parts(self.custom_validator()));
 
-        self.runtime.block_on(async {
-            let result = match &self.provider {
-                ProviderKind::OpenAi(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render_incremental(&template_inner, &mut session.inner).await
-                },
-                ProviderKind::Anthropic(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render_incremental(&template_inner, &mut session.inner).await
-                },
-                ProviderKind::Gemini(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render_incremental(&template_inner, &mut session.inner).await
-                },
-                ProviderKind::Ollama(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render_incremental(&template_inner, &mut session.inner).await
-                },
-                ProviderKind::Grok(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    engine.render_incremental(&template_inner, &mut session.inner).await
-                },
-            };
+        let runtime = self.runtime()?;
 
-            result.map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-        })
+        py.allow_threads(|| runtime.block_on(engine.render_incremental(&template_inner, &mut session.inner)))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Incrementally render a template, streaming only the slots that changed.
+    ///
+    /// Cached slots are passed to `callback` immediately as one chunk with their
+    /// full value; the others are streamed live and stored in the session.
+    /// An exception raised by the callback stops the render and is re-raised.
+    ///
+    /// # Example
+    /// ```python
+    /// def on_chunk(slot, chunk):
+    ///     print(f"[{slot}] {chunk}", end='', flush=True)
+    ///
+    /// engine.render_incremental_stream(template, session, on_chunk)
+    /// ```
+    #[pyo3(signature = (template, session, callback))]
+    fn render_incremental_stream(
+        &self,
+        template: &Template,
+        session: &mut RenderSession,
+        callback: PyObject,
+    ) -> PyResult<String> {
+        let template_inner = template.inner.clone();
+        let engine = InjectionEngine::assemble(self.engine_parts(None));
+        let runtime = self.runtime()?;
+
+        // Keep the callback's exception so it is re-raised as is
+        let mut callback_error = None;
+        let result = runtime.block_on(engine.render_incremental_stream(&template_inner, &mut session.inner, |slot, delta| {
+            Python::with_gil(|py| callback.call1(py, (slot, delta))).map(|_| ()).map_err(|e| {
+                let message = e.to_string();
+                callback_error = Some(e);
+                AetherError::RenderError(message)
+            })
+        }));
+
+        if let Some(e) = callback_error {
+            return Err(e);
+        }
+        result.map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
     }
 
     /// Deserialize a TOON string back into a JSON structure.
@@ -351,7 +414,7 @@ impl Engine {
     /// * `inputs` - Optional dictionary of input variables.
     /// 
     /// # Returns
-    /// The result of the script execution as a string.
+    /// The result of the script execution as a string (arrays and maps are JSON-encoded).
     #[pyo3(signature = (script, inputs=None))]
     fn execute_script(&self, script: &str, inputs: Option<&PyDict>) -> PyResult<String> {
         // Create a fresh AetherRuntime for each call (ensures thread safety)
@@ -378,6 +441,11 @@ impl Engine {
         let result = rhai_runtime.execute(script, rhai_inputs)
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
 
+        // Arrays and maps are returned as JSON so callers can json.loads them
+        if result.is_array() || result.is_map() {
+            return serde_json::to_string(&AetherRuntime::to_json(result))
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()));
+        }
         Ok(result.to_string())
     }
 
@@ -387,11 +455,14 @@ impl Engine {
     /// * `template` - The template to render.
     /// * `slot_name` - The name of the slot to stream (must have exactly one slot).
     /// * `callback` - A Python callable that receives each chunk as a string.
+    ///   Returning `False` stops generation; the text streamed so far is returned.
+    ///   An exception raised by the callback stops generation and is re-raised.
     /// 
     /// # Example
     /// ```python
     /// def on_chunk(chunk):
     ///     print(chunk, end='', flush=True)
+    ///     return not stop_requested
     /// 
     /// engine.render_stream(template, "code", on_chunk)
     /// ```
@@ -406,139 +477,81 @@ impl Engine {
         use futures::StreamExt;
         
         let template_inner = template.inner.clone();
-
-        self.runtime.block_on(async {
-            match &self.provider {
-                ProviderKind::OpenAi(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    let stream_result = engine.generate_slot_stream(&template_inner, &slot_name);
-                    match stream_result {
-                        Ok(mut stream) => {
-                            let mut full_result = String::new();
-                            while let Some(result) = stream.next().await {
-                                match result {
-                                    Ok(chunk) => {
-                                        full_result.push_str(&chunk.delta);
-                                        Python::with_gil(|py| {
-                                            let _ = callback.call1(py, (chunk.delta.clone(),));
-                                        });
-                                    }
-                                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
-                                }
-                            }
-                            Ok(full_result)
-                        }
-                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-                    }
-                },
-                ProviderKind::Anthropic(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    let stream_result = engine.generate_slot_stream(&template_inner, &slot_name);
-                    match stream_result {
-                        Ok(mut stream) => {
-                            let mut full_result = String::new();
-                            while let Some(result) = stream.next().await {
-                                match result {
-                                    Ok(chunk) => {
-                                        full_result.push_str(&chunk.delta);
-                                        Python::with_gil(|py| {
-                                            let _ = callback.call1(py, (chunk.delta.clone(),));
-                                        });
-                                    }
-                                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
-                                }
-                            }
-                            Ok(full_result)
-                        }
-                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-                    }
-                },
-                ProviderKind::Gemini(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    let stream_result = engine.generate_slot_stream(&template_inner, &slot_name);
-                    match stream_result {
-                        Ok(mut stream) => {
-                            let mut full_result = String::new();
-                            while let Some(result) = stream.next().await {
-                                match result {
-                                    Ok(chunk) => {
-                                        full_result.push_str(&chunk.delta);
-                                        Python::with_gil(|py| {
-                                            let _ = callback.call1(py, (chunk.delta.clone(),));
-                                        });
-                                    }
-                                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
-                                }
-                            }
-                            Ok(full_result)
+        let engine = InjectionEngine::assemble(self.engine_parts(None));
+        let runtime = self.runtime()?;
+
+        runtime.block_on(async {
+            let mut stream = engine
+                .generate_slot_stream(&template_inner, &slot_name)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+
+            let mut full_result = String::new();
+            while let Some(result) = stream.next().await {
+                match result {
+                    Ok(chunk) => {
+                        full_result.push_str(&chunk.delta);
+                        // Only an explicit `False` stops; `None` and other values continue
+                        let keep_going = callback
+                            .call1(py, (chunk.delta,))?
+                            .extract::<bool>(py)
+                            .unwrap_or(true);
+                        if !keep_going {
+                            // Dropping the stream cancels the request
+                            break;
                         }
-                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
                     }
-                },
-                ProviderKind::Ollama(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    let stream_result = engine.generate_slot_stream(&template_inner, &slot_name);
-                    match stream_result {
-                        Ok(mut stream) => {
-                            let mut full_result = String::new();
-                            while let Some(result) = stream.next().await {
-                                match result {
-                                    Ok(chunk) => {
-                                        full_result.push_str(&chunk.delta);
-                                        Python::with_gil(|py| {
-                                            let _ = callback.call1(py, (chunk.delta.clone(),));
-                                        });
-                                    }
-                                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
-                                }
-                            }
-                            Ok(full_result)
-                        }
-                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-                    }
-                },
-                ProviderKind::Grok(p) => {
-                    let mut engine = InjectionEngine::with_config(p.clone(), self.config.clone());
-                    if let Some(ref ctx) = self.global_context {
-                        engine = engine.with_context(ctx.clone());
-                    }
-                    let stream_result = engine.generate_slot_stream(&template_inner, &slot_name);
-                    match stream_result {
-                        Ok(mut stream) => {
-                            let mut full_result = String::new();
-                            while let Some(result) = stream.next().await {
-                                match result {
-                                    Ok(chunk) => {
-                                        full_result.push_str(&chunk.delta);
-                                        Python::with_gil(|py| {
-                                            let _ = callback.call1(py, (chunk.delta.clone(),));
-                                        });
-                                    }
-                                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
-                                }
-                            }
-                            Ok(full_result)
-                        }
-                        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
-                    }
-                },
+                    Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string())),
+                }
             }
+            Ok(full_result)
         })
     }
 }
 
+impl Engine {
+    /// The owned runtime, or an error if the engine has been closed.
+    fn runtime(&self) -> PyResult<&tokio::runtime::Runtime> {
+        self.runtime.as_ref().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Engine is closed")
+        })
+    }
+
+    /// Engine parts for one call: the stored provider, config, context and validator.
+    fn engine_parts(&self, validator: Option<MultiValidator>) -> EngineParts<dyn AiProvider> {
+        EngineParts {
+            config: self.config.clone(),
+            context: self.global_context.clone(),
+            validator: validator.map(|v| Arc::new(v) as Arc<dyn Validator>),
+            ..EngineParts::new(self.provider.shared())
+        }
+    }
+
+    /// Build a validator with the registered per-kind callbacks, if healing is on.
+    fn custom_validator(&self) -> Option<MultiValidator> {
+        if !self.config.healing_enabled || self.validators.is_empty() {
+            return None;
+        }
+
+        let callbacks = self.validators.iter().map(|(kind, callback)| {
+            let callback = callback.clone();
+            (kind.clone(), move |code: &str| {
+                Python::with_gil(|py| {
+                    let result = callback.call1(py, (code,)).map_err(|e| e.to_string())?;
+                    let result = result.as_ref(py);
+                    if result.is_none() {
+                        return Ok(());
+                    }
+                    if let Ok(valid) = result.extract::<bool>() {
+                        return if valid { Ok(()) } else { Err("Rejected by custom validator".to_string()) };
+                    }
+                    Err(result.str().map(|s| s.to_string()).unwrap_or_default())
+                })
+            })
+        });
+        Some(MultiValidator::new().with_kind_callbacks(callbacks))
+    }
+}
+
 // ============================================================
 // Module Registration (PyO3 0.20 style)
 // ============================================================
@@ -0,0 +1,47 @@
+import os
+import sys
+
+import pytest
+
+import aether
+
+
+def _thread_count():
+    return len(os.listdir("/proc/self/task"))
+
+
+def test_close_is_idempotent():
+    engine = aether.Engine("ollama")
+    assert not engine.closed
+    engine.close()
+    engine.close()
+    assert engine.closed
+
+
+def test_render_after_close_raises():
+    engine = aether.Engine("ollama")
+    engine.close()
+    template = aether.Template("{{AI:code}}")
+    template.add_slot("code", "noop")
+    with pytest.raises(RuntimeError, match="closed"):
+        engine.render(template)
+
+
+def test_health_check_after_close_raises():
+    engine = aether.Engine("ollama")
+    engine.close()
+    with pytest.raises(RuntimeError, match="closed"):
+        engine.health_check()
+
+
+@pytest.mark.skipif(not sys.platform.startswith("linux"), reason="uses /proc")
+def test_repeated_open_close_does_not_leak_threads():
+    with aether.Engine("ollama"):
+        pass
+    baseline = _thread_count()
+
+    for _ in range(50):
+        with aether.Engine("ollama"):
+            pass
+
+    assert _thread_count() <= baseline + 1
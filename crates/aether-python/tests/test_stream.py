@@ -0,0 +1,82 @@
+import json
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import pytest
+
+import aether
+
+CHUNKS = ["fn ", "main() ", "{ ", "println!(); ", "}"]
+
+
+class _OllamaStub(BaseHTTPRequestHandler):
+    def do_POST(self):
+        self.rfile.read(int(self.headers["Content-Length"]))
+        self.send_response(200)
+        self.send_header("Content-Type", "application/x-ndjson")
+        self.end_headers()
+        for i, chunk in enumerate(CHUNKS):
+            done = i == len(CHUNKS) - 1
+            line = json.dumps({"response": chunk, "done": done}) + "\n"
+            try:
+                self.wfile.write(line.encode())
+                self.wfile.flush()
+            except (BrokenPipeError, ConnectionResetError):
+                return
+
+    def log_message(self, *args):
+        pass
+
+
+@pytest.fixture
+def ollama_stub():
+    # The binding talks to Ollama's default address, so the stub takes its place
+    try:
+        server = HTTPServer(("127.0.0.1", 11434), _OllamaStub)
+    except OSError:
+        pytest.skip("port 11434 is in use (is Ollama running?)")
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    yield
+    server.shutdown()
+    server.server_close()
+
+
+def _template():
+    template = aether.Template("{{AI:code}}")
+    template.add_slot("code", "Write a main function")
+    return template
+
+
+def test_render_stream_collects_all_chunks(ollama_stub):
+    with aether.Engine("ollama") as engine:
+        result = engine.render_stream(_template(), "code", lambda chunk: None)
+    assert result == "".join(CHUNKS)
+
+
+def test_render_stream_stops_when_callback_returns_false(ollama_stub):
+    received = []
+
+    def on_chunk(chunk):
+        received.append(chunk)
+        return len(received) < 2
+
+    with aether.Engine("ollama") as engine:
+        result = engine.render_stream(_template(), "code", on_chunk)
+
+    assert received == CHUNKS[:2]
+    assert result == "fn main() "
+
+
+def test_render_stream_reraises_callback_exception(ollama_stub):
+    received = []
+
+    def on_chunk(chunk):
+        received.append(chunk)
+        raise ValueError("stop here")
+
+    with aether.Engine("ollama") as engine:
+        with pytest.raises(ValueError, match="stop here"):
+            engine.render_stream(_template(), "code", on_chunk)
+
+    assert received == CHUNKS[:1]